@@ -1,29 +1,20 @@
-use crate::common;
-use crate::stocks::data_company::DataCompany;
 use crate::stocks::data_company::StockData;
 use crate::stocks::data_company::StockDataWithNo;
 
 const MODULE_NAME: &str = "analysis::bullish_engulfing";
 
+/// date 是序列裡的 StockData.date，任何週期的 K 棒都可以
 pub fn find_bullish_engulfing_date_company(
-    data_company: &DataCompany,
+    stock_no: &str,
+    stock_data: &[StockData],
     date: &str,
 ) -> Vec<StockDataWithNo> {
     let mut results = Vec::new();
 
-    // find index of date in data_company.stock_data
-    let date_fugle_format = common::convert_date_to_fugle_format(date);
-    let curr_index = match data_company
-        .stock_data
-        .iter()
-        .position(|d| d.date == date_fugle_format)
-    {
+    let curr_index = match stock_data.iter().position(|d| d.date == date) {
         Some(i) => i,
         None => {
-            println!(
-                "[{MODULE_NAME}] 找不到 {} 日期 {} 的資料",
-                data_company.stock_no, date
-            );
+            println!("[{MODULE_NAME}] 找不到 {stock_no} 日期 {date} 的資料");
             return Vec::new();
         }
     };
@@ -31,19 +22,16 @@ pub fn find_bullish_engulfing_date_company(
     let prev_index = if curr_index >= 1 {
         curr_index - 1
     } else {
-        println!(
-            "[{MODULE_NAME}] {} 資料不足，無法計算 陽吞噬形態",
-            data_company.stock_no
-        );
+        println!("[{MODULE_NAME}] {stock_no} 資料不足，無法計算 陽吞噬形態");
         return Vec::new();
     };
 
-    let curr_data = &data_company.stock_data[curr_index];
-    let prev_data = &data_company.stock_data[prev_index];
+    let curr_data = &stock_data[curr_index];
+    let prev_data = &stock_data[prev_index];
 
     if is_bullish_engulfing(prev_data, curr_data) {
         results.push(StockDataWithNo {
-            stock_no: data_company.stock_no.clone(),
+            stock_no: stock_no.to_string(),
             stock_data: curr_data.clone(),
         });
     }
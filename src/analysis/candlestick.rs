@@ -13,7 +13,8 @@ pub enum CandlestickType {
     Unknown,
 }
 
-pub fn anal_candlestick(stock_data: &StockData) -> CandlestickType {
+/// long_body_ratio: 實體超過收盤價的這個比例才算長紅/長黑
+pub fn anal_candlestick(stock_data: &StockData, long_body_ratio: f64) -> CandlestickType {
     let open = stock_data.open;
     let close = stock_data.close;
     let high = stock_data.high;
@@ -23,7 +24,7 @@ pub fn anal_candlestick(stock_data: &StockData) -> CandlestickType {
     let upper_shadow = high - open.max(close);
     let lower_shadow = open.min(close) - low;
 
-    if body_length > 0.05 * close {
+    if body_length > long_body_ratio * close {
         if close > open {
             CandlestickType::LongRedCandle
         } else {
@@ -0,0 +1,40 @@
+use std::fmt;
+
+/// 快線由下往上穿過慢線是黃金交叉、由上往下是死亡交叉，MACD、KD 與均線共用
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum CrossType {
+    GoldenCross,
+    DeathCross,
+}
+
+impl fmt::Display for CrossType {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            CrossType::GoldenCross => write!(f, "黃金交叉"),
+            CrossType::DeathCross => write!(f, "死亡交叉"),
+        }
+    }
+}
+
+/// MACD、KD 的交叉紀錄，Zone 是交叉發生的位置 (零軸上下、高低檔)
+pub trait Cross {
+    type Zone: Copy + PartialEq;
+
+    fn date(&self) -> &str;
+    fn cross_type(&self) -> CrossType;
+    fn zone(&self) -> Self::Zone;
+}
+
+/// date 那根 K 棒有 cross_type 的交叉，zone 為 None 時不限位置
+pub fn crossed_on<C: Cross>(
+    crosses: &[C],
+    date: &str,
+    cross_type: CrossType,
+    zone: Option<C::Zone>,
+) -> bool {
+    crosses.iter().any(|cross| {
+        cross.date() == date
+            && cross.cross_type() == cross_type
+            && zone.is_none_or(|zone| cross.zone() == zone)
+    })
+}
@@ -0,0 +1,319 @@
+use std::cmp::Ordering;
+use std::fmt;
+
+use crate::analysis::macd::MacdResult;
+use crate::stocks::data_company::StockData;
+
+/// 前後各這麼多根 K 棒都沒有更低 (高) 才算波段低 (高) 點
+const SWING_STRENGTH: usize = 3;
+/// MACD 從頭開始算，前面這幾根的 EMA 還不穩定，不拿來比較
+const MACD_WARM_UP: usize = 26 + 9;
+/// 兩個波段點相隔太遠就不算背離
+const MAX_SWING_GAP: usize = 60;
+
+/// 波段高低點，price 是那根 K 棒的最高價 (波段高點) 或最低價 (波段低點)
+#[derive(Debug, Clone)]
+pub struct SwingPoint {
+    pub index: usize,
+    pub date: String,
+    pub price: f64,
+}
+
+/// 最低價比前後 strength 根都低的 K 棒；最後 strength 根還沒被確認，不會出現
+pub fn find_swing_lows(bars: &[StockData], strength: usize) -> Vec<SwingPoint> {
+    find_swings(bars, strength, |bar| bar.low, |a, b| a < b)
+}
+
+/// 最高價比前後 strength 根都高的 K 棒；最後 strength 根還沒被確認，不會出現
+pub fn find_swing_highs(bars: &[StockData], strength: usize) -> Vec<SwingPoint> {
+    find_swings(bars, strength, |bar| bar.high, |a, b| a > b)
+}
+
+fn find_swings(
+    bars: &[StockData],
+    strength: usize,
+    price: impl Fn(&StockData) -> f64,
+    beats: impl Fn(f64, f64) -> bool,
+) -> Vec<SwingPoint> {
+    if strength == 0 || bars.len() < strength * 2 + 1 {
+        return Vec::new();
+    }
+    (strength..bars.len() - strength)
+        .filter(|&i| {
+            let pivot = price(&bars[i]);
+            // 左邊要嚴格勝過，右邊平手也算，連續同價只取第一根
+            bars[i - strength..i]
+                .iter()
+                .all(|bar| beats(pivot, price(bar)))
+                && bars[i + 1..=i + strength]
+                    .iter()
+                    .all(|bar| !beats(price(bar), pivot))
+        })
+        .map(|i| SwingPoint {
+            index: i,
+            date: bars[i].date.clone(),
+            price: price(&bars[i]),
+        })
+        .collect()
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum DivergenceKind {
+    /// 價格創新低，指標低點墊高
+    RegularBullish,
+    /// 價格低點墊高，指標創新低
+    HiddenBullish,
+    /// 價格創新高，指標高點降低
+    RegularBearish,
+    /// 價格高點降低，指標創新高
+    HiddenBearish,
+}
+
+impl fmt::Display for DivergenceKind {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            DivergenceKind::RegularBullish => write!(f, "底背離"),
+            DivergenceKind::HiddenBullish => write!(f, "隱藏底背離"),
+            DivergenceKind::RegularBearish => write!(f, "頂背離"),
+            DivergenceKind::HiddenBearish => write!(f, "隱藏頂背離"),
+        }
+    }
+}
+
+/// 跟價格比較的 MACD 線
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum MacdLine {
+    Dif,
+    Histogram,
+}
+
+impl MacdLine {
+    fn value(self, result: &MacdResult) -> f64 {
+        match self {
+            MacdLine::Dif => result.dif,
+            MacdLine::Histogram => result.histogram,
+        }
+    }
+}
+
+impl fmt::Display for MacdLine {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            MacdLine::Dif => write!(f, "DIF"),
+            MacdLine::Histogram => write!(f, "柱狀體"),
+        }
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct Divergence {
+    pub kind: DivergenceKind,
+    pub line: MacdLine,
+    pub prev: SwingPoint,
+    pub curr: SwingPoint,
+    pub prev_value: f64,
+    pub curr_value: f64,
+}
+
+impl fmt::Display for Divergence {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "{} {}: {} {:.2} ({:.2}) → {} {:.2} ({:.2})",
+            self.line,
+            self.kind,
+            self.prev.date,
+            self.prev.price,
+            self.prev_value,
+            self.curr.date,
+            self.curr.price,
+            self.curr_value
+        )
+    }
+}
+
+/// 比較最近兩個波段低點與最近兩個波段高點，價格與 DIF、柱狀體方向相反就是背離
+///
+/// results 是 MacdCalculator::calc 的結果，對應 bars 的最後 results.len() 根
+pub fn find_divergences(bars: &[StockData], results: &[MacdResult]) -> Vec<Divergence> {
+    let Some(offset) = bars.len().checked_sub(results.len()) else {
+        return Vec::new();
+    };
+    // 只在 MACD 穩定之後的範圍找波段點
+    let start = offset + MACD_WARM_UP.min(results.len());
+    let window = &bars[start..];
+
+    let mut divergences = Vec::new();
+    let pairs = [
+        (find_swing_lows(window, SWING_STRENGTH), true),
+        (find_swing_highs(window, SWING_STRENGTH), false),
+    ];
+    for (swings, is_low) in pairs {
+        let [.., prev, curr] = swings.as_slice() else {
+            continue;
+        };
+        if curr.index - prev.index > MAX_SWING_GAP {
+            continue;
+        }
+        for line in [MacdLine::Dif, MacdLine::Histogram] {
+            let value = |swing: &SwingPoint| line.value(&results[start - offset + swing.index]);
+            let (prev_value, curr_value) = (value(prev), value(curr));
+            let price = curr.price.total_cmp(&prev.price);
+            let indicator = curr_value.total_cmp(&prev_value);
+            let kind = match (is_low, price, indicator) {
+                (true, Ordering::Less, Ordering::Greater) => DivergenceKind::RegularBullish,
+                (true, Ordering::Greater, Ordering::Less) => DivergenceKind::HiddenBullish,
+                (false, Ordering::Greater, Ordering::Less) => DivergenceKind::RegularBearish,
+                (false, Ordering::Less, Ordering::Greater) => DivergenceKind::HiddenBearish,
+                _ => continue,
+            };
+            divergences.push(Divergence {
+                kind,
+                line,
+                prev: SwingPoint {
+                    index: start + prev.index,
+                    ..prev.clone()
+                },
+                curr: SwingPoint {
+                    index: start + curr.index,
+                    ..curr.clone()
+                },
+                prev_value,
+                curr_value,
+            });
+        }
+    }
+
+    divergences
+}
+
+/// 最後一根 K 棒剛好確認了第二個波段點的背離，也就是這一天才出現的背離
+pub fn find_divergences_on_last_bar(bars: &[StockData], results: &[MacdResult]) -> Vec<Divergence> {
+    let Some(confirmed) = bars.len().checked_sub(SWING_STRENGTH + 1) else {
+        return Vec::new();
+    };
+    find_divergences(bars, results)
+        .into_iter()
+        .filter(|divergence| divergence.curr.index == confirmed)
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::analysis::test_bars;
+
+    const BARS: usize = 60;
+
+    /// 平盤的 K 棒，只在 swings 的位置放波段低點 (或高點) 與對應的 DIF、柱狀體
+    fn fixture(swings: &[(usize, f64, f64)], lows: bool) -> (Vec<StockData>, Vec<MacdResult>) {
+        let mut bars = test_bars::bars(&[100.0; BARS]);
+        let mut results: Vec<MacdResult> = bars
+            .iter()
+            .map(|bar| MacdResult {
+                date: bar.date.clone(),
+                dif: 0.0,
+                macd_signal: 0.0,
+                histogram: 0.0,
+            })
+            .collect();
+        for &(index, price, value) in swings {
+            if lows {
+                bars[index].low = price;
+            } else {
+                bars[index].high = price;
+            }
+            results[index].dif = value;
+            results[index].histogram = value;
+        }
+        (bars, results)
+    }
+
+    fn kinds(divergences: &[Divergence]) -> Vec<(DivergenceKind, MacdLine)> {
+        divergences.iter().map(|d| (d.kind, d.line)).collect()
+    }
+
+    #[test]
+    fn swing_points_need_strength_bars_on_both_sides() {
+        let (bars, _) = fixture(&[(40, 90.0, 0.0), (41, 90.0, 0.0), (57, 80.0, 0.0)], true);
+
+        let lows = find_swing_lows(&bars, SWING_STRENGTH);
+
+        // 連續同價只取第一根，最後 3 根內的低點還沒確認
+        assert_eq!(lows.len(), 1);
+        assert_eq!(lows[0].index, 40);
+        assert_eq!(lows[0].date, bars[40].date);
+        assert_eq!(lows[0].price, 90.0);
+        assert!(find_swing_highs(&bars, SWING_STRENGTH).is_empty());
+    }
+
+    #[test]
+    fn regular_bullish() {
+        let (bars, results) = fixture(&[(40, 90.0, -2.0), (50, 85.0, -1.0)], true);
+        let divergences = find_divergences(&bars, &results);
+        assert_eq!(
+            kinds(&divergences),
+            [
+                (DivergenceKind::RegularBullish, MacdLine::Dif),
+                (DivergenceKind::RegularBullish, MacdLine::Histogram),
+            ]
+        );
+        assert_eq!(divergences[0].prev.index, 40);
+        assert_eq!(divergences[0].curr.index, 50);
+    }
+
+    #[test]
+    fn hidden_bullish() {
+        let (bars, results) = fixture(&[(40, 85.0, -1.0), (50, 90.0, -2.0)], true);
+        assert_eq!(
+            kinds(&find_divergences(&bars, &results)),
+            [
+                (DivergenceKind::HiddenBullish, MacdLine::Dif),
+                (DivergenceKind::HiddenBullish, MacdLine::Histogram),
+            ]
+        );
+    }
+
+    #[test]
+    fn regular_bearish() {
+        let (bars, results) = fixture(&[(40, 110.0, 2.0), (50, 115.0, 1.0)], false);
+        assert_eq!(
+            kinds(&find_divergences(&bars, &results)),
+            [
+                (DivergenceKind::RegularBearish, MacdLine::Dif),
+                (DivergenceKind::RegularBearish, MacdLine::Histogram),
+            ]
+        );
+    }
+
+    #[test]
+    fn hidden_bearish() {
+        let (bars, results) = fixture(&[(40, 115.0, 1.0), (50, 110.0, 2.0)], false);
+        assert_eq!(
+            kinds(&find_divergences(&bars, &results)),
+            [
+                (DivergenceKind::HiddenBearish, MacdLine::Dif),
+                (DivergenceKind::HiddenBearish, MacdLine::Histogram),
+            ]
+        );
+    }
+
+    #[test]
+    fn same_direction_is_not_a_divergence() {
+        let (bars, results) = fixture(&[(40, 90.0, -1.0), (50, 85.0, -2.0)], true);
+        assert!(find_divergences(&bars, &results).is_empty());
+    }
+
+    #[test]
+    fn reported_on_the_bar_that_confirms_the_swing() {
+        let (bars, results) = fixture(&[(40, 90.0, -2.0), (50, 85.0, -1.0)], true);
+        let until = |len: usize| find_divergences_on_last_bar(&bars[..len], &results[..len]);
+
+        // 第 50 根要等到後面 3 根都沒有更低，第 53 根收盤才確認
+        assert!(until(53).is_empty());
+        assert_eq!(until(54).len(), 2);
+        assert!(until(54).iter().all(|d| d.curr.index == 50));
+        // 隔天就不是剛出現的背離
+        assert!(until(55).is_empty());
+    }
+}
@@ -0,0 +1,346 @@
+use crate::cfg::data::PriceView;
+use crate::stocks::data_company::{DataCompany, StockData};
+
+pub const RSI_PERIOD: usize = 14;
+pub const BOLLINGER_PERIOD: usize = 20;
+/// 布林通道上下軌離中軌幾個標準差
+pub const BOLLINGER_WIDTH: f64 = 2.0;
+pub const ATR_PERIOD: usize = 14;
+pub const MFI_PERIOD: usize = 14;
+pub const DMI_PERIOD: usize = 14;
+
+#[derive(Debug, Clone, Copy)]
+pub struct Bollinger {
+    /// 中軌 = 收盤價的簡單移動平均
+    pub middle: f64,
+    pub upper: f64,
+    pub lower: f64,
+    /// %B = (收盤價 - 下軌) / (上軌 - 下軌)，0 是下軌、1 是上軌
+    pub percent_b: f64,
+    /// 帶寬 = (上軌 - 下軌) / 中軌，越小代表通道越窄
+    pub bandwidth: f64,
+}
+
+#[derive(Debug, Clone, Copy)]
+pub struct Dmi {
+    pub plus_di: f64,
+    pub minus_di: f64,
+    /// ADX 要再多平滑一輪，前面幾根只有 DI 沒有 ADX
+    pub adx: Option<f64>,
+}
+
+/// 某根 K 棒的所有指標，資料不夠算的是 None
+#[derive(Debug, Clone)]
+pub struct IndicatorValues {
+    pub date: String,
+    pub rsi: Option<f64>,
+    pub bollinger: Option<Bollinger>,
+    pub atr: Option<f64>,
+    pub obv: i64,
+    pub mfi: Option<f64>,
+    pub dmi: Option<Dmi>,
+}
+
+/// 跟 K 棒一對一對齊的指標序列，第 i 個值就是第 i 根 K 棒的指標
+pub struct Indicators {
+    dates: Vec<String>,
+    pub rsi: Vec<Option<f64>>,
+    pub bollinger: Vec<Option<Bollinger>>,
+    pub atr: Vec<Option<f64>>,
+    pub obv: Vec<i64>,
+    pub mfi: Vec<Option<f64>>,
+    pub dmi: Vec<Option<Dmi>>,
+}
+
+impl Indicators {
+    /// bars 要依日期排序，用預設的週期計算
+    pub fn new(bars: &[StockData]) -> Self {
+        Indicators {
+            dates: bars.iter().map(|bar| bar.date.clone()).collect(),
+            rsi: rsi(bars, RSI_PERIOD),
+            bollinger: bollinger(bars, BOLLINGER_PERIOD, BOLLINGER_WIDTH),
+            atr: atr(bars, ATR_PERIOD),
+            obv: obv(bars),
+            mfi: mfi(bars, MFI_PERIOD),
+            dmi: dmi(bars, DMI_PERIOD),
+        }
+    }
+
+    pub fn from_company(data_company: &DataCompany, view: PriceView) -> Self {
+        Indicators::new(&data_company.view(view))
+    }
+
+    /// date 是 K 棒的 StockData.date，所有序列共用這個位置
+    pub fn index(&self, date: &str) -> Option<usize> {
+        date_index(&self.dates, date)
+    }
+
+    pub fn at_index(&self, index: usize) -> Option<IndicatorValues> {
+        Some(IndicatorValues {
+            date: self.dates.get(index)?.clone(),
+            rsi: self.rsi[index],
+            bollinger: self.bollinger[index],
+            atr: self.atr[index],
+            obv: self.obv[index],
+            mfi: self.mfi[index],
+            dmi: self.dmi[index],
+        })
+    }
+}
+
+/// 依日期排序的序列裡找 date 的位置
+pub fn date_index(dates: &[String], date: &str) -> Option<usize> {
+    dates.binary_search_by(|d| d.as_str().cmp(date)).ok()
+}
+
+/// Wilder 平滑: 前 period 個值取平均當起點，之後 (前值 * (period - 1) + 新值) / period
+fn wilder_smooth(values: &[f64], period: usize) -> Vec<Option<f64>> {
+    let mut smoothed = vec![None; values.len()];
+    if period == 0 || values.len() < period {
+        return smoothed;
+    }
+    let mut average = values[..period].iter().sum::<f64>() / period as f64;
+    smoothed[period - 1] = Some(average);
+    for (i, value) in values.iter().enumerate().skip(period) {
+        average = (average * (period - 1) as f64 + value) / period as f64;
+        smoothed[i] = Some(average);
+    }
+    smoothed
+}
+
+/// 第 i 根與前一根比較的值，第 0 根沒有前一根
+fn with_prev<T>(bars: &[StockData], f: impl Fn(&StockData, &StockData) -> T) -> Vec<T> {
+    bars.windows(2).map(|w| f(&w[0], &w[1])).collect()
+}
+
+/// 比較的序列少了第 0 根，補回去跟 K 棒對齊
+fn align_after_first<T>(bars: &[StockData], values: Vec<Option<T>>) -> Vec<Option<T>> {
+    if bars.is_empty() {
+        return Vec::new();
+    }
+    std::iter::once(None).chain(values).collect()
+}
+
+/// RSI，用 Wilder 平滑的平均漲幅與平均跌幅
+pub fn rsi(bars: &[StockData], period: usize) -> Vec<Option<f64>> {
+    let gains = with_prev(bars, |prev, curr| (curr.close - prev.close).max(0.0));
+    let losses = with_prev(bars, |prev, curr| (prev.close - curr.close).max(0.0));
+    let values = wilder_smooth(&gains, period)
+        .into_iter()
+        .zip(wilder_smooth(&losses, period))
+        .map(|(gain, loss)| {
+            let (gain, loss) = (gain?, loss?);
+            Some(if gain + loss == 0.0 {
+                50.0
+            } else {
+                100.0 * gain / (gain + loss)
+            })
+        })
+        .collect();
+    align_after_first(bars, values)
+}
+
+/// 布林通道，標準差用母體標準差 (除以 period)
+pub fn bollinger(bars: &[StockData], period: usize, width: f64) -> Vec<Option<Bollinger>> {
+    let mut bands = vec![None; bars.len()];
+    if period == 0 {
+        return bands;
+    }
+    for (i, window) in bars.windows(period).enumerate() {
+        let middle = window.iter().map(|bar| bar.close).sum::<f64>() / period as f64;
+        let variance = window
+            .iter()
+            .map(|bar| (bar.close - middle).powi(2))
+            .sum::<f64>()
+            / period as f64;
+        let upper = middle + width * variance.sqrt();
+        let lower = middle - width * variance.sqrt();
+        let close = window[period - 1].close;
+        bands[i + period - 1] = Some(Bollinger {
+            middle,
+            upper,
+            lower,
+            // 通道寬度是 0 時收盤價就在中軌
+            percent_b: if upper > lower {
+                (close - lower) / (upper - lower)
+            } else {
+                0.5
+            },
+            bandwidth: if middle != 0.0 {
+                (upper - lower) / middle
+            } else {
+                0.0
+            },
+        });
+    }
+    bands
+}
+
+fn true_range(prev: &StockData, curr: &StockData) -> f64 {
+    (curr.high - curr.low)
+        .max((curr.high - prev.close).abs())
+        .max((curr.low - prev.close).abs())
+}
+
+/// ATR，真實波幅的 Wilder 平均
+pub fn atr(bars: &[StockData], period: usize) -> Vec<Option<f64>> {
+    align_after_first(bars, wilder_smooth(&with_prev(bars, true_range), period))
+}
+
+/// OBV，收漲加上成交量、收跌減掉成交量，從 0 開始累計
+pub fn obv(bars: &[StockData]) -> Vec<i64> {
+    let mut total = 0i64;
+    let mut values = Vec::with_capacity(bars.len());
+    values.extend(bars.first().map(|_| 0));
+    for w in bars.windows(2) {
+        let volume = w[1].volume as i64;
+        if w[1].close > w[0].close {
+            total += volume;
+        } else if w[1].close < w[0].close {
+            total -= volume;
+        }
+        values.push(total);
+    }
+    values
+}
+
+/// MFI，典型價格 (高 + 低 + 收) / 3 乘上成交量，比較 period 根內上漲與下跌的資金流
+pub fn mfi(bars: &[StockData], period: usize) -> Vec<Option<f64>> {
+    let typical = |bar: &StockData| (bar.high + bar.low + bar.close) / 3.0;
+    let flows = with_prev(bars, |prev, curr| {
+        let flow = typical(curr) * curr.volume as f64;
+        match typical(curr).total_cmp(&typical(prev)) {
+            std::cmp::Ordering::Greater => (flow, 0.0),
+            std::cmp::Ordering::Less => (0.0, flow),
+            std::cmp::Ordering::Equal => (0.0, 0.0),
+        }
+    });
+
+    let mut values = vec![None; flows.len()];
+    if period > 0 {
+        for (i, window) in flows.windows(period).enumerate() {
+            let positive: f64 = window.iter().map(|(up, _)| up).sum();
+            let negative: f64 = window.iter().map(|(_, down)| down).sum();
+            values[i + period - 1] = Some(if positive + negative == 0.0 {
+                50.0
+            } else {
+                100.0 * positive / (positive + negative)
+            });
+        }
+    }
+    align_after_first(bars, values)
+}
+
+/// DMI (+DI、-DI) 與 ADX，都用 Wilder 平滑
+pub fn dmi(bars: &[StockData], period: usize) -> Vec<Option<Dmi>> {
+    let plus_dm = with_prev(bars, |prev, curr| {
+        let up = curr.high - prev.high;
+        let down = prev.low - curr.low;
+        if up > down && up > 0.0 { up } else { 0.0 }
+    });
+    let minus_dm = with_prev(bars, |prev, curr| {
+        let up = curr.high - prev.high;
+        let down = prev.low - curr.low;
+        if down > up && down > 0.0 { down } else { 0.0 }
+    });
+    let tr = wilder_smooth(&with_prev(bars, true_range), period);
+    let plus = wilder_smooth(&plus_dm, period);
+    let minus = wilder_smooth(&minus_dm, period);
+
+    let di: Vec<Option<(f64, f64)>> = tr
+        .iter()
+        .zip(plus.iter().zip(&minus))
+        .map(|(tr, (plus, minus))| {
+            let tr = (*tr)?;
+            // 完全沒有波動時 DI 都是 0
+            Some(if tr > 0.0 {
+                (100.0 * (*plus)? / tr, 100.0 * (*minus)? / tr)
+            } else {
+                (0.0, 0.0)
+            })
+        })
+        .collect();
+    let first = di.iter().position(Option::is_some);
+    let dx: Vec<f64> = di
+        .iter()
+        .flatten()
+        .map(|(plus_di, minus_di)| {
+            let sum = plus_di + minus_di;
+            if sum > 0.0 {
+                100.0 * (plus_di - minus_di).abs() / sum
+            } else {
+                0.0
+            }
+        })
+        .collect();
+    let adx = wilder_smooth(&dx, period);
+
+    let values = di
+        .iter()
+        .enumerate()
+        .map(|(i, di)| {
+            let (plus_di, minus_di) = (*di)?;
+            Some(Dmi {
+                plus_di,
+                minus_di,
+                adx: first.and_then(|first| adx[i - first]),
+            })
+        })
+        .collect();
+    align_after_first(bars, values)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::analysis::test_bars;
+
+    #[test]
+    fn series_are_aligned_with_bars() {
+        let bars: Vec<StockData> = (0..30)
+            .map(|day| test_bars::bar(day, 100.0 + day as f64))
+            .collect();
+
+        let indicators = Indicators::new(&bars);
+
+        assert_eq!(indicators.rsi.len(), bars.len());
+        assert_eq!(indicators.dmi.len(), bars.len());
+        // RSI 要有 period 個漲跌 (period + 1 根)，布林要有 period 根收盤價
+        assert!(indicators.rsi[RSI_PERIOD - 1].is_none());
+        assert_eq!(indicators.rsi[RSI_PERIOD], Some(100.0));
+        assert!(indicators.bollinger[BOLLINGER_PERIOD - 2].is_none());
+        assert!(indicators.bollinger[BOLLINGER_PERIOD - 1].is_some());
+        assert_eq!(indicators.index(&test_bars::date(14)), Some(14));
+        assert!(indicators.index(&test_bars::date(30)).is_none());
+
+        let values = indicators.at_index(29).unwrap();
+        assert_eq!(values.obv, 29_000);
+        assert_eq!(values.mfi, Some(100.0));
+        // 每天高低點都往上移 1，ATR 是 2 (前收到今高)
+        assert!((values.atr.unwrap() - 2.0).abs() < 1e-9);
+        let dmi = values.dmi.unwrap();
+        assert_eq!(dmi.minus_di, 0.0);
+        assert!((dmi.adx.unwrap() - 100.0).abs() < 1e-9);
+    }
+
+    #[test]
+    fn flat_prices_have_neutral_readings() {
+        let bars: Vec<StockData> = (0..30).map(|day| test_bars::bar(day, 50.0)).collect();
+
+        let values = Indicators::new(&bars).at_index(29).unwrap();
+
+        assert_eq!(values.rsi, Some(50.0));
+        assert_eq!(values.obv, 0);
+        let bollinger = values.bollinger.unwrap();
+        assert_eq!(bollinger.percent_b, 0.5);
+        assert_eq!(bollinger.bandwidth, 0.0);
+    }
+
+    #[test]
+    fn empty_bars_give_empty_series() {
+        let indicators = Indicators::new(&[]);
+        assert!(indicators.rsi.is_empty());
+        assert!(indicators.atr.is_empty());
+        assert!(indicators.at_index(0).is_none());
+    }
+}
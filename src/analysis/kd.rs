@@ -0,0 +1,321 @@
+use std::fmt;
+
+use crate::analysis::cross::{Cross, CrossType};
+use crate::stocks::data_company::StockData;
+
+const MODULE_NAME: &str = "analysis::kd";
+const INTERVALS: usize = 100;
+/// KD(9,3,3): RSV 看 9 根，K、D 都用 1/3 的權重平滑
+const RSV_PERIOD: usize = 9;
+const SMOOTHING: f64 = 3.0;
+/// 還沒有前一天的 K、D 時用 50 起算
+const INITIAL_VALUE: f64 = 50.0;
+const OVERBOUGHT: f64 = 80.0;
+const OVERSOLD: f64 = 20.0;
+/// 連續幾根 K 值在 80 以上 (20 以下) 算鈍化
+const PASSIVATION_BARS: usize = 3;
+
+/// 交叉時 K 值的位置，80 以上為高檔、20 以下為低檔
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum KdZone {
+    High,
+    Middle,
+    Low,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum KdPassivation {
+    /// K 值連續在 80 以上，強勢股常見，不急著賣
+    High,
+    /// K 值連續在 20 以下，弱勢股常見，不急著買
+    Low,
+}
+
+impl fmt::Display for KdZone {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            KdZone::High => write!(f, "高檔 ({OVERBOUGHT} 以上)"),
+            KdZone::Middle => write!(f, "中間"),
+            KdZone::Low => write!(f, "低檔 ({OVERSOLD} 以下)"),
+        }
+    }
+}
+
+impl fmt::Display for KdPassivation {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            KdPassivation::High => write!(f, "高檔鈍化"),
+            KdPassivation::Low => write!(f, "低檔鈍化"),
+        }
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct KdCross {
+    pub date: String,
+    pub k: f64,
+    pub d: f64,
+    pub cross_type: CrossType,
+    pub zone: KdZone,
+}
+
+impl Cross for KdCross {
+    type Zone = KdZone;
+
+    fn date(&self) -> &str {
+        &self.date
+    }
+
+    fn cross_type(&self) -> CrossType {
+        self.cross_type
+    }
+
+    fn zone(&self) -> KdZone {
+        self.zone
+    }
+}
+
+impl fmt::Display for KdCross {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "{}{} (K {:.1} / D {:.1})",
+            self.zone, self.cross_type, self.k, self.d
+        )
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct KdResult {
+    pub date: String,
+    pub k: f64,
+    pub d: f64,
+}
+
+/// 最後幾根 K 值都在 80 以上或都在 20 以下
+pub fn passivation(results: &[KdResult]) -> Option<KdPassivation> {
+    let recent = results.get(results.len().checked_sub(PASSIVATION_BARS)?..)?;
+    if recent.iter().all(|r| r.k > OVERBOUGHT) {
+        Some(KdPassivation::High)
+    } else if recent.iter().all(|r| r.k < OVERSOLD) {
+        Some(KdPassivation::Low)
+    } else {
+        None
+    }
+}
+
+pub struct KdCalculator {
+    stock_no: String,
+    prev_k: f64,
+    prev_d: f64,
+}
+
+impl KdCalculator {
+    pub fn new(stock_no: String) -> Self {
+        Self {
+            stock_no,
+            prev_k: INITIAL_VALUE,
+            prev_d: INITIAL_VALUE,
+        }
+    }
+
+    pub fn reset(&mut self) {
+        self.prev_k = INITIAL_VALUE;
+        self.prev_d = INITIAL_VALUE;
+    }
+
+    /// 計算到 date 那根 K 棒為止，date 是序列裡的 StockData.date，日K或分K都可以
+    pub fn calc(
+        &mut self,
+        stock_data: &[StockData],
+        date: &str,
+    ) -> Option<(Vec<KdResult>, Vec<KdCross>)> {
+        self.reset();
+
+        let mut results = Vec::new();
+        let mut kd_crosses = Vec::new();
+
+        let end_index = match stock_data.iter().position(|d| d.date == date) {
+            Some(i) => i,
+            None => {
+                println!(
+                    "[{MODULE_NAME}] 找不到 {} 日期 {} 的資料",
+                    self.stock_no, date
+                );
+                return None;
+            }
+        };
+
+        // 第一根也要有完整的 9 根算 RSV
+        let start_index = if end_index >= INTERVALS + RSV_PERIOD - 1 {
+            end_index - INTERVALS
+        } else {
+            println!("[{MODULE_NAME}] {} 資料不足，無法計算 KD", self.stock_no);
+            return None;
+        };
+
+        for index in start_index..=end_index {
+            let period = &stock_data[index + 1 - RSV_PERIOD..=index];
+            let (res, cross) = self.feed(period);
+            results.push(res);
+            // 第一根的前一天是預設的 50，不算交叉
+            if let Some(c) = cross
+                && index > start_index
+            {
+                kd_crosses.push(c);
+            }
+        }
+
+        Some((results, kd_crosses))
+    }
+
+    /// period 是最近 9 根，最後一根是當天
+    fn feed(&mut self, period: &[StockData]) -> (KdResult, Option<KdCross>) {
+        let today = &period[period.len() - 1];
+        let highest = period.iter().map(|d| d.high).fold(f64::MIN, f64::max);
+        let lowest = period.iter().map(|d| d.low).fold(f64::MAX, f64::min);
+        // 9 根都同價 (例如連續一字板) 時沒有區間，視為 50
+        let rsv = if highest > lowest {
+            (today.close - lowest) / (highest - lowest) * 100.0
+        } else {
+            INITIAL_VALUE
+        };
+        let k = self.prev_k * (SMOOTHING - 1.0) / SMOOTHING + rsv / SMOOTHING;
+        let d = self.prev_d * (SMOOTHING - 1.0) / SMOOTHING + k / SMOOTHING;
+
+        let res = KdResult {
+            date: today.date.clone(),
+            k,
+            d,
+        };
+
+        let cross_type = if self.prev_k <= self.prev_d && k > d {
+            Some(CrossType::GoldenCross)
+        } else if self.prev_k >= self.prev_d && k < d {
+            Some(CrossType::DeathCross)
+        } else {
+            None
+        };
+        let cross = cross_type.map(|cross_type| KdCross {
+            date: today.date.clone(),
+            k,
+            d,
+            cross_type,
+            zone: if k > OVERBOUGHT {
+                KdZone::High
+            } else if k < OVERSOLD {
+                KdZone::Low
+            } else {
+                KdZone::Middle
+            },
+        });
+
+        self.prev_k = k;
+        self.prev_d = d;
+        (res, cross)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::analysis::test_bars;
+
+    /// 每天漲跌 step 走 120 根，最後一根反向 reversal
+    fn trend(step: f64, reversal: f64) -> Vec<StockData> {
+        let mut closes: Vec<f64> = (0..120).map(|day| 500.0 + step * day as f64).collect();
+        closes.push(closes[119] + reversal);
+        test_bars::bars(&closes)
+    }
+
+    fn calc(bars: &[StockData]) -> (Vec<KdResult>, Vec<KdCross>) {
+        let mut calculator = KdCalculator::new("test".to_string());
+        calculator.calc(bars, &bars[bars.len() - 1].date).unwrap()
+    }
+
+    #[test]
+    fn smooths_rsv_like_brokers() {
+        // 每天都收最高，RSV 一直是 100
+        let bars: Vec<StockData> = (0..120)
+            .map(|day| {
+                let close = 100.0 + day as f64;
+                StockData {
+                    high: close,
+                    ..test_bars::bar(day, close)
+                }
+            })
+            .collect();
+
+        let (results, crosses) = calc(&bars);
+
+        assert_eq!(results.len(), INTERVALS + 1);
+        // K = 2/3 * 50 + 1/3 * 100，D = 2/3 * 50 + 1/3 * K
+        assert!((results[0].k - 66.666_666).abs() < 1e-4);
+        assert!((results[0].d - 55.555_555).abs() < 1e-4);
+        assert!((results[1].k - 77.777_777).abs() < 1e-4);
+        assert!(crosses.is_empty());
+        assert_eq!(passivation(&results), Some(KdPassivation::High));
+    }
+
+    #[test]
+    fn flat_range_counts_as_fifty() {
+        let bars: Vec<StockData> = (0..120)
+            .map(|day| StockData {
+                high: 100.0,
+                low: 100.0,
+                ..test_bars::bar(day, 100.0)
+            })
+            .collect();
+
+        let (results, crosses) = calc(&bars);
+
+        assert!(results.iter().all(|r| r.k == 50.0 && r.d == 50.0));
+        assert!(crosses.is_empty());
+        assert_eq!(passivation(&results), None);
+    }
+
+    #[test]
+    fn golden_cross_below_twenty() {
+        let bars = trend(-2.0, 6.0);
+
+        let (results, crosses) = calc(&bars);
+
+        let last = results.last().unwrap();
+        assert!(last.k > last.d && last.k < OVERSOLD);
+        let cross = crosses.last().unwrap();
+        assert_eq!(cross.date, last.date);
+        assert_eq!(cross.cross_type, CrossType::GoldenCross);
+        assert_eq!(cross.zone, KdZone::Low);
+        // 只反彈一根，前面仍是低檔鈍化
+        assert_eq!(
+            passivation(&results[..results.len() - 1]),
+            Some(KdPassivation::Low)
+        );
+    }
+
+    #[test]
+    fn death_cross_above_eighty() {
+        let bars = trend(2.0, -6.0);
+
+        let (results, crosses) = calc(&bars);
+
+        let last = results.last().unwrap();
+        assert!(last.k < last.d && last.k > OVERBOUGHT);
+        let cross = crosses.last().unwrap();
+        assert_eq!(cross.date, last.date);
+        assert_eq!(cross.cross_type, CrossType::DeathCross);
+        assert_eq!(cross.zone, KdZone::High);
+    }
+
+    #[test]
+    fn passivation_needs_consecutive_bars() {
+        let (results, _) = calc(&trend(-2.0, -2.0));
+        assert_eq!(passivation(&results), Some(KdPassivation::Low));
+
+        // 最後一根大漲拉出低檔，就不算鈍化
+        let (results, _) = calc(&trend(-2.0, 30.0));
+        assert!(results.last().unwrap().k >= OVERSOLD);
+        assert_eq!(passivation(&results), None);
+        assert_eq!(passivation(&results[..2]), None);
+    }
+}
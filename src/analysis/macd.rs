@@ -1,32 +1,87 @@
+use std::fmt;
+
 use ta::Next;
 use ta::indicators::ExponentialMovingAverage as Ema;
 
-use crate::common;
-use crate::stocks::data_company::DataCompany;
+use crate::analysis::cross::{Cross, CrossType};
+use crate::stocks::data_company::StockData;
 
 const MODULE_NAME: &str = "analysis::macd";
 const INTERVALS: usize = 100;
 
-#[derive(Debug, Clone, PartialEq, Eq)]
-pub enum MacdCrossType {
-    GoldenCross,
-    DeathCross,
+/// 交叉發生在零軸之上或之下，以交叉時的訊號線 (MACD) 判斷
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum MacdZone {
+    AboveZero,
+    BelowZero,
+}
+
+impl fmt::Display for MacdZone {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            MacdZone::AboveZero => write!(f, "零軸上"),
+            MacdZone::BelowZero => write!(f, "零軸下"),
+        }
+    }
 }
 
-// #[derive(Debug, Clone)]
+#[derive(Debug, Clone)]
 pub struct MacdCross {
-    pub stock_no: String,
     pub date: String,
-    // pub dif: f64,
-    // pub macd_signal: f64,
-    pub cross_type: MacdCrossType,
+    pub dif: f64,
+    pub macd_signal: f64,
+    pub cross_type: CrossType,
+    pub zone: MacdZone,
 }
 
-// #[derive(Debug, Clone)]
+impl Cross for MacdCross {
+    type Zone = MacdZone;
+
+    fn date(&self) -> &str {
+        &self.date
+    }
+
+    fn cross_type(&self) -> CrossType {
+        self.cross_type
+    }
+
+    fn zone(&self) -> MacdZone {
+        self.zone
+    }
+}
+
+impl fmt::Display for MacdCross {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "{}{} (DIF {:.2} / MACD {:.2})",
+            self.zone, self.cross_type, self.dif, self.macd_signal
+        )
+    }
+}
+
+/// 某根 K 棒的 MACD，histogram = DIF - 訊號線 (柱狀體)
+#[derive(Debug, Clone)]
 pub struct MacdResult {
-    // dif: f64,
-    // macd_signal: f64,
-    // histogram: f64,
+    pub date: String,
+    pub dif: f64,
+    pub macd_signal: f64,
+    pub histogram: f64,
+}
+
+/// 最後 bars 根柱狀體同一邊且長度一根比一根短，代表原本的趨勢力道在減弱
+pub fn histogram_shrinking(results: &[MacdResult], bars: usize) -> bool {
+    if bars < 2 || results.len() < bars {
+        return false;
+    }
+    let recent = &results[results.len() - bars..];
+    let positive = recent[0].histogram > 0.0;
+    recent
+        .iter()
+        .all(|r| r.histogram != 0.0 && (r.histogram > 0.0) == positive)
+        && recent
+            .windows(2)
+            .all(|w| w[1].histogram.abs() < w[0].histogram.abs())
 }
 
 pub struct MacdCalculator {
@@ -58,9 +113,10 @@ impl MacdCalculator {
         self.prev_signal = 0.0;
     }
 
+    /// 計算到 date 那根 K 棒為止，date 是序列裡的 StockData.date，日K或分K都可以
     pub fn calc(
         &mut self,
-        data_company: &DataCompany,
+        stock_data: &[StockData],
         date: &str,
     ) -> Option<(Vec<MacdResult>, Vec<MacdCross>)> {
         self.reset();
@@ -68,13 +124,7 @@ impl MacdCalculator {
         let mut results = Vec::new();
         let mut macd_crosses = Vec::new();
 
-        // find index of date in data_company.stock_data
-        let date_fugle_format = common::convert_date_to_fugle_format(date);
-        let end_index = match data_company
-            .stock_data
-            .iter()
-            .position(|d| d.date == date_fugle_format)
-        {
+        let end_index = match stock_data.iter().position(|d| d.date == date) {
             Some(i) => i,
             None => {
                 println!(
@@ -92,7 +142,7 @@ impl MacdCalculator {
             return None;
         };
 
-        for daily in &data_company.stock_data[start_index..=end_index] {
+        for daily in &stock_data[start_index..=end_index] {
             let close_price = daily.close;
             let (res, cross) = self.feed(&daily.date, close_price);
             results.push(res);
@@ -110,38 +160,133 @@ impl MacdCalculator {
 
         let dif = e12 - e26;
         let signal = self.signal_ema9.next(dif);
-        let _histogram = dif - signal;
+        let histogram = dif - signal;
 
         let res = MacdResult {
-            // dif,
-            // macd_signal: signal,
-            // histogram,
+            date: date.to_string(),
+            dif,
+            macd_signal: signal,
+            histogram,
         };
 
         // 判斷交叉邏輯
-        let mut cross = None;
-        if self.prev_dif <= self.prev_signal && dif > signal {
+        let cross_type = if self.prev_dif <= self.prev_signal && dif > signal {
             // println!("🚀 【黃金交叉】{} Date: {} DIF({:.2}) 向上突破 MACD({:.2})", self.stock_no, date, dif, signal);
-            cross = Some(MacdCross {
-                stock_no: self.stock_no.clone(),
-                date: date.to_string(),
-                // dif,
-                // macd_signal: signal,
-                cross_type: MacdCrossType::GoldenCross,
-            });
+            Some(CrossType::GoldenCross)
         } else if self.prev_dif >= self.prev_signal && dif < signal {
             // println!("💀 【死亡交叉】{} Date: {} DIF({:.2}) 向下貫穿 MACD({:.2})", self.stock_no, date, dif, signal);
-            cross = Some(MacdCross {
-                stock_no: self.stock_no.clone(),
-                date: date.to_string(),
-                // dif,
-                // macd_signal: signal,
-                cross_type: MacdCrossType::DeathCross,
-            });
-        }
+            Some(CrossType::DeathCross)
+        } else {
+            None
+        };
+        let cross = cross_type.map(|cross_type| MacdCross {
+            date: date.to_string(),
+            dif,
+            macd_signal: signal,
+            cross_type,
+            zone: if signal < 0.0 {
+                MacdZone::BelowZero
+            } else {
+                MacdZone::AboveZero
+            },
+        });
 
         self.prev_dif = dif;
         self.prev_signal = signal;
         (res, cross)
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::analysis::test_bars;
+
+    /// 先走 trend 的趨勢 120 根，再反向 pullback 根，最後回到原本的趨勢
+    fn pullback(trend: f64, pullback: usize) -> Vec<StockData> {
+        let mut close = 500.0;
+        let mut closes = Vec::new();
+        for day in 0..150 {
+            close += if (120..120 + pullback).contains(&day) {
+                -trend
+            } else {
+                trend
+            };
+            closes.push(close);
+        }
+        test_bars::bars(&closes)
+    }
+
+    /// 拉回開始之後的交叉，前面暖機時的交叉不算
+    fn crosses(bars: &[StockData]) -> Vec<MacdCross> {
+        let mut calculator = MacdCalculator::new("test".to_string());
+        let last = bars.last().unwrap();
+        let (_, crosses) = calculator.calc(bars, &last.date).unwrap();
+        crosses
+            .into_iter()
+            .filter(|cross| cross.date >= bars[120].date)
+            .collect()
+    }
+
+    #[test]
+    fn crosses_above_zero_in_an_uptrend() {
+        let crosses = crosses(&pullback(2.0, 5));
+
+        let kinds: Vec<_> = crosses.iter().map(|c| (c.cross_type, c.zone)).collect();
+        assert_eq!(
+            kinds,
+            [
+                (CrossType::DeathCross, MacdZone::AboveZero),
+                (CrossType::GoldenCross, MacdZone::AboveZero),
+            ]
+        );
+    }
+
+    #[test]
+    fn crosses_below_zero_in_a_downtrend() {
+        let crosses = crosses(&pullback(-2.0, 5));
+
+        let kinds: Vec<_> = crosses.iter().map(|c| (c.cross_type, c.zone)).collect();
+        assert_eq!(
+            kinds,
+            [
+                (CrossType::GoldenCross, MacdZone::BelowZero),
+                (CrossType::DeathCross, MacdZone::BelowZero),
+            ]
+        );
+    }
+
+    #[test]
+    fn not_enough_bars_has_no_macd() {
+        let bars = test_bars::bars(&[100.0; INTERVALS]);
+        let mut calculator = MacdCalculator::new("test".to_string());
+        assert!(calculator.calc(&bars, &bars[INTERVALS - 1].date).is_none());
+        assert!(calculator.calc(&bars, "2025-12-31").is_none());
+    }
+
+    #[test]
+    fn histogram_shrinks_on_one_side() {
+        let results = |histograms: &[f64]| -> Vec<MacdResult> {
+            histograms
+                .iter()
+                .enumerate()
+                .map(|(day, &histogram)| MacdResult {
+                    date: test_bars::date(day),
+                    dif: 0.0,
+                    macd_signal: 0.0,
+                    histogram,
+                })
+                .collect()
+        };
+
+        // 紅柱、綠柱連續縮短
+        assert!(histogram_shrinking(&results(&[5.0, 3.0, 2.0, 1.0]), 3));
+        assert!(histogram_shrinking(&results(&[-3.0, -2.0, -1.0]), 3));
+        // 沒有一根比一根短、跨過零軸、根數不夠都不算
+        assert!(!histogram_shrinking(&results(&[3.0, 3.0, 1.0]), 3));
+        assert!(!histogram_shrinking(&results(&[1.0, 2.0, 3.0]), 3));
+        assert!(!histogram_shrinking(&results(&[2.0, -1.0, 0.5]), 3));
+        assert!(!histogram_shrinking(&results(&[2.0, 1.0]), 3));
+        assert!(!histogram_shrinking(&results(&[2.0, 1.0]), 1));
+    }
+}
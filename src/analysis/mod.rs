@@ -1,4 +1,11 @@
 pub mod bullish_engulfing;
 pub mod candlestick;
+pub mod cross;
+pub mod divergence;
+pub mod indicators;
+pub mod kd;
 pub mod macd;
+pub mod moving_average;
+#[cfg(test)]
+pub mod test_bars;
 pub mod volume;
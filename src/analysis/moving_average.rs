@@ -0,0 +1,237 @@
+use std::fmt;
+
+use crate::analysis::cross::CrossType;
+use crate::stocks::data_company::StockData;
+
+pub const MA_SHORT: usize = 5;
+pub const MA_MID: usize = 20;
+pub const MA_LONG: usize = 60;
+
+/// 均線種類
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum MaKind {
+    /// 簡單移動平均，台灣看盤軟體的 MA 預設都是這個
+    Sma,
+    /// 指數移動平均，以 SMA 當第一個值
+    Ema,
+    /// 加權移動平均，越近的權重越大 (1, 2, ..., period)
+    Wma,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Alignment {
+    /// 短均 > 中均 > 長均
+    Bullish,
+    /// 短均 < 中均 < 長均
+    Bearish,
+}
+
+/// 用扣抵值推估均線明天的方向
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum DeductionOutlook {
+    /// 均線還在下彎，但收盤價高於扣抵值 (扣低)，明天收平盤均線就會上揚
+    TurningUp,
+    /// 均線還在上揚，但收盤價低於扣抵值 (扣高)，明天收平盤均線就會下彎
+    TurningDown,
+}
+
+impl fmt::Display for MaKind {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            MaKind::Sma => write!(f, "MA"),
+            MaKind::Ema => write!(f, "EMA"),
+            MaKind::Wma => write!(f, "WMA"),
+        }
+    }
+}
+
+impl fmt::Display for Alignment {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Alignment::Bullish => write!(f, "多頭排列"),
+            Alignment::Bearish => write!(f, "空頭排列"),
+        }
+    }
+}
+
+impl fmt::Display for DeductionOutlook {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            DeductionOutlook::TurningUp => write!(f, "扣低即將上揚"),
+            DeductionOutlook::TurningDown => write!(f, "扣高即將下彎"),
+        }
+    }
+}
+
+/// 收盤價的均線，跟 bars 一對一對齊，前 period - 1 根是 None
+pub fn moving_average(bars: &[StockData], kind: MaKind, period: usize) -> Vec<Option<f64>> {
+    let closes: Vec<f64> = bars.iter().map(|bar| bar.close).collect();
+    match kind {
+        MaKind::Sma => sma(&closes, period),
+        MaKind::Ema => ema(&closes, period),
+        MaKind::Wma => wma(&closes, period),
+    }
+}
+
+pub fn sma(values: &[f64], period: usize) -> Vec<Option<f64>> {
+    let mut averages = vec![None; values.len()];
+    if period == 0 {
+        return averages;
+    }
+    for (i, window) in values.windows(period).enumerate() {
+        averages[i + period - 1] = Some(window.iter().sum::<f64>() / period as f64);
+    }
+    averages
+}
+
+pub fn ema(values: &[f64], period: usize) -> Vec<Option<f64>> {
+    let mut averages = sma(values, period);
+    let Some(start) = averages.iter().position(Option::is_some) else {
+        return averages;
+    };
+    let alpha = 2.0 / (period as f64 + 1.0);
+    let mut prev = averages[start].unwrap_or_default();
+    for i in start + 1..values.len() {
+        prev += alpha * (values[i] - prev);
+        averages[i] = Some(prev);
+    }
+    averages
+}
+
+pub fn wma(values: &[f64], period: usize) -> Vec<Option<f64>> {
+    let mut averages = vec![None; values.len()];
+    if period == 0 {
+        return averages;
+    }
+    let weights = (period * (period + 1) / 2) as f64;
+    for (i, window) in values.windows(period).enumerate() {
+        let weighted: f64 = window
+            .iter()
+            .enumerate()
+            .map(|(w, value)| (w + 1) as f64 * value)
+            .sum();
+        averages[i + period - 1] = Some(weighted / weights);
+    }
+    averages
+}
+
+/// fast 在 index 那根由下往上 (由上往下) 穿過 slow
+pub fn cross_at(fast: &[Option<f64>], slow: &[Option<f64>], index: usize) -> Option<CrossType> {
+    let prev = index.checked_sub(1)?;
+    let (prev_fast, prev_slow) = ((*fast.get(prev)?)?, (*slow.get(prev)?)?);
+    let (fast, slow) = ((*fast.get(index)?)?, (*slow.get(index)?)?);
+    if prev_fast <= prev_slow && fast > slow {
+        Some(CrossType::GoldenCross)
+    } else if prev_fast >= prev_slow && fast < slow {
+        Some(CrossType::DeathCross)
+    } else {
+        None
+    }
+}
+
+/// 短、中、長三條均線在 index 那根的排列
+pub fn alignment_at(
+    short: &[Option<f64>],
+    mid: &[Option<f64>],
+    long: &[Option<f64>],
+    index: usize,
+) -> Option<Alignment> {
+    let (short, mid, long) = (
+        (*short.get(index)?)?,
+        (*mid.get(index)?)?,
+        (*long.get(index)?)?,
+    );
+    if short > mid && mid > long {
+        Some(Alignment::Bullish)
+    } else if short < mid && mid < long {
+        Some(Alignment::Bearish)
+    } else {
+        None
+    }
+}
+
+/// 扣抵值: 明天加入一根新 K 棒時，period 日 SMA 會被扣掉的那一天的收盤價
+pub fn deduction_price(bars: &[StockData], period: usize, index: usize) -> Option<f64> {
+    let oldest = (index + 1).checked_sub(period)?;
+    (index < bars.len()).then(|| bars[oldest].close)
+}
+
+/// 比較今天收盤價與扣抵值，看 period 日 SMA 是不是快要轉向
+pub fn deduction_outlook(
+    bars: &[StockData],
+    average: &[Option<f64>],
+    period: usize,
+    index: usize,
+) -> Option<DeductionOutlook> {
+    let deduction = deduction_price(bars, period, index)?;
+    let close = bars[index].close;
+    let (prev, curr) = (
+        (*average.get(index.checked_sub(1)?)?)?,
+        (*average.get(index)?)?,
+    );
+    if curr < prev && close > deduction {
+        Some(DeductionOutlook::TurningUp)
+    } else if curr > prev && close < deduction {
+        Some(DeductionOutlook::TurningDown)
+    } else {
+        None
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::analysis::test_bars;
+
+    #[test]
+    fn averages_follow_their_definitions() {
+        let values = [1.0, 2.0, 3.0, 4.0, 5.0];
+
+        assert_eq!(
+            sma(&values, 3),
+            [None, None, Some(2.0), Some(3.0), Some(4.0)]
+        );
+        // (1*3 + 2*4 + 3*5) / 6
+        assert_eq!(wma(&values, 3)[4], Some(26.0 / 6.0));
+        // SMA 2 起算，alpha = 0.5: 2 → 3 → 4
+        assert_eq!(
+            ema(&values, 3),
+            [None, None, Some(2.0), Some(3.0), Some(4.0)]
+        );
+        assert!(sma(&values, 6).iter().all(Option::is_none));
+    }
+
+    #[test]
+    fn crosses_and_alignment() {
+        let fast = [Some(1.0), Some(3.0), Some(1.0)];
+        let slow = [Some(2.0), Some(2.0), Some(2.0)];
+
+        assert_eq!(cross_at(&fast, &slow, 0), None);
+        assert_eq!(cross_at(&fast, &slow, 1), Some(CrossType::GoldenCross));
+        assert_eq!(cross_at(&fast, &slow, 2), Some(CrossType::DeathCross));
+        assert_eq!(
+            alignment_at(&[Some(3.0)], &[Some(2.0)], &[Some(1.0)], 0),
+            Some(Alignment::Bullish)
+        );
+        assert_eq!(alignment_at(&[Some(3.0)], &[Some(2.0)], &[None], 0), None);
+    }
+
+    #[test]
+    fn deduction_predicts_the_turn() {
+        // 3 日均線 10 → 9 下彎，但明天要扣掉的是 6，今天收 9 比它高
+        let bars = test_bars::bars(&[12.0, 6.0, 12.0, 9.0, 7.0]);
+        let average = sma(&bars.iter().map(|bar| bar.close).collect::<Vec<_>>(), 3);
+
+        assert_eq!(deduction_price(&bars, 3, 3), Some(6.0));
+        assert_eq!(deduction_price(&bars, 3, 1), None);
+        assert_eq!(
+            deduction_outlook(&bars, &average, 3, 3),
+            Some(DeductionOutlook::TurningUp)
+        );
+        // 隔天均線果然上揚到 9.33，但接下來要扣 12，收 7 就會再下彎
+        assert_eq!(
+            deduction_outlook(&bars, &average, 3, 4),
+            Some(DeductionOutlook::TurningDown)
+        );
+    }
+}
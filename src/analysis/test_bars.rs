@@ -0,0 +1,38 @@
+use chrono::{Datelike, Days, NaiveDate, Weekday};
+
+use crate::stocks::data_company::StockData;
+
+/// 第 day 根 (0 起算) K 棒的日期，從 2026-01-02 起每個平日一根
+pub fn date(day: usize) -> String {
+    let mut date = NaiveDate::from_ymd_opt(2026, 1, 2).unwrap();
+    for _ in 0..day {
+        date = date + Days::new(1);
+        while matches!(date.weekday(), Weekday::Sat | Weekday::Sun) {
+            date = date + Days::new(1);
+        }
+    }
+    date.format("%Y-%m-%d").to_string()
+}
+
+/// 開盤等於收盤、高低點各差 1 的 K 棒，需要其他形狀時用 struct update 覆蓋
+pub fn bar(day: usize, close: f64) -> StockData {
+    StockData {
+        date: date(day),
+        open: close,
+        high: close + 1.0,
+        low: close - 1.0,
+        close,
+        volume: 1000,
+        turnover: 0,
+        change: Some(0.0),
+    }
+}
+
+/// 依序每個收盤價一根 K 棒
+pub fn bars(closes: &[f64]) -> Vec<StockData> {
+    closes
+        .iter()
+        .enumerate()
+        .map(|(day, &close)| bar(day, close))
+        .collect()
+}
@@ -1,25 +1,18 @@
-use crate::common;
-use crate::stocks::data_company::DataCompany;
+use crate::stocks::data_company::StockData;
 
 const MODULE_NAME: &str = "analysis::volume";
 
-pub fn find_max_min_date_range_company(
-    data_company: &DataCompany,
+/// date 是序列裡的 StockData.date，range 以 K 棒根數計算，日K以外的週期也一樣
+pub fn find_max_min_date_range(
+    stock_no: &str,
+    stock_data: &[StockData],
     date: &str,
     range: usize,
 ) -> Option<(f64, f64)> {
-    let date_fugle_format = common::convert_date_to_fugle_format(date);
-    let curr_index = match data_company
-        .stock_data
-        .iter()
-        .position(|d| d.date == date_fugle_format)
-    {
+    let curr_index = match stock_data.iter().position(|d| d.date == date) {
         Some(i) => i,
         None => {
-            println!(
-                "[{MODULE_NAME}] 找不到 {} 日期 {} 的資料",
-                data_company.stock_no, date
-            );
+            println!("[{MODULE_NAME}] 找不到 {stock_no} 日期 {date} 的資料");
             return None;
         }
     };
@@ -27,18 +20,14 @@ pub fn find_max_min_date_range_company(
     let start_index = if curr_index >= range {
         curr_index - range + 1
     } else {
-        println!(
-            "[{MODULE_NAME}] {} 日期 {} 前面資料不足，無法計算最大最小值",
-            data_company.stock_no, date
-        );
+        println!("[{MODULE_NAME}] {stock_no} 日期 {date} 前面資料不足，無法計算最大最小值");
         return None;
     };
 
     let mut max_price = f64::MIN;
     let mut min_price = f64::MAX;
 
-    for i in start_index..=curr_index {
-        let data = &data_company.stock_data[i];
+    for data in &stock_data[start_index..=curr_index] {
         if data.high > max_price {
             max_price = data.high;
         }
@@ -50,19 +39,12 @@ pub fn find_max_min_date_range_company(
     Some((max_price, min_price))
 }
 
-pub fn find_mv(data_company: &DataCompany, date: &str) -> Option<(f64, f64, f64)> {
-    let date_fugle_format = common::convert_date_to_fugle_format(date);
-    let curr_index = match data_company
-        .stock_data
-        .iter()
-        .position(|d| d.date == date_fugle_format)
-    {
+/// 到 date 那根 K 棒為止的 5/10/20 根均量
+pub fn find_mv(stock_no: &str, stock_data: &[StockData], date: &str) -> Option<(f64, f64, f64)> {
+    let curr_index = match stock_data.iter().position(|d| d.date == date) {
         Some(i) => i,
         None => {
-            println!(
-                "[{MODULE_NAME}] 找不到 {} 日期 {} 的資料",
-                data_company.stock_no, date
-            );
+            println!("[{MODULE_NAME}] 找不到 {stock_no} 日期 {date} 的資料");
             return None;
         }
     };
@@ -72,10 +54,7 @@ pub fn find_mv(data_company: &DataCompany, date: &str) -> Option<(f64, f64, f64)
     let start_index = if curr_index >= range {
         curr_index - range + 1
     } else {
-        println!(
-            "[{MODULE_NAME}] {} 日期 {} 前面資料不足，無法計算 MV",
-            data_company.stock_no, date
-        );
+        println!("[{MODULE_NAME}] {stock_no} 日期 {date} 前面資料不足，無法計算 MV");
         return None;
     };
 
@@ -83,8 +62,12 @@ pub fn find_mv(data_company: &DataCompany, date: &str) -> Option<(f64, f64, f64)
     let mut sum_mv10 = 0;
     let mut sum_mv20 = 0;
 
-    for i in start_index..=curr_index {
-        let data = &data_company.stock_data[i];
+    for (i, data) in stock_data
+        .iter()
+        .enumerate()
+        .take(curr_index + 1)
+        .skip(start_index)
+    {
         if i >= curr_index - 4 {
             sum_mv5 += data.volume;
         }
@@ -0,0 +1,192 @@
+use serde::{Deserialize, Serialize};
+
+/// 平日休市或週末開市的原因
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum DayKind {
+    /// 國定假日、補假、彈性放假、農曆年前封關 (只辦結算交割)
+    Holiday,
+    /// 颱風停止上班，證交所休市
+    Typhoon,
+    /// 週六補行上班日，證交所只辦結算交割，不開盤
+    Makeup,
+    /// 週末但有開盤的補行交易日
+    Trading,
+}
+
+impl DayKind {
+    pub fn label(&self) -> &'static str {
+        match self {
+            DayKind::Holiday => "休市",
+            DayKind::Typhoon => "颱風休市",
+            DayKind::Makeup => "補行上班日 (不開盤)",
+            DayKind::Trading => "補行交易日",
+        }
+    }
+}
+
+// 2019 ~ 2025 依歷史交易資料核對；2026 依證交所公告的市場開休市日期
+#[rustfmt::skip]
+pub const TWSE_SPECIAL_DAYS: &[(&str, DayKind, &str)] = &[
+    ("2019-01-01", DayKind::Holiday, "開國紀念日"),
+    ("2019-01-19", DayKind::Makeup, "補行上班"),
+    ("2019-01-31", DayKind::Holiday, "農曆春節前封關"),
+    ("2019-02-01", DayKind::Holiday, "農曆春節前封關"),
+    ("2019-02-04", DayKind::Holiday, "農曆除夕"),
+    ("2019-02-05", DayKind::Holiday, "春節"),
+    ("2019-02-06", DayKind::Holiday, "春節"),
+    ("2019-02-07", DayKind::Holiday, "春節"),
+    ("2019-02-08", DayKind::Holiday, "春節彈性放假"),
+    ("2019-02-23", DayKind::Makeup, "補行上班"),
+    ("2019-02-28", DayKind::Holiday, "和平紀念日"),
+    ("2019-03-01", DayKind::Holiday, "彈性放假"),
+    ("2019-04-04", DayKind::Holiday, "兒童節"),
+    ("2019-04-05", DayKind::Holiday, "民族掃墓節"),
+    ("2019-05-01", DayKind::Holiday, "勞動節"),
+    ("2019-06-07", DayKind::Holiday, "端午節"),
+    ("2019-08-09", DayKind::Typhoon, "利奇馬颱風"),
+    ("2019-09-13", DayKind::Holiday, "中秋節"),
+    ("2019-09-30", DayKind::Typhoon, "米塔颱風"),
+    ("2019-10-05", DayKind::Makeup, "補行上班"),
+    ("2019-10-10", DayKind::Holiday, "國慶日"),
+    ("2019-10-11", DayKind::Holiday, "彈性放假"),
+    ("2020-01-01", DayKind::Holiday, "開國紀念日"),
+    ("2020-01-21", DayKind::Holiday, "農曆春節前封關"),
+    ("2020-01-22", DayKind::Holiday, "農曆春節前封關"),
+    ("2020-01-23", DayKind::Holiday, "春節彈性放假"),
+    ("2020-01-24", DayKind::Holiday, "農曆除夕"),
+    ("2020-01-27", DayKind::Holiday, "春節補假"),
+    ("2020-01-28", DayKind::Holiday, "春節補假"),
+    ("2020-01-29", DayKind::Holiday, "春節補假"),
+    ("2020-02-15", DayKind::Makeup, "補行上班"),
+    ("2020-02-28", DayKind::Holiday, "和平紀念日"),
+    ("2020-04-02", DayKind::Holiday, "兒童節補假"),
+    ("2020-04-03", DayKind::Holiday, "民族掃墓節補假"),
+    ("2020-05-01", DayKind::Holiday, "勞動節"),
+    ("2020-06-20", DayKind::Makeup, "補行上班"),
+    ("2020-06-25", DayKind::Holiday, "端午節"),
+    ("2020-06-26", DayKind::Holiday, "彈性放假"),
+    ("2020-09-26", DayKind::Makeup, "補行上班"),
+    ("2020-10-01", DayKind::Holiday, "中秋節"),
+    ("2020-10-02", DayKind::Holiday, "彈性放假"),
+    ("2020-10-09", DayKind::Holiday, "國慶日補假"),
+    ("2021-01-01", DayKind::Holiday, "開國紀念日"),
+    ("2021-02-08", DayKind::Holiday, "農曆春節前封關"),
+    ("2021-02-09", DayKind::Holiday, "農曆春節前封關"),
+    ("2021-02-10", DayKind::Holiday, "彈性放假"),
+    ("2021-02-11", DayKind::Holiday, "農曆除夕"),
+    ("2021-02-12", DayKind::Holiday, "春節"),
+    ("2021-02-15", DayKind::Holiday, "春節補假"),
+    ("2021-02-16", DayKind::Holiday, "春節補假"),
+    ("2021-02-20", DayKind::Makeup, "補行上班"),
+    ("2021-03-01", DayKind::Holiday, "和平紀念日補假"),
+    ("2021-04-02", DayKind::Holiday, "兒童節補假"),
+    ("2021-04-05", DayKind::Holiday, "民族掃墓節補假"),
+    ("2021-04-30", DayKind::Holiday, "勞動節補假"),
+    ("2021-06-14", DayKind::Holiday, "端午節"),
+    ("2021-09-11", DayKind::Makeup, "補行上班"),
+    ("2021-09-20", DayKind::Holiday, "彈性放假"),
+    ("2021-09-21", DayKind::Holiday, "中秋節"),
+    ("2021-10-11", DayKind::Holiday, "國慶日補假"),
+    ("2021-12-31", DayKind::Holiday, "開國紀念日補假"),
+    ("2022-01-22", DayKind::Makeup, "補行上班"),
+    ("2022-01-27", DayKind::Holiday, "農曆春節前封關"),
+    ("2022-01-28", DayKind::Holiday, "農曆春節前封關"),
+    ("2022-01-31", DayKind::Holiday, "農曆除夕"),
+    ("2022-02-01", DayKind::Holiday, "春節"),
+    ("2022-02-02", DayKind::Holiday, "春節"),
+    ("2022-02-03", DayKind::Holiday, "春節"),
+    ("2022-02-04", DayKind::Holiday, "彈性放假"),
+    ("2022-02-28", DayKind::Holiday, "和平紀念日"),
+    ("2022-04-04", DayKind::Holiday, "兒童節"),
+    ("2022-04-05", DayKind::Holiday, "民族掃墓節"),
+    ("2022-05-02", DayKind::Holiday, "勞動節補假"),
+    ("2022-06-03", DayKind::Holiday, "端午節"),
+    ("2022-09-09", DayKind::Holiday, "中秋節補假"),
+    ("2022-10-10", DayKind::Holiday, "國慶日"),
+    ("2023-01-02", DayKind::Holiday, "開國紀念日補假"),
+    ("2023-01-07", DayKind::Makeup, "補行上班"),
+    ("2023-01-18", DayKind::Holiday, "農曆春節前封關"),
+    ("2023-01-19", DayKind::Holiday, "農曆春節前封關"),
+    ("2023-01-20", DayKind::Holiday, "彈性放假"),
+    ("2023-01-23", DayKind::Holiday, "春節"),
+    ("2023-01-24", DayKind::Holiday, "春節"),
+    ("2023-01-25", DayKind::Holiday, "春節補假"),
+    ("2023-01-26", DayKind::Holiday, "春節補假"),
+    ("2023-01-27", DayKind::Holiday, "彈性放假"),
+    ("2023-02-04", DayKind::Makeup, "補行上班"),
+    ("2023-02-18", DayKind::Makeup, "補行上班"),
+    ("2023-02-27", DayKind::Holiday, "彈性放假"),
+    ("2023-02-28", DayKind::Holiday, "和平紀念日"),
+    ("2023-03-25", DayKind::Makeup, "補行上班"),
+    ("2023-04-03", DayKind::Holiday, "彈性放假"),
+    ("2023-04-04", DayKind::Holiday, "兒童節"),
+    ("2023-04-05", DayKind::Holiday, "民族掃墓節"),
+    ("2023-05-01", DayKind::Holiday, "勞動節"),
+    ("2023-06-17", DayKind::Makeup, "補行上班"),
+    ("2023-06-22", DayKind::Holiday, "端午節"),
+    ("2023-06-23", DayKind::Holiday, "彈性放假"),
+    ("2023-08-03", DayKind::Typhoon, "卡努颱風"),
+    ("2023-09-23", DayKind::Makeup, "補行上班"),
+    ("2023-09-29", DayKind::Holiday, "中秋節"),
+    ("2023-10-09", DayKind::Holiday, "彈性放假"),
+    ("2023-10-10", DayKind::Holiday, "國慶日"),
+    ("2024-01-01", DayKind::Holiday, "開國紀念日"),
+    ("2024-02-06", DayKind::Holiday, "農曆春節前封關"),
+    ("2024-02-07", DayKind::Holiday, "農曆春節前封關"),
+    ("2024-02-08", DayKind::Holiday, "彈性放假"),
+    ("2024-02-09", DayKind::Holiday, "農曆除夕"),
+    ("2024-02-12", DayKind::Holiday, "春節補假"),
+    ("2024-02-13", DayKind::Holiday, "春節補假"),
+    ("2024-02-14", DayKind::Holiday, "春節補假"),
+    ("2024-02-17", DayKind::Makeup, "補行上班"),
+    ("2024-02-28", DayKind::Holiday, "和平紀念日"),
+    ("2024-04-04", DayKind::Holiday, "兒童節及民族掃墓節"),
+    ("2024-04-05", DayKind::Holiday, "兒童節補假"),
+    ("2024-05-01", DayKind::Holiday, "勞動節"),
+    ("2024-06-10", DayKind::Holiday, "端午節"),
+    ("2024-07-24", DayKind::Typhoon, "凱米颱風"),
+    ("2024-07-25", DayKind::Typhoon, "凱米颱風"),
+    ("2024-09-17", DayKind::Holiday, "中秋節"),
+    ("2024-10-02", DayKind::Typhoon, "山陀兒颱風"),
+    ("2024-10-03", DayKind::Typhoon, "山陀兒颱風"),
+    ("2024-10-10", DayKind::Holiday, "國慶日"),
+    ("2024-10-31", DayKind::Typhoon, "康芮颱風"),
+    ("2025-01-01", DayKind::Holiday, "開國紀念日"),
+    ("2025-01-23", DayKind::Holiday, "農曆春節前封關"),
+    ("2025-01-24", DayKind::Holiday, "農曆春節前封關"),
+    ("2025-01-27", DayKind::Holiday, "彈性放假"),
+    ("2025-01-28", DayKind::Holiday, "農曆除夕"),
+    ("2025-01-29", DayKind::Holiday, "春節"),
+    ("2025-01-30", DayKind::Holiday, "春節"),
+    ("2025-01-31", DayKind::Holiday, "春節"),
+    ("2025-02-08", DayKind::Makeup, "補行上班"),
+    ("2025-02-28", DayKind::Holiday, "和平紀念日"),
+    ("2025-04-03", DayKind::Holiday, "兒童節補假"),
+    ("2025-04-04", DayKind::Holiday, "兒童節及民族掃墓節"),
+    ("2025-05-01", DayKind::Holiday, "勞動節"),
+    ("2025-05-30", DayKind::Holiday, "端午節補假"),
+    ("2025-09-29", DayKind::Holiday, "教師節補假"),
+    ("2025-10-06", DayKind::Holiday, "中秋節"),
+    ("2025-10-10", DayKind::Holiday, "國慶日"),
+    ("2025-10-24", DayKind::Holiday, "臺灣光復暨金門古寧頭大捷紀念日補假"),
+    ("2025-12-25", DayKind::Holiday, "行憲紀念日"),
+    ("2026-01-01", DayKind::Holiday, "開國紀念日"),
+    ("2026-02-12", DayKind::Holiday, "農曆春節前封關"),
+    ("2026-02-13", DayKind::Holiday, "農曆春節前封關"),
+    ("2026-02-16", DayKind::Holiday, "農曆除夕"),
+    ("2026-02-17", DayKind::Holiday, "春節"),
+    ("2026-02-18", DayKind::Holiday, "春節"),
+    ("2026-02-19", DayKind::Holiday, "春節"),
+    ("2026-02-20", DayKind::Holiday, "春節補假"),
+    ("2026-02-27", DayKind::Holiday, "和平紀念日補假"),
+    ("2026-04-03", DayKind::Holiday, "兒童節補假"),
+    ("2026-04-06", DayKind::Holiday, "民族掃墓節補假"),
+    ("2026-05-01", DayKind::Holiday, "勞動節"),
+    ("2026-06-19", DayKind::Holiday, "端午節"),
+    ("2026-09-25", DayKind::Holiday, "中秋節"),
+    ("2026-09-28", DayKind::Holiday, "教師節"),
+    ("2026-10-09", DayKind::Holiday, "國慶日補假"),
+    ("2026-10-26", DayKind::Holiday, "臺灣光復暨金門古寧頭大捷紀念日補假"),
+    ("2026-12-25", DayKind::Holiday, "行憲紀念日"),
+];
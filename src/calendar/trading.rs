@@ -0,0 +1,218 @@
+use std::collections::BTreeMap;
+use std::fs::File;
+use std::io;
+use std::path::Path;
+
+use chrono::{Datelike, Days, NaiveDate, Weekday};
+use serde::Deserialize;
+
+use crate::calendar::holidays::{DayKind, TWSE_SPECIAL_DAYS};
+use crate::error::{Error, Result};
+
+const MODULE_NAME: &str = "calendar::trading";
+
+#[derive(Debug, Clone)]
+pub struct SpecialDay {
+    pub kind: DayKind,
+    pub name: String,
+}
+
+/// calendar.json 的一筆資料，用來補上臨時公告的颱風休市或補行交易日
+#[derive(Deserialize)]
+struct SpecialDayOverride {
+    date: String,
+    kind: DayKind,
+    name: String,
+}
+
+/// TWSE 交易日曆：週一到週五開盤，扣掉休市日，加上補行交易日
+pub struct TradingCalendar {
+    special_days: BTreeMap<NaiveDate, SpecialDay>,
+}
+
+impl TradingCalendar {
+    pub fn new(calendar_path: &str) -> Result<Self> {
+        let mut special_days = BTreeMap::new();
+        for (date, kind, name) in TWSE_SPECIAL_DAYS {
+            special_days.insert(
+                parse_date(date)?,
+                SpecialDay {
+                    kind: *kind,
+                    name: name.to_string(),
+                },
+            );
+        }
+
+        if Path::new(calendar_path).exists() {
+            let file = File::open(calendar_path)?;
+            let reader = io::BufReader::new(file);
+            let overrides: Vec<SpecialDayOverride> = serde_json::from_reader(reader)?;
+            println!(
+                "[{MODULE_NAME}] 從 {calendar_path} 載入 {} 筆自訂開休市日",
+                overrides.len()
+            );
+            for o in overrides {
+                special_days.insert(
+                    parse_date(&o.date)?,
+                    SpecialDay {
+                        kind: o.kind,
+                        name: o.name,
+                    },
+                );
+            }
+        }
+
+        Ok(TradingCalendar { special_days })
+    }
+
+    pub fn is_trading_day(&self, date: NaiveDate) -> bool {
+        match self.special_days.get(&date) {
+            Some(special_day) => special_day.kind == DayKind::Trading,
+            None => !matches!(date.weekday(), Weekday::Sat | Weekday::Sun),
+        }
+    }
+
+    /// 說明某天為什麼不是交易日
+    pub fn describe_closure(&self, date: NaiveDate) -> String {
+        match self.special_days.get(&date) {
+            Some(special_day) => format!("{} {}", special_day.kind.label(), special_day.name),
+            None => "週末".to_string(),
+        }
+    }
+
+    /// date 當天或之前最近的交易日
+    pub fn latest_trading_day(&self, date: NaiveDate) -> NaiveDate {
+        let mut date = date;
+        while !self.is_trading_day(date) {
+            date = date - Days::new(1);
+        }
+        date
+    }
+
+    /// date 之前 (不含當天) 的上一個交易日
+    pub fn previous_trading_day(&self, date: NaiveDate) -> NaiveDate {
+        self.latest_trading_day(date - Days::new(1))
+    }
+
+    /// date 之後 (不含當天) 的下一個交易日
+    pub fn next_trading_day(&self, date: NaiveDate) -> NaiveDate {
+        let mut date = date + Days::new(1);
+        while !self.is_trading_day(date) {
+            date = date + Days::new(1);
+        }
+        date
+    }
+
+    /// 從 date 當天或之前最近的交易日往回數 n 個交易日
+    pub fn trading_days_ago(&self, date: NaiveDate, n: usize) -> NaiveDate {
+        let mut date = self.latest_trading_day(date);
+        for _ in 0..n {
+            date = self.previous_trading_day(date);
+        }
+        date
+    }
+
+    /// from ~ to (含頭尾) 之間的所有交易日
+    pub fn trading_days_between(&self, from: NaiveDate, to: NaiveDate) -> Vec<NaiveDate> {
+        from.iter_days()
+            .take_while(|date| *date <= to)
+            .filter(|date| self.is_trading_day(*date))
+            .collect()
+    }
+
+    pub fn first_trading_day_of_year(&self, year: i32) -> Option<NaiveDate> {
+        let first = NaiveDate::from_ymd_opt(year, 1, 1)?;
+        let first = if self.is_trading_day(first) {
+            first
+        } else {
+            self.next_trading_day(first)
+        };
+        Some(first)
+    }
+}
+
+/// 支援 "YYYY-MM-DD" 與 "YYYYMMDD"
+pub fn parse_date(date: &str) -> Result<NaiveDate> {
+    NaiveDate::parse_from_str(date, "%Y-%m-%d")
+        .or_else(|_| NaiveDate::parse_from_str(date, "%Y%m%d"))
+        .map_err(|e| Error::Config(format!("日期格式錯誤 {date}: {e}")))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn date(ymd: &str) -> NaiveDate {
+        parse_date(ymd).unwrap()
+    }
+
+    fn calendar() -> TradingCalendar {
+        TradingCalendar::new("calendar.test.missing.json").unwrap()
+    }
+
+    #[test]
+    fn holidays_and_makeup_saturdays_are_closed() {
+        let calendar = calendar();
+
+        assert!(calendar.is_trading_day(date("20260211")));
+        // 農曆春節前封關
+        assert!(!calendar.is_trading_day(date("20260212")));
+        assert_eq!(
+            calendar.describe_closure(date("20260212")),
+            "休市 農曆春節前封關"
+        );
+        // 補行上班的週六只辦交割，不開盤
+        assert!(!calendar.is_trading_day(date("20250208")));
+        assert!(!calendar.is_trading_day(date("20260214")));
+        assert_eq!(calendar.describe_closure(date("20260214")), "週末");
+    }
+
+    #[test]
+    fn steps_over_the_lunar_new_year_break() {
+        let calendar = calendar();
+
+        assert_eq!(
+            calendar.next_trading_day(date("20260211")),
+            date("20260223")
+        );
+        assert_eq!(
+            calendar.previous_trading_day(date("20260223")),
+            date("20260211")
+        );
+        assert_eq!(
+            calendar.latest_trading_day(date("20260215")),
+            date("20260211")
+        );
+        assert_eq!(
+            calendar.trading_days_between(date("20260210"), date("20260224")),
+            [
+                date("20260210"),
+                date("20260211"),
+                date("20260223"),
+                date("20260224")
+            ]
+        );
+        assert_eq!(
+            calendar.first_trading_day_of_year(2026),
+            Some(date("20260102"))
+        );
+    }
+
+    #[test]
+    fn overrides_add_trading_saturdays() {
+        let path = std::env::temp_dir().join(format!("calendar-{}.json", std::process::id()));
+        std::fs::write(
+            &path,
+            r#"[{"date": "2026-03-07", "kind": "trading", "name": "補行交易"}]"#,
+        )
+        .unwrap();
+        let calendar = TradingCalendar::new(path.to_str().unwrap()).unwrap();
+        std::fs::remove_file(&path).unwrap();
+
+        assert!(calendar.is_trading_day(date("20260307")));
+        assert_eq!(
+            calendar.next_trading_day(date("20260306")),
+            date("20260307")
+        );
+    }
+}
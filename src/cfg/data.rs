@@ -1,26 +1,491 @@
+use std::collections::BTreeMap;
+use std::fmt;
 use std::fs;
+use std::ops::RangeInclusive;
+use std::path::Path;
+
+use chrono::{Datelike, Local};
 
 use serde::{Deserialize, Serialize};
+use serde_json::{Map, Value};
+
+use crate::consts;
+use crate::error::{Error, Result};
+use crate::stocks::universe;
+use crate::stocks::universe::{DEFAULT_UNIVERSE, Universe};
 
 const CFG_FILE: &str = "cfg.json";
+const ENV_PREFIX: &str = "STOCK2_";
 
-#[derive(Serialize, Deserialize)]
+#[derive(Serialize, Deserialize, Default, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "lowercase")]
+pub enum StorageBackend {
+    #[default]
+    Json,
+    Sqlite,
+}
+
+/// 分析時使用的價格：實際成交的原始價格或除權息還原後的價格
+#[derive(Serialize, Deserialize, Default, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "lowercase")]
+pub enum PriceView {
+    Raw,
+    #[default]
+    Adjusted,
+}
+
+/// 每個設定值最後是由哪一層決定的
+#[derive(Clone, Copy, PartialEq, Eq)]
+pub enum CfgSource {
+    Default,
+    File,
+    Env,
+    Cli,
+}
+
+impl fmt::Display for CfgSource {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            CfgSource::Default => write!(f, "預設"),
+            CfgSource::File => write!(f, "{CFG_FILE}"),
+            CfgSource::Env => write!(f, "環境變數"),
+            CfgSource::Cli => write!(f, "命令列"),
+        }
+    }
+}
+
+/// 設定值依序由 預設 → cfg.json → 環境變數 (STOCK2_<KEY>) → 命令列 (--key=value) 覆蓋
+#[derive(Serialize, Deserialize, Clone)]
+#[serde(default)]
 pub struct CfgData {
+    /// 離線模式只用本機的 data/ 與 company_map.json，所有連網的動作都會回傳錯誤
+    pub offline: bool,
     pub fugle_api_key: String,
+    /// Fugle 行情 API 的網址，測試時指向本機的假伺服器
+    pub fugle_base_url: String,
+    /// 依 Fugle 方案調整，例如基本方案每分鐘 60 次
+    pub fugle_requests_per_minute: u32,
+    pub fugle_concurrency: usize,
+    /// 429 沒有帶 Retry-After 時等待的毫秒數
+    pub fugle_retry_after_ms: u64,
+    /// 回應錯誤時隔多少毫秒重試
+    pub fugle_retry_delay_ms: u64,
+    /// TWSE 網站 (每日收盤行情 MI_INDEX) 的網址
+    pub twse_base_url: String,
+    /// TWSE OpenAPI (上市公司清單) 的網址
+    pub twse_openapi_url: String,
+    /// TPEx OpenAPI (上櫃公司清單) 的網址
+    pub tpex_openapi_url: String,
+    /// TWSE 每日收盤行情的請求速率，官方建議每 5 秒不超過 3 次
+    pub twse_requests_per_minute: u32,
+    /// 缺少的交易日不超過這個天數時用 TWSE 全市場日報補，超過就用 Fugle 逐檔回補
+    pub twse_daily_max_days: usize,
+    pub storage_backend: StorageBackend,
+    pub sqlite_path: String,
+    pub data_dir: String,
+    pub cache_dir: String,
+    pub company_map_path: String,
+    /// 把每個 K 棒 API 的原始回應壓縮存到 archive_dir，之後可以從封存重建資料
+    pub archive_responses: bool,
+    pub archive_dir: String,
+    pub company_history_path: String,
+    /// 年度抓取的進度，中斷後從這裡續傳
+    pub fetch_journal_path: String,
+    /// 公司清單超過幾天自動重新下載，0 代表只在選單手動更新
+    pub company_map_refresh_days: u64,
+    /// 自訂開休市日 (臨時颱風假等)，檔案不存在就只用內建的日曆
+    pub calendar_path: String,
+    /// 個股停止買賣的期間，資料完整性檢查不會把這段期間當成缺漏
+    pub suspensions_path: String,
+    pub year_from: i32,
+    /// 過了這一年之後照樣讀取、抓取到今年 (見 years)
+    pub year_to: i32,
+    /// MACD 黃金交叉的大成交量門檻 (張)
+    pub large_volume: u64,
+    /// 複合條件往回看的天數
+    pub look_back_days: usize,
+    /// 十字線往回找最大最小值的天數
+    pub doji_range: usize,
+    /// 實體超過收盤價的這個比例才算長紅/長黑
+    pub long_body_ratio: f64,
+    /// MACD 等指標用的價格，K 線形態與漲停判斷一律用原始價格
+    pub indicator_price_view: PriceView,
+    /// 要納入的 ETF: 代號 → 名稱
+    pub etfs: BTreeMap<String, String>,
+    /// 具名的股票池，至少要有 default
+    pub universes: BTreeMap<String, Universe>,
+    /// 掃描名稱 → 股票池名稱，沒列出的掃描用 default
+    pub scan_universes: BTreeMap<String, String>,
+    #[serde(skip)]
+    sources: BTreeMap<String, CfgSource>,
+}
+
+impl Default for CfgData {
+    fn default() -> Self {
+        CfgData {
+            offline: false,
+            fugle_api_key: String::new(),
+            fugle_base_url: "https://api.fugle.tw/marketdata/v1.0".to_string(),
+            fugle_requests_per_minute: 60,
+            fugle_concurrency: 4,
+            fugle_retry_after_ms: 5000,
+            fugle_retry_delay_ms: 3000,
+            twse_base_url: "https://www.twse.com.tw".to_string(),
+            twse_openapi_url: "https://openapi.twse.com.tw/v1".to_string(),
+            tpex_openapi_url: "https://www.tpex.org.tw/openapi/v1".to_string(),
+            twse_requests_per_minute: 20,
+            twse_daily_max_days: 10,
+            storage_backend: StorageBackend::Json,
+            sqlite_path: "stock2.db".to_string(),
+            data_dir: "data".to_string(),
+            cache_dir: "cache".to_string(),
+            company_map_path: "company_map.json".to_string(),
+            archive_responses: false,
+            archive_dir: "archive".to_string(),
+            company_history_path: "company_history.json".to_string(),
+            fetch_journal_path: "fetch_journal.json".to_string(),
+            company_map_refresh_days: 7,
+            calendar_path: "calendar.json".to_string(),
+            suspensions_path: "suspensions.json".to_string(),
+            year_from: 2019,
+            year_to: 2026,
+            large_volume: 2000,
+            look_back_days: 20 * 3, // 看三個月的資料
+            doji_range: 20 * 6,
+            long_body_ratio: 0.05,
+            indicator_price_view: PriceView::Adjusted,
+            etfs: [
+                ("0050", "元大台灣50"),
+                ("0056", "元大高股息"),
+                ("006208", "富邦台50"),
+                ("00692", "富邦公司治理"),
+                ("00878", "國泰永續高股息"),
+                ("00919", "群益台灣精選高息"),
+            ]
+            .into_iter()
+            .map(|(stock_no, name)| (stock_no.to_string(), name.to_string()))
+            .collect(),
+            universes: universe::default_universes(),
+            scan_universes: BTreeMap::new(),
+            sources: BTreeMap::new(),
+        }
+    }
 }
 
 impl CfgData {
-    fn read_from_file() -> Self {
-        let file = fs::File::open(CFG_FILE).unwrap();
+    fn read_from_file() -> Result<Option<Map<String, Value>>> {
+        if !Path::new(CFG_FILE).exists() {
+            return Ok(None);
+        }
+
+        let file = fs::File::open(CFG_FILE)
+            .map_err(|e| Error::Config(format!("無法開啟 {CFG_FILE}: {e}")))?;
         let reader = std::io::BufReader::new(file);
-        serde_json::from_reader::<_, CfgData>(reader).unwrap()
+        match serde_json::from_reader::<_, Value>(reader) {
+            Ok(Value::Object(map)) => Ok(Some(map)),
+            Ok(_) => Err(Error::Config(format!("{CFG_FILE} 必須是 JSON 物件"))),
+            Err(e) => Err(Error::Config(format!("{CFG_FILE} 格式錯誤: {e}"))),
+        }
+    }
+
+    pub fn new() -> Result<Self> {
+        let args: Vec<String> = std::env::args().skip(1).collect();
+        Self::load(&args)
+    }
+
+    fn load(args: &[String]) -> Result<Self> {
+        Self::layered(Self::read_from_file()?, |key| std::env::var(key).ok(), args)
+    }
+
+    /// 依序套用 cfg.json 的內容、env(環境變數名稱) 與命令列參數
+    fn layered(
+        file_map: Option<Map<String, Value>>,
+        env: impl Fn(&str) -> Option<String>,
+        args: &[String],
+    ) -> Result<Self> {
+        let Value::Object(defaults) = serde_json::to_value(CfgData::default())? else {
+            unreachable!("CfgData 一定序列化成物件");
+        };
+        let mut merged = defaults.clone();
+        let mut sources: BTreeMap<String, CfgSource> = defaults
+            .keys()
+            .map(|key| (key.clone(), CfgSource::Default))
+            .collect();
+
+        if let Some(file_map) = file_map {
+            for (key, value) in file_map {
+                if !defaults.contains_key(&key) {
+                    return Err(Error::Config(format!("{CFG_FILE} 有未知的設定項目: {key}")));
+                }
+                merged.insert(key.clone(), value);
+                sources.insert(key, CfgSource::File);
+            }
+        }
+
+        for (key, default) in &defaults {
+            let env_key = format!("{ENV_PREFIX}{}", key.to_uppercase());
+            if let Some(raw) = env(&env_key) {
+                merged.insert(key.clone(), parse_override(default, &raw));
+                sources.insert(key.clone(), CfgSource::Env);
+            }
+        }
+
+        for (key, raw) in parse_args(args)? {
+            let Some(default) = defaults.get(&key) else {
+                return Err(Error::Config(format!("未知的命令列參數: --{key}")));
+            };
+            merged.insert(key.clone(), parse_override(default, &raw));
+            sources.insert(key, CfgSource::Cli);
+        }
+
+        let mut cfg: CfgData = serde_json::from_value(Value::Object(merged))
+            .map_err(|e| Error::Config(format!("設定值型別錯誤: {e}")))?;
+        cfg.sources = sources;
+        cfg.validate()?;
+        Ok(cfg)
+    }
+
+    fn validate(&self) -> Result<()> {
+        let mut errors = Vec::new();
+        if !self.offline && self.fugle_api_key.is_empty() {
+            errors.push("fugle_api_key 未設定".to_string());
+        }
+        if self.fugle_requests_per_minute == 0 {
+            errors.push("fugle_requests_per_minute 必須大於 0".to_string());
+        }
+        if self.fugle_concurrency == 0 {
+            errors.push("fugle_concurrency 必須大於 0".to_string());
+        }
+        if self.twse_requests_per_minute == 0 {
+            errors.push("twse_requests_per_minute 必須大於 0".to_string());
+        }
+        if self.year_from < 1990 || self.year_from > self.year_to {
+            errors.push(format!(
+                "年份範圍 {} ~ {} 不正確",
+                self.year_from, self.year_to
+            ));
+        }
+        // 烏鴉躍空會往回看 6 天
+        if self.look_back_days < 6 {
+            errors.push("look_back_days 至少要 6 天".to_string());
+        }
+        if self.doji_range == 0 {
+            errors.push("doji_range 必須大於 0".to_string());
+        }
+        if !(self.long_body_ratio > 0.0 && self.long_body_ratio < 1.0) {
+            errors.push("long_body_ratio 必須介於 0 ~ 1".to_string());
+        }
+
+        if !self.universes.contains_key(DEFAULT_UNIVERSE) {
+            errors.push(format!("universes 必須包含 {DEFAULT_UNIVERSE}"));
+        }
+        for (scan, universe) in &self.scan_universes {
+            if !consts::SCAN_NAMES.contains(&scan.as_str()) {
+                errors.push(format!(
+                    "scan_universes 有未知的掃描: {scan} (可用: {})",
+                    consts::SCAN_NAMES.join(", ")
+                ));
+            }
+            if !self.universes.contains_key(universe) {
+                errors.push(format!(
+                    "scan_universes.{scan} 指定的股票池 {universe} 不存在"
+                ));
+            }
+        }
+
+        if errors.is_empty() {
+            Ok(())
+        } else {
+            Err(Error::Config(errors.join("; ")))
+        }
+    }
+
+    /// 儲存讀取的年份範圍，year_to 已經過了的話延伸到今年，補抓寫進的今年年度檔才讀得到
+    pub fn years(&self) -> RangeInclusive<i32> {
+        self.year_from..=self.year_to.max(Local::now().year())
+    }
+
+    /// 某個掃描要用的股票池
+    pub fn universe_for(&self, scan: &str) -> &Universe {
+        let name = self
+            .scan_universes
+            .get(scan)
+            .map(String::as_str)
+            .unwrap_or(DEFAULT_UNIVERSE);
+        &self.universes[name]
     }
 
-    pub fn new() -> Self {
-        Self::read_from_file()
+    pub fn print(&self) {
+        let Ok(Value::Object(values)) = serde_json::to_value(self) else {
+            return;
+        };
+        println!("目前生效的設定:");
+        for (key, value) in values {
+            let value = if key == "fugle_api_key" {
+                mask(value.as_str().unwrap_or_default())
+            } else {
+                value.to_string()
+            };
+            let source = self
+                .sources
+                .get(&key)
+                .copied()
+                .unwrap_or(CfgSource::Default);
+            println!("    {key:<28}= {value:<24}({source})");
+        }
+    }
+}
+
+/// 命令列參數支援 --key=value 與 --key value，key 裡的 - 視同 _；
+/// 後面沒有值的 --key (例如 --offline) 視為 true
+fn parse_args(args: &[String]) -> Result<Vec<(String, String)>> {
+    let mut overrides = Vec::new();
+    let mut iter = args.iter().peekable();
+    while let Some(arg) = iter.next() {
+        let Some(arg) = arg.strip_prefix("--") else {
+            return Err(Error::Config(format!("無法辨識的命令列參數: {arg}")));
+        };
+        let (key, raw) = match arg.split_once('=') {
+            Some((key, raw)) => (key.to_string(), raw.to_string()),
+            None => match iter.next_if(|next| !next.starts_with("--")) {
+                Some(raw) => (arg.to_string(), raw.clone()),
+                None => (arg.to_string(), "true".to_string()),
+            },
+        };
+        overrides.push((key.replace('-', "_"), raw));
+    }
+    Ok(overrides)
+}
+
+/// 字串型別的設定直接當字串，其他型別當成 JSON 值解析
+fn parse_override(default: &Value, raw: &str) -> Value {
+    if default.is_string() {
+        Value::String(raw.to_string())
+    } else {
+        serde_json::from_str(raw).unwrap_or_else(|_| Value::String(raw.to_string()))
+    }
+}
+
+fn mask(secret: &str) -> String {
+    if secret.is_empty() {
+        "\"\"".to_string()
+    } else {
+        format!("\"{}****\"", secret.chars().take(4).collect::<String>())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    fn file(value: Value) -> Option<Map<String, Value>> {
+        match value {
+            Value::Object(map) => Some(map),
+            _ => None,
+        }
+    }
+
+    fn args(args: &[&str]) -> Vec<String> {
+        args.iter().map(|arg| arg.to_string()).collect()
+    }
+
+    fn load(file_map: Value, env: &[(&str, &str)], cli: &[&str]) -> Result<CfgData> {
+        CfgData::layered(
+            file(file_map),
+            |key| {
+                env.iter()
+                    .find(|(name, _)| *name == key)
+                    .map(|(_, value)| value.to_string())
+            },
+            &args(cli),
+        )
     }
 
-    // pub fn print(&self) {
-    //     println!("Fugle API Key: {}", self.fugle_api_key);
-    // }
+    fn config_error(result: Result<CfgData>) -> String {
+        match result {
+            Err(Error::Config(msg)) => msg,
+            Err(e) => panic!("不是設定錯誤: {e}"),
+            Ok(_) => panic!("應該要失敗"),
+        }
+    }
+
+    #[test]
+    fn later_layers_override_earlier_ones() {
+        let cfg = load(
+            json!({ "fugle_api_key": "key", "year_from": 2015, "large_volume": 3000, "doji_range": 50 }),
+            &[("STOCK2_LARGE_VOLUME", "4000"), ("STOCK2_DOJI_RANGE", "60")],
+            &["--doji-range=70", "--data_dir", "other", "--offline"],
+        )
+        .unwrap();
+
+        assert_eq!(cfg.year_from, 2015);
+        assert_eq!(cfg.large_volume, 4000);
+        assert_eq!(cfg.doji_range, 70);
+        assert_eq!(cfg.data_dir, "other");
+        assert!(cfg.offline);
+        assert_eq!(cfg.look_back_days, CfgData::default().look_back_days);
+
+        let source = |key: &str| cfg.sources[key];
+        assert!(source("year_from") == CfgSource::File);
+        assert!(source("large_volume") == CfgSource::Env);
+        assert!(source("doji_range") == CfgSource::Cli);
+        assert!(source("look_back_days") == CfgSource::Default);
+    }
+
+    #[test]
+    fn rejects_bad_and_unknown_keys() {
+        let key = json!({ "fugle_api_key": "key" });
+
+        let msg = config_error(load(key.clone(), &[], &["--year_from=abc"]));
+        assert!(msg.contains("型別錯誤"), "{msg}");
+        let msg = config_error(load(key.clone(), &[], &["2019"]));
+        assert!(msg.contains("無法辨識"), "{msg}");
+        let msg = config_error(load(key.clone(), &[], &["--no_such_key=1"]));
+        assert!(msg.contains("no_such_key"), "{msg}");
+        let msg = config_error(load(json!({ "no_such_key": 1 }), &[], &[]));
+        assert!(msg.contains("no_such_key"), "{msg}");
+    }
+
+    #[test]
+    fn validate_reports_every_range_error() {
+        let msg = config_error(load(
+            json!({
+                "year_from": 2026,
+                "year_to": 2019,
+                "fugle_concurrency": 0,
+                "look_back_days": 3,
+                "long_body_ratio": 1.5,
+            }),
+            &[],
+            &[],
+        ));
+
+        assert!(msg.contains("fugle_api_key 未設定"), "{msg}");
+        assert!(msg.contains("年份範圍 2026 ~ 2019"), "{msg}");
+        assert!(msg.contains("fugle_concurrency"), "{msg}");
+        assert!(msg.contains("look_back_days"), "{msg}");
+        assert!(msg.contains("long_body_ratio"), "{msg}");
+
+        let msg = config_error(load(
+            json!({ "offline": true, "year_from": 1980 }),
+            &[],
+            &[],
+        ));
+        assert!(msg.contains("年份範圍 1980"), "{msg}");
+    }
+
+    #[test]
+    fn years_extend_past_year_to() {
+        let cfg = CfgData {
+            year_from: 2019,
+            year_to: 2020,
+            ..Default::default()
+        };
+
+        let years = cfg.years();
+
+        assert_eq!(*years.start(), 2019);
+        assert_eq!(*years.end(), Local::now().year().max(2020));
+    }
 }
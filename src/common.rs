@@ -28,28 +28,24 @@ pub fn str_volume(volume: u64) -> String {
 }
 
 use crate::stocks::data::Data;
-use crate::stocks::data_company::DataCompany;
-
-pub fn get_company_data<'a>(data: &'a Data, stock_no: &str) -> &'a DataCompany {
-    data.data_company.get(stock_no).expect("找不到股票資料")
-}
 
 use crate::stocks::data_company::StockDataWithNo;
 
 pub fn print_lower_30_percent_list(data: &Data, results: &[StockDataWithNo]) {
     println!(
-        "{:<9}{:<5}{:>6}{:>6}{:>8}  公司名稱",
+        "{:<9}{:<5}{:>6}{:>6}{:>8}  市場  公司名稱",
         "日期", "台股", "成交張數", "收盤價", "-30%",
     );
     for result in results {
         println!(
-            "{:<11}{:<6}{:>10}{:>9.2}{:>9.2}  {:<20}",
+            "{:<11}{:<6}{:>10}{:>9.2}{:>9.2}  {}  {:<20}",
             result.stock_data.date,
             result.stock_no,
             str_volume(result.stock_data.volume),
             result.stock_data.close,
             result.stock_data.close * 0.7,
-            data.company_map.get_name(&result.stock_no),
+            data.company_map.display_market(&result.stock_no),
+            data.company_map.display_name(&result.stock_no),
         );
     }
 }
@@ -1,60 +1,19 @@
-pub const YEAR_FROM: i32 = 2019;
-pub const YEAR_TO: i32 = 2026;
-
-#[rustfmt::skip]
-pub const IGNORED_STOCKS: &[&str] = &[
-    "1342", "1563", "1623",
-    "2211", 
-    "2241", "2247", "2248", 
-    "2250", "2254", "2258",
-    "2432", 
-    "2645", "2646", 
-    "2753", "2762",
-    "2945",
-    "3135", "3138", "3150", "3168",
-    "3447",
-    "3543", "3592", 
-    "3714", "3715", "3716", "3717", 
-    "4440", "4441", 
-    "4569", 
-    "4581", "4583", "4585", "4588", 
-    "4590",
-    "4770", "4771",
-    "4949",
-    "5222", "5244", "5292",
-    "5546",
-    "6272", 
-    "6515", "6526", "6534", "6550", 
-    "6585", "6598",
-    "6606",
-    "6614", "6645", "6657", "6658", 
-    "6689", "6691", "6695", 
-    "6719", "6722", "6742", "6743",
-    "6753", "6754", "6756", "6757", "6768",
-    "6770", "6771", "6776",
-    "6781", "6782", "6789", 
-    "6790", "6792", "6794", "6796", "6799",
-    "6805", "6806", "6807",
-    "6830", "6831", "6834", "6835", "6838", 
-    "6854", "6858",
-    "6861", "6862", "6863", "6869", 
-    "6873",
-    "6885", "6887", "6890",
-    "6901", "6902", "6906", "6909", 
-    "6914", "6916", "6918", "6919",
-    "6921", "6923", "6924", "6928",
-    "6931", "6933", "6934", "6936", "6937",
-    "6944", "6949", 
-    "6951", "6952", "6955", "6957", "6958",
-    "6962", "6965", "6969",
-    "6988", "6994",
-    "7610", "7631",
-    "7705",
-    "7711", "7721", "7722", "7732", "7736", 
-    "7730", "7740", "7749", "7750",
-    "7765", "7769", 
-    "7780", "7786", "7788", "7791", "7795", "7799", 
-    "7823",
-    "8045", "8162",
-    "8438", "8487",
+/// 可以在 cfg.json 的 scan_universes 指定股票池的掃描
+pub const SCAN_NAMES: &[&str] = &[
+    "long_red_candle",
+    "doji",
+    "lower_shadow",
+    "hanging_man",
+    "bullish_engulfing",
+    "macd",
+    "macd_divergence",
+    "kd",
+    "moving_average",
+    "complex_hanging_man",
+    "dark_cloud_cover",
+    "bullish_harami",
+    "bearish_harami",
+    "bullish_harami_reversal",
+    "bearish_harami_reversal",
+    "upside_gap_two_crows",
 ];
@@ -0,0 +1,71 @@
+use std::fmt;
+
+/// 資料層共用的錯誤型別
+#[derive(Debug)]
+pub enum Error {
+    /// cfg.json 缺少或內容不正確
+    Config(String),
+    Io(std::io::Error),
+    /// JSON 解析失敗
+    Parse(serde_json::Error),
+    Http(reqwest::Error),
+    Database(rusqlite::Error),
+    /// API 回應了錯誤，或重試後仍拿不到資料
+    Api(String),
+    UnknownStock(String),
+    /// 離線模式下要連網，內容是原本要做的事
+    Offline(String),
+}
+
+pub type Result<T> = std::result::Result<T, Error>;
+
+impl fmt::Display for Error {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Error::Config(msg) => write!(f, "設定錯誤: {msg}"),
+            Error::Io(e) => write!(f, "檔案讀寫錯誤: {e}"),
+            Error::Parse(e) => write!(f, "JSON 解析錯誤: {e}"),
+            Error::Http(e) => write!(f, "HTTP 錯誤: {e}"),
+            Error::Database(e) => write!(f, "資料庫錯誤: {e}"),
+            Error::Api(msg) => write!(f, "API 錯誤: {msg}"),
+            Error::UnknownStock(stock_no) => write!(f, "找不到股票代號: {stock_no}"),
+            Error::Offline(what) => write!(f, "離線模式，不會連網: {what}"),
+        }
+    }
+}
+
+impl std::error::Error for Error {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            Error::Io(e) => Some(e),
+            Error::Parse(e) => Some(e),
+            Error::Http(e) => Some(e),
+            Error::Database(e) => Some(e),
+            _ => None,
+        }
+    }
+}
+
+impl From<std::io::Error> for Error {
+    fn from(e: std::io::Error) -> Self {
+        Error::Io(e)
+    }
+}
+
+impl From<serde_json::Error> for Error {
+    fn from(e: serde_json::Error) -> Self {
+        Error::Parse(e)
+    }
+}
+
+impl From<reqwest::Error> for Error {
+    fn from(e: reqwest::Error) -> Self {
+        Error::Http(e)
+    }
+}
+
+impl From<rusqlite::Error> for Error {
+    fn from(e: rusqlite::Error) -> Self {
+        Error::Database(e)
+    }
+}
@@ -1,13 +1,21 @@
-use std::error::Error;
-
+use chrono::DateTime;
+use reqwest::header::RETRY_AFTER;
+use reqwest::{StatusCode, Url};
 use serde::{Deserialize, Serialize};
 use tokio::time::Duration;
 
 use crate::cfg::data::CfgData;
-use crate::consts;
+use crate::error::{Error, Result};
+use crate::provider::rate_limiter::RateLimiter;
 use crate::stocks::data_company::StockData;
+use crate::stocks::timeframe::Timeframe;
+use crate::storage::archive::{Archive, ArchivedResponse};
 
 const MODULE_NAME: &str = "fugle::stocks";
+pub const ARCHIVE_SOURCE: &str = "fugle";
+const MAX_RETRIES: usize = 3;
+/// 連續 429 超過這個次數視為額度用完，不再等待
+const MAX_RATE_LIMITED: usize = 10;
 
 #[derive(Debug, Serialize, Deserialize)]
 struct FugleStockResponse {
@@ -15,24 +23,47 @@ struct FugleStockResponse {
     pub data: Vec<StockData>,
 }
 
-pub async fn fetch(
+/// 送 Fugle 請求用的 client，整個 provider 共用一個
+pub fn client() -> Result<reqwest::Client> {
+    Ok(reqwest::Client::builder()
+        .timeout(Duration::from_secs(10))
+        .build()?)
+}
+
+/// 抓取 from ~ to (YYYY-MM-DD，含頭尾) 之間未還原的原始 K 棒，還原因子由 stocks::adjustment 另外計算
+///
+/// 分K沒有成交金額與漲跌，date 會統一成 YYYY-MM-DDTHH:MM:SS+08:00
+pub async fn fetch_range(
     cfg: &CfgData,
+    client: &reqwest::Client,
+    limiter: &RateLimiter,
     stock_no: &str,
-    year: &str,
-) -> Result<Vec<StockData>, Box<dyn Error>> {
-    let mut retry_count = 0;
-    let max_retries = 3;
-    loop {
-        let from = format!("{}-01-01", year);
-        let to = format!("{}-12-31", year);
-        let url = format!(
-            "https://api.fugle.tw/marketdata/v1.0/stock/historical/candles/{stock_no}?from={from}&to={to}&timeframe=D&adjusted=true&fields=open,high,low,close,volume,turnover,change&sort=asc",
-        );
+    timeframe: Timeframe,
+    from: &str,
+    to: &str,
+) -> Result<Vec<StockData>> {
+    if cfg.offline {
+        return Err(Error::Offline(format!(
+            "Fugle {stock_no} {timeframe} {from} ~ {to}"
+        )));
+    }
 
-        let client = reqwest::Client::builder()
-            .timeout(Duration::from_secs(10))
-            .build()?;
+    let mut retry_count = 0;
+    let mut rate_limited_count = 0;
+    let fields = if timeframe.is_intraday() {
+        "open,high,low,close,volume"
+    } else {
+        "open,high,low,close,volume,turnover,change"
+    };
+    let url = format!(
+        "{}/stock/historical/candles/{stock_no}?from={from}&to={to}&timeframe={}&adjusted=false&fields={fields}&sort=asc",
+        cfg.fugle_base_url,
+        timeframe.fugle_param(),
+    );
+    let archive = Archive::from_cfg(cfg);
 
+    loop {
+        limiter.acquire().await;
         let response = client
             .get(&url)
             .header("User-Agent", "Mozilla/5.0 (Windows NT 10.0; Win64; x64) AppleWebKit/537.36 (KHTML, like Gecko) Chrome/144.0.0.0 Safari/537.36")
@@ -41,34 +72,328 @@ pub async fn fetch(
             .send()
             .await?;
 
+        let status = response.status();
+        let headers = response.headers().clone();
         // 先拿到原始文字，避免解析 JSON 失敗時看不到原因
         let body_text = response.text().await?;
         // println!("[{MODULE_NAME}] Raw response: {body_text}");
-        let parsed: FugleStockResponse = match serde_json::from_str(&body_text) {
-            Ok(data) => data,
-            Err(e) => {
-                if body_text.contains("Rate limit exceeded") {
-                    println!("[{MODULE_NAME}] API rate limit exceeded. 5 秒後重試...");
-                    tokio::time::sleep(Duration::from_secs(5)).await;
-                    continue; // 等待後重試
-                }
-                if consts::IGNORED_STOCKS.contains(&stock_no) {
-                    println!("[{MODULE_NAME}] 股票 {stock_no} 在 {year} 沒有資料，列管已忽略");
-                    return Ok(vec![]); // 回傳空資料
-                }
-                println!("[{MODULE_NAME}] JSON 解析失敗: {e}");
-                println!("[{MODULE_NAME}] body_text: {body_text}");
-                println!("[{MODULE_NAME}] 3 秒後重試...");
-                tokio::time::sleep(Duration::from_secs(3)).await;
-                retry_count += 1;
-                if retry_count >= max_retries {
-                    println!("[{MODULE_NAME}] 已達最大重試次數，放棄 {stock_no} 的資料");
-                    return Ok(vec![]); // 回傳空資料
-                }
-                continue; // 解析失敗，等待後重試
+        if let Some(archive) = &archive {
+            archive.record(
+                ARCHIVE_SOURCE,
+                &ArchivedResponse::new(&url, status.as_u16(), &headers, &body_text),
+            );
+        }
+
+        if status == StatusCode::TOO_MANY_REQUESTS {
+            rate_limited_count += 1;
+            if rate_limited_count >= MAX_RATE_LIMITED {
+                println!("[{MODULE_NAME}] 連續 {MAX_RATE_LIMITED} 次 429，放棄 {stock_no} 的資料");
+                return Err(Error::Api(format!(
+                    "{stock_no} {from} ~ {to} 連續 {MAX_RATE_LIMITED} 次 429，可能是額度已用完"
+                )));
             }
+            // 依伺服器給的 Retry-After 等待，沒有的話用預設值
+            let retry_after = headers
+                .get(RETRY_AFTER)
+                .and_then(|v| v.to_str().ok())
+                .and_then(|v| v.trim().parse::<u64>().ok())
+                .map(Duration::from_secs)
+                .unwrap_or(Duration::from_millis(cfg.fugle_retry_after_ms));
+            limiter.pause(retry_after).await;
+            continue; // 等待後重試，只計入 429 的次數上限
+        }
+
+        let error = if status.is_success() {
+            match parse(&body_text, timeframe) {
+                Ok(candles) => return Ok(candles),
+                Err(e) => format!("JSON 解析失敗: {e}"),
+            }
+        } else {
+            format!("HTTP 狀態碼 {status}")
         };
 
-        return Ok(parsed.data);
+        println!("[{MODULE_NAME}] {stock_no} {error}");
+        println!("[{MODULE_NAME}] body_text: {body_text}");
+        retry_count += 1;
+        if retry_count >= MAX_RETRIES {
+            println!("[{MODULE_NAME}] 已達最大重試次數，放棄 {stock_no} 的資料");
+            return Err(Error::Api(format!(
+                "{stock_no} {from} ~ {to} 重試 {MAX_RETRIES} 次後仍失敗: {error}"
+            )));
+        }
+        let retry_delay = Duration::from_millis(cfg.fugle_retry_delay_ms);
+        println!("[{MODULE_NAME}] {} 秒後重試...", retry_delay.as_secs_f64());
+        tokio::time::sleep(retry_delay).await;
+    }
+}
+
+/// 解析 historical candles 的回應，分K的時間會先正規化
+pub fn parse(body_text: &str, timeframe: Timeframe) -> serde_json::Result<Vec<StockData>> {
+    let mut parsed = serde_json::from_str::<FugleStockResponse>(body_text)?;
+    if timeframe.is_intraday() {
+        parsed.data.iter_mut().for_each(normalize_time);
+    }
+    Ok(parsed.data)
+}
+
+/// 從 fetch_range 的網址取回股票代號與週期，重播封存時用
+pub fn parse_url(url: &str) -> Option<(String, Timeframe)> {
+    let url = Url::parse(url).ok()?;
+    let mut segments = url.path_segments()?.rev();
+    let stock_no = segments.next()?.to_string();
+    if segments.next()? != "candles" {
+        return None;
+    }
+    let timeframe = url
+        .query_pairs()
+        .find(|(name, _)| name == "timeframe")
+        .and_then(|(_, value)| Timeframe::parse(&value))
+        .unwrap_or_default();
+    Some((stock_no, timeframe))
+}
+
+/// Fugle 分K的時間帶毫秒 (2026-02-11T09:00:00.000+08:00)，去掉毫秒方便比對與排序
+fn normalize_time(candle: &mut StockData) {
+    if let Ok(time) = DateTime::parse_from_rfc3339(&candle.date) {
+        candle.date = time.format("%Y-%m-%dT%H:%M:%S%:z").to_string();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::mock_server::{self, MockResponse, MockServer};
+
+    const CANDLES: &str = include_str!("../../fixtures/fugle/candles_2330_2026.json");
+    const INTRADAY: &str = include_str!("../../fixtures/fugle/candles_2330_60m.json");
+    const EMPTY: &str = include_str!("../../fixtures/fugle/candles_empty.json");
+    const RATE_LIMIT: &str = include_str!("../../fixtures/fugle/rate_limit.json");
+    const MALFORMED: &str = include_str!("../../fixtures/fugle/malformed.json");
+
+    fn cfg(base_url: &str) -> CfgData {
+        let mut cfg = CfgData::default();
+        cfg.fugle_api_key = "test".to_string();
+        cfg.fugle_base_url = base_url.to_string();
+        // 測試時不要真的等那麼久
+        cfg.fugle_retry_after_ms = 10;
+        cfg.fugle_retry_delay_ms = 10;
+        cfg
+    }
+
+    async fn fetch_daily(server: &MockServer) -> Result<Vec<StockData>> {
+        let limiter = RateLimiter::new(6000);
+        fetch_range(
+            &cfg(&server.base_url),
+            &client().unwrap(),
+            &limiter,
+            "2330",
+            Timeframe::Day,
+            "2026-01-01",
+            "2026-12-31",
+        )
+        .await
+    }
+
+    fn rate_limited() -> MockResponse {
+        MockResponse::new(429, RATE_LIMIT)
+    }
+
+    #[tokio::test]
+    async fn returns_candles() {
+        let server = MockServer::start(vec![MockResponse::ok(CANDLES)]).await;
+
+        let candles = fetch_daily(&server).await.unwrap();
+
+        assert_eq!(
+            server.requests(),
+            [
+                "/stock/historical/candles/2330?from=2026-01-01&to=2026-12-31&timeframe=D&adjusted=false&fields=open,high,low,close,volume,turnover,change&sort=asc"
+            ]
+        );
+        assert_eq!(candles.len(), 3);
+        assert_eq!(candles[0].date, "2026-01-02");
+        assert_eq!(candles[0].close, 1585.0);
+        assert_eq!(candles[0].turnover, 62_469_965_712);
+        assert_eq!(candles[0].change, Some(35.0));
+    }
+
+    #[tokio::test]
+    async fn empty_year_returns_no_candles() {
+        let server = MockServer::start(vec![MockResponse::ok(EMPTY)]).await;
+        assert!(fetch_daily(&server).await.unwrap().is_empty());
+        assert_eq!(server.requests().len(), 1);
+    }
+
+    #[tokio::test]
+    async fn intraday_times_are_normalized() {
+        let server = MockServer::start(vec![MockResponse::ok(INTRADAY)]).await;
+        let limiter = RateLimiter::new(6000);
+
+        let candles = fetch_range(
+            &cfg(&server.base_url),
+            &client().unwrap(),
+            &limiter,
+            "2330",
+            Timeframe::Min60,
+            "2026-02-11",
+            "2026-02-11",
+        )
+        .await
+        .unwrap();
+
+        assert!(server.requests()[0].contains("timeframe=60&"));
+        assert!(server.requests()[0].contains("fields=open,high,low,close,volume&"));
+        assert_eq!(candles[0].date, "2026-02-11T09:00:00+08:00");
+        assert_eq!(candles[1].date, "2026-02-11T10:00:00+08:00");
+        assert_eq!(candles[1].turnover, 0);
+    }
+
+    #[tokio::test]
+    async fn rate_limit_waits_retry_after_without_counting_retries() {
+        // 連續 429 的次數超過 MAX_RETRIES 也不會放棄
+        let mut responses = vec![rate_limited().header("Retry-After", "0"); MAX_RETRIES + 1];
+        responses.push(MockResponse::ok(CANDLES));
+        let server = MockServer::start(responses).await;
+
+        assert_eq!(fetch_daily(&server).await.unwrap().len(), 3);
+        assert_eq!(server.requests().len(), MAX_RETRIES + 2);
+    }
+
+    #[tokio::test]
+    async fn persistent_rate_limit_gives_up() {
+        let server = MockServer::start(vec![rate_limited().header("Retry-After", "0")]).await;
+
+        let error = fetch_daily(&server).await.unwrap_err();
+
+        assert!(matches!(error, Error::Api(_)), "{error}");
+        assert_eq!(server.requests().len(), MAX_RATE_LIMITED);
+    }
+
+    #[tokio::test]
+    async fn rate_limit_without_retry_after_uses_default_wait() {
+        let server = MockServer::start(vec![rate_limited(), MockResponse::ok(CANDLES)]).await;
+
+        assert_eq!(fetch_daily(&server).await.unwrap().len(), 3);
+        assert_eq!(server.requests().len(), 2);
+    }
+
+    #[tokio::test]
+    async fn malformed_json_is_retried() {
+        let server =
+            MockServer::start(vec![MockResponse::ok(MALFORMED), MockResponse::ok(CANDLES)]).await;
+
+        assert_eq!(fetch_daily(&server).await.unwrap().len(), 3);
+        assert_eq!(server.requests().len(), 2);
+    }
+
+    #[tokio::test]
+    async fn http_error_is_retried() {
+        let server = MockServer::start(vec![
+            MockResponse::new(500, "{}"),
+            MockResponse::ok(CANDLES),
+        ])
+        .await;
+
+        assert_eq!(fetch_daily(&server).await.unwrap().len(), 3);
+        assert_eq!(server.requests().len(), 2);
+    }
+
+    #[tokio::test]
+    async fn gives_up_after_max_retries() {
+        let server = MockServer::start(vec![MockResponse::ok(MALFORMED)]).await;
+
+        let result = fetch_daily(&server).await;
+
+        assert!(matches!(result, Err(Error::Api(message)) if message.contains("JSON 解析失敗")));
+        assert_eq!(server.requests().len(), MAX_RETRIES);
+    }
+
+    #[tokio::test]
+    async fn offline_never_connects() {
+        let server = MockServer::start(vec![MockResponse::ok(CANDLES)]).await;
+        let mut cfg = cfg(&server.base_url);
+        cfg.offline = true;
+        let limiter = RateLimiter::new(6000);
+
+        let result = fetch_range(
+            &cfg,
+            &client().unwrap(),
+            &limiter,
+            "2330",
+            Timeframe::Day,
+            "2026-01-01",
+            "2026-12-31",
+        )
+        .await;
+
+        assert!(matches!(result, Err(Error::Offline(_))));
+        assert!(server.requests().is_empty());
+    }
+
+    #[tokio::test]
+    async fn connection_error_is_not_retried() {
+        let base_url = mock_server::unused_base_url().await;
+        let limiter = RateLimiter::new(6000);
+
+        let result = fetch_range(
+            &cfg(&base_url),
+            &client().unwrap(),
+            &limiter,
+            "2330",
+            Timeframe::Day,
+            "2026-01-01",
+            "2026-12-31",
+        )
+        .await;
+
+        assert!(matches!(result, Err(Error::Http(_))));
+    }
+
+    #[tokio::test]
+    async fn archived_responses_replay_to_the_same_candles() {
+        let server = MockServer::start(vec![rate_limited(), MockResponse::ok(CANDLES)]).await;
+        let dir = std::env::temp_dir().join(format!("stock2-archive-{}", std::process::id()));
+        let mut cfg = cfg(&server.base_url);
+        cfg.archive_responses = true;
+        cfg.archive_dir = dir.to_string_lossy().to_string();
+        let limiter = RateLimiter::new(6000);
+
+        let candles = fetch_range(
+            &cfg,
+            &client().unwrap(),
+            &limiter,
+            "2330",
+            Timeframe::Day,
+            "2026-01-01",
+            "2026-12-31",
+        )
+        .await
+        .unwrap();
+
+        // 429 也會封存
+        let archive = Archive::new(&cfg.archive_dir);
+        let paths = archive.list(ARCHIVE_SOURCE);
+        let responses: Vec<ArchivedResponse> = paths
+            .iter()
+            .map(|path| Archive::load(path).unwrap())
+            .collect();
+        std::fs::remove_dir_all(&dir).unwrap();
+
+        assert_eq!(
+            responses.iter().map(|r| r.status).collect::<Vec<_>>(),
+            [429, 200]
+        );
+        assert_eq!(
+            responses[1].headers.get("content-type").map(String::as_str),
+            Some("application/json")
+        );
+        assert_eq!(
+            parse_url(&responses[1].url),
+            Some(("2330".to_string(), Timeframe::Day))
+        );
+        let replayed = parse(&responses[1].body, Timeframe::Day).unwrap();
+        assert_eq!(replayed.len(), candles.len());
+        assert_eq!(replayed[2].date, candles[2].date);
+        assert_eq!(replayed[2].close, candles[2].close);
     }
 }
@@ -1,17 +1,29 @@
 mod analysis;
+mod calendar;
 mod cfg;
 mod common;
 mod consts;
+mod error;
 mod fugle;
 mod menu;
+#[cfg(test)]
+mod mock_server;
+mod provider;
 mod scripts;
 mod stocks;
 mod storage;
+mod tpex;
 mod twse;
 
 #[tokio::main]
 async fn main() {
-    let mut data = stocks::data::Data::new().await;
+    let mut data = match stocks::data::Data::new().await {
+        Ok(data) => data,
+        Err(e) => {
+            println!("無法啟動: {e}");
+            return;
+        }
+    };
 
     menu::main_menu::main_menu(&mut data).await;
 }
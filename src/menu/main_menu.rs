@@ -1,29 +1,45 @@
+use std::cmp::Reverse;
 use std::io;
 
-use chrono::Local;
+use chrono::{Datelike, Local, NaiveDate};
 
+use crate::analysis;
+use crate::analysis::cross::CrossType;
+use crate::analysis::indicators::Indicators;
+use crate::analysis::kd::{KdPassivation, KdZone};
+use crate::analysis::macd::MacdZone;
+use crate::analysis::moving_average::{
+    Alignment, DeductionOutlook, MA_LONG, MA_MID, MA_SHORT, MaKind,
+};
+use crate::calendar;
+use crate::cfg::data::PriceView;
 use crate::common;
 use crate::menu::regression_menu;
 use crate::scripts;
+use crate::stocks::company_map::Market;
 use crate::stocks::data::Data;
 use crate::stocks::data_company::StockDataWithNo;
+use crate::stocks::timeframe::Timeframe;
+use crate::storage;
 
 fn print_line() {
     println!("--------------------------------------------------------------------------------");
 }
 
-// get YYYYMMDD format of today's date
-fn get_today_date() -> String {
-    let today = Local::now().date_naive();
-    today.format("%Y%m%d").to_string()
-}
-
 pub async fn main_menu(data: &mut Data) {
     loop {
+        let offline = data.cfg.offline;
         println!("Main Menu");
-        println!("每日工作: 1/4/5/6");
-        println!("1. 抓 2026 全部股票資料");
-        println!("2. 抓 年度個股股票資料");
+        if offline {
+            println!("離線模式: 只使用本機資料，不提供抓取資料的選項");
+        } else {
+            if data.legacy_prices {
+                println!("目前的資料是舊版的還原價，可用選單 27 逐年重抓原始價格");
+            }
+            println!("每日工作: 1/4/5/6");
+            println!("1. 補抓全部股票最新資料");
+            println!("2. 抓 年度個股股票資料");
+        }
         println!("3. 單日長紅 K 棒");
         println!("4. 單日十字線配合前 20*6 日最大最小值");
         println!("5. 單日陽吞噬形態");
@@ -35,7 +51,35 @@ pub async fn main_menu(data: &mut Data) {
         println!("11. 內困三日翻紅");
         println!("12. 內困三日翻黑");
         println!("13. 烏鴉躍空");
+        if !offline {
+            println!("14. 重抓 {} 全部股票資料", data.current_year());
+        }
+        println!("15. 交易日曆查詢");
+        println!("16. 資料完整性檢查");
+        if !offline {
+            println!("17. 更新公司清單");
+        }
+        println!("18. 個股分K型態、MACD 與 KD");
+        println!("19. 從封存的 API 回應重建資料 (不連網)");
+        if !offline {
+            println!("20. 重試上次失敗的年度抓取");
+        }
+        println!("21. MACD 零軸交叉與柱狀體篩選");
+        println!("22. MACD 背離");
+        println!("23. KD 交叉與鈍化");
+        println!("24. 個股技術指標 (RSI/布林/ATR/OBV/MFI/DMI)");
+        println!("25. 均線交叉、排列與扣抵");
+        if !offline {
+            println!("26. 抓取所有公司的分K (分K掃描用)");
+            if data.legacy_prices {
+                println!("27. 重抓原始價格 (取代舊版還原價)");
+            }
+        }
+        println!("98. 匯入 data/ 至 SQLite");
         println!("99. 回歸測試");
+        println!("c. 顯示目前設定");
+        println!("m. 切換掃描市場 (目前: {})", market_filter_label(data));
+        println!("t. 切換掃描週期 (目前: {})", data.scan_timeframe);
         println!("q/e. 退出 (Quit/Exit)");
         println!("h. Help");
         println!("請輸入選項：");
@@ -47,7 +91,10 @@ pub async fn main_menu(data: &mut Data) {
         let input = input.trim();
 
         match input {
-            "1" => menu_fetch_data_all_companies(data).await,
+            "1" | "2" | "14" | "17" | "20" | "26" | "27" if offline => {
+                println!("離線模式，無法使用此選項。")
+            }
+            "1" => menu_fetch_catch_up_all_companies(data).await,
             "2" => menu_fetch_data_company(data).await,
             "3" => menu_long_red_candle_date(data),
             "4" => menu_doji_date_range_max_min(data),
@@ -60,7 +107,25 @@ pub async fn main_menu(data: &mut Data) {
             "11" => menu_complex_bullish_harami_three_day_reversal_date(data),
             "12" => menu_complex_bearish_harami_three_day_reversal_date(data),
             "13" => menu_upside_gap_two_crows_date(data),
+            "14" => menu_fetch_data_all_companies(data).await,
+            "15" => menu_trading_calendar(data),
+            "16" => menu_validate_data(data).await,
+            "17" => menu_refresh_company_map(data).await,
+            "18" => menu_intraday_company(data).await,
+            "19" => menu_replay_archive(data),
+            "20" => menu_retry_failed_years(data).await,
+            "21" => menu_macd_screen_date(data),
+            "22" => menu_macd_divergence_date(data),
+            "23" => menu_kd_screen_date(data),
+            "24" => menu_indicators_company(data),
+            "25" => menu_moving_average_date(data),
+            "26" => menu_fetch_intraday_all(data).await,
+            "27" if data.legacy_prices => menu_rebuild_raw_prices(data).await,
+            "98" => menu_migrate_json_to_sqlite(data),
             "99" => regression_menu::menu(data),
+            "c" => data.cfg.print(),
+            "m" => menu_market_filter(data),
+            "t" => menu_scan_timeframe(data),
             "h" => menu_help(),
             "q" | "e" => {
                 println!("退出程式");
@@ -92,9 +157,37 @@ fn menu_help() {
     println!("    賣");
 }
 
+/// 舊版資料是還原價，逐年重抓成原始價格，中斷後再執行會從沒抓完的年度繼續
+async fn menu_rebuild_raw_prices(data: &mut Data) {
+    println!(
+        "{} 儲存的是舊版的 Fugle 還原價，沒有除權息的還原因子。",
+        data.store.name()
+    );
+    println!(
+        "要重抓 {} 年起的全部原始價格，是否現在重抓? (y/N)",
+        data.cfg.year_from
+    );
+    let mut input = String::new();
+    io::stdin().read_line(&mut input).expect("讀取失敗");
+    if !input.trim().eq_ignore_ascii_case("y") {
+        return;
+    }
+
+    print_line();
+    data.rebuild_raw_prices().await;
+    print_line();
+}
+
+async fn menu_fetch_catch_up_all_companies(data: &mut Data) {
+    print_line();
+    data.fetch_catch_up().await;
+    print_line();
+}
+
 async fn menu_fetch_data_all_companies(data: &mut Data) {
     print_line();
-    data.fetch_year("2026").await;
+    let year = data.current_year();
+    data.fetch_year(&year).await;
     print_line();
 }
 
@@ -110,26 +203,424 @@ async fn menu_fetch_data_company(data: &mut Data) {
     let stock_no = stock_no.trim();
 
     print_line();
-    data.fetch_company_year(stock_no, input).await;
+    if let Err(e) = data.fetch_company_year(stock_no, input).await {
+        println!("抓取失敗: {e}");
+    }
+    print_line();
+}
+
+fn menu_trading_calendar(data: &Data) {
+    println!("請輸入日期 (YYYYMMDD，空白為今天):");
+    let mut input = String::new();
+    io::stdin().read_line(&mut input).expect("讀取失敗");
+    let input = input.trim();
+    let date = if input.is_empty() {
+        Local::now().date_naive()
+    } else {
+        match calendar::trading::parse_date(input) {
+            Ok(date) => date,
+            Err(e) => {
+                println!("{e}");
+                return;
+            }
+        }
+    };
+
+    let calendar = &data.calendar;
+    let format = |d: NaiveDate| d.format("%Y%m%d").to_string();
+    print_line();
+    if calendar.is_trading_day(date) {
+        println!("{} 是交易日", format(date));
+    } else {
+        println!(
+            "{} 不是交易日: {}",
+            format(date),
+            calendar.describe_closure(date)
+        );
+    }
+    println!(
+        "上一個交易日: {}",
+        format(calendar.previous_trading_day(date))
+    );
+    println!("下一個交易日: {}", format(calendar.next_trading_day(date)));
+    for n in [5, 20, 60, 120] {
+        println!(
+            "{n} 個交易日前: {}",
+            format(calendar.trading_days_ago(date, n))
+        );
+    }
+    if let Some(first) = calendar.first_trading_day_of_year(date.year()) {
+        println!(
+            "今年第一個交易日 {}，到 {} 共 {} 個交易日",
+            format(first),
+            format(date),
+            calendar.trading_days_between(first, date).len()
+        );
+    }
+    print_line();
+}
+
+async fn menu_validate_data(data: &mut Data) {
+    print_line();
+    let reports = data.validate();
+    let mut targets = Vec::new();
+    for report in &reports {
+        println!(
+            "{} ({}) 共 {} 個問題",
+            report.stock_no,
+            data.company_map.display_name(&report.stock_no),
+            report.issues.len()
+        );
+        for issue in &report.issues {
+            println!("    {issue}");
+        }
+        for year in report.years() {
+            targets.push((report.stock_no.clone(), year));
+        }
+    }
+    println!(
+        "總共有 {} 支股票有問題，涉及 {} 個年度檔",
+        reports.len(),
+        targets.len()
+    );
+    print_line();
+
+    if targets.is_empty() || data.cfg.offline {
+        return;
+    }
+    println!("是否重抓有問題的年度? (y/N)");
+    let mut input = String::new();
+    io::stdin().read_line(&mut input).expect("讀取失敗");
+    if input.trim().eq_ignore_ascii_case("y") {
+        print_line();
+        data.refetch_years(&targets).await;
+        print_line();
+    }
+}
+
+async fn menu_refresh_company_map(data: &mut Data) {
+    print_line();
+    match data.refresh_company_map().await {
+        Ok(events) => {
+            for event in &events {
+                println!("{event}");
+            }
+            println!(
+                "公司清單共 {} 家，本次異動 {} 筆，歷史異動共 {} 筆",
+                data.company_map.stock_map.len(),
+                events.len(),
+                data.company_map.history.events.len()
+            );
+        }
+        Err(e) => println!("更新失敗: {e}"),
+    }
+    print_line();
+}
+
+/// 分K列出最近這麼多根
+const INTRADAY_LIST_BARS: usize = 20;
+
+async fn menu_intraday_company(data: &mut Data) {
+    println!("請輸入股票代號:");
+    let mut stock_no = String::new();
+    io::stdin().read_line(&mut stock_no).expect("讀取失敗");
+    let stock_no = stock_no.trim();
+
+    println!("請輸入分K週期 (1/5/10/15/30/60，空白為 60):");
+    let mut input = String::new();
+    io::stdin().read_line(&mut input).expect("讀取失敗");
+    let timeframe = match input.trim() {
+        "" => Timeframe::Min60,
+        text => match Timeframe::parse(text) {
+            Some(timeframe) if timeframe.is_intraday() => timeframe,
+            _ => {
+                println!("無效的分K週期: {text}");
+                return;
+            }
+        },
+    };
+
+    print_line();
+    // 抓不到時仍用已存檔的分K分析，離線時只看已存檔的
+    if !data.cfg.offline
+        && let Err(e) = data.fetch_intraday(stock_no, timeframe).await
+    {
+        println!("抓取失敗: {e}");
+    }
+    let candles = match data.read_intraday(stock_no, timeframe) {
+        Ok(candles) => candles,
+        Err(e) => {
+            println!("讀取失敗: {e}");
+            return;
+        }
+    };
+    let Some(last) = candles.last() else {
+        println!("{stock_no} 沒有{timeframe}資料");
+        print_line();
+        return;
+    };
+
+    let mut macd_calculator = analysis::macd::MacdCalculator::new(stock_no.to_string());
+    let (macd_results, crosses) = macd_calculator
+        .calc(&candles, &last.date)
+        .unwrap_or_default();
+    let mut kd_calculator = analysis::kd::KdCalculator::new(stock_no.to_string());
+    let (kd_results, kd_crosses) = kd_calculator.calc(&candles, &last.date).unwrap_or_default();
+
+    println!(
+        "{} ({}) {timeframe}，共 {} 根",
+        stock_no,
+        data.company_map.display_name(stock_no),
+        candles.len()
+    );
+    println!(
+        "{:<23}{:>6}{:>6}{:>6}{:>6}{:>6}{:>9}{:>9}{:>6}{:>7}{:>7}  型態  MACD/KD",
+        "時間",
+        "成交張數",
+        "開盤價",
+        "收盤價",
+        "最高價",
+        "最低價",
+        "DIF",
+        "MACD",
+        "柱狀體",
+        "K",
+        "D",
+    );
+    for candle in candles
+        .iter()
+        .skip(candles.len().saturating_sub(INTRADAY_LIST_BARS))
+    {
+        let cross = crosses
+            .iter()
+            .find(|cross| cross.date == candle.date)
+            .map(|cross| format!("MACD {cross} "))
+            .unwrap_or_default()
+            + &kd_crosses
+                .iter()
+                .find(|cross| cross.date == candle.date)
+                .map(|cross| format!("KD {cross}"))
+                .unwrap_or_default();
+        // MACD、KD 只算最近一段，較早的 K 棒沒有值
+        let macd = macd_results
+            .iter()
+            .find(|result| result.date == candle.date)
+            .map(|r| format!("{:>9.2}{:>9.2}{:>9.2}", r.dif, r.macd_signal, r.histogram))
+            .unwrap_or_else(|| format!("{:>27}", "-"));
+        let kd = kd_results
+            .iter()
+            .find(|result| result.date == candle.date)
+            .map(|r| format!("{:>7.1}{:>7.1}", r.k, r.d))
+            .unwrap_or_else(|| format!("{:>14}", "-"));
+        println!(
+            "{:<25}{:>10}{:>9.2}{:>9.2}{:>9.2}{:>9.2}{macd}{kd}  {:?}  {}",
+            candle.date,
+            common::str_volume(candle.volume),
+            candle.open,
+            candle.close,
+            candle.high,
+            candle.low,
+            analysis::candlestick::anal_candlestick(candle, data.cfg.long_body_ratio),
+            cross,
+        );
+    }
+    print_line();
+}
+
+/// 先抓全部公司的分K，再用 t 切換到同一個週期，就能用 3~13、21~23、25 的篩選掃描分K
+async fn menu_fetch_intraday_all(data: &Data) {
+    println!("請輸入分K週期 (1/5/10/15/30/60，空白為 60):");
+    let mut input = String::new();
+    io::stdin().read_line(&mut input).expect("讀取失敗");
+    let timeframe = match input.trim() {
+        "" => Timeframe::Min60,
+        text => match Timeframe::parse(text) {
+            Some(timeframe) if timeframe.is_intraday() => timeframe,
+            _ => {
+                println!("無效的分K週期: {text}");
+                return;
+            }
+        },
+    };
+
+    print_line();
+    data.fetch_intraday_all(timeframe).await;
+    if data.scan_timeframe != timeframe {
+        println!(
+            "目前掃描週期是 {}，用 t 切換到 {timeframe} 後再執行篩選",
+            data.scan_timeframe
+        );
+    }
+    print_line();
+}
+
+/// 日K指標列出最近這麼多天
+const INDICATOR_LIST_DAYS: usize = 20;
+
+fn menu_indicators_company(data: &Data) {
+    println!("請輸入股票代號:");
+    let mut stock_no = String::new();
+    io::stdin().read_line(&mut stock_no).expect("讀取失敗");
+    let stock_no = stock_no.trim();
+    let Some(data_company) = data.data_company.get(stock_no) else {
+        println!("找不到 {stock_no} 的資料");
+        return;
+    };
+    let Some(input) = get_date_input(data) else {
+        return;
+    };
+
+    print_line();
+    let indicators = Indicators::from_company(data_company, data.cfg.indicator_price_view);
+    let date = common::convert_date_to_fugle_format(&input);
+    let Some(end) = indicators.index(&date) else {
+        println!("{stock_no} 在 {input} 沒有交易");
+        print_line();
+        return;
+    };
+
+    let fmt = |value: Option<f64>, width: usize, precision: usize| match value {
+        Some(value) => format!("{value:>width$.precision$}"),
+        None => format!("{:>width$}", "-"),
+    };
+    println!(
+        "{} ({}) 日K，指標用{}價格",
+        stock_no,
+        data.company_map.display_name(stock_no),
+        match data.cfg.indicator_price_view {
+            PriceView::Raw => "原始",
+            PriceView::Adjusted => "還原",
+        }
+    );
+    println!(
+        "{:<9}{:>6}{:>9}{:>7}{:>7}{:>7}{:>9}{:>6}{:>9}{:>11}{:>9}{:>7}{:>7}{:>7}",
+        "日期",
+        "收盤價",
+        "RSI",
+        "下軌",
+        "中軌",
+        "上軌",
+        "%B",
+        "帶寬",
+        "ATR",
+        "OBV(張)",
+        "MFI",
+        "+DI",
+        "-DI",
+        "ADX",
+    );
+    for index in end.saturating_sub(INDICATOR_LIST_DAYS - 1)..=end {
+        let (Some(values), Some(bar)) = (
+            indicators.at_index(index),
+            data_company.stock_data.get(index),
+        ) else {
+            continue;
+        };
+        println!(
+            "{:<11}{:>9.2}{}{}{}{}{}{}{}{:>12}{}{}{}{}",
+            values.date,
+            bar.close,
+            fmt(values.rsi, 9, 2),
+            fmt(values.bollinger.map(|b| b.lower), 9, 2),
+            fmt(values.bollinger.map(|b| b.middle), 9, 2),
+            fmt(values.bollinger.map(|b| b.upper), 9, 2),
+            fmt(values.bollinger.map(|b| b.percent_b), 9, 2),
+            fmt(values.bollinger.map(|b| b.bandwidth), 8, 3),
+            fmt(values.atr, 9, 2),
+            values.obv / 1000,
+            fmt(values.mfi, 9, 2),
+            fmt(values.dmi.map(|d| d.plus_di), 7, 2),
+            fmt(values.dmi.map(|d| d.minus_di), 7, 2),
+            fmt(values.dmi.and_then(|d| d.adx), 7, 2),
+        );
+    }
+    print_line();
+}
+
+fn market_filter_label(data: &Data) -> String {
+    data.market_filter
+        .map(|market| market.to_string())
+        .unwrap_or_else(|| "全部".to_string())
+}
+
+fn menu_market_filter(data: &mut Data) {
+    println!("請選擇掃描市場 (1: 全部, 2: 上市, 3: 上櫃):");
+    let mut input = String::new();
+    io::stdin().read_line(&mut input).expect("讀取失敗");
+
+    data.market_filter = match input.trim() {
+        "1" => None,
+        "2" => Some(Market::Twse),
+        "3" => Some(Market::Tpex),
+        _ => {
+            println!("無效的選項，維持 {}", market_filter_label(data));
+            return;
+        }
+    };
+    println!("掃描市場: {}", market_filter_label(data));
+}
+
+fn menu_scan_timeframe(data: &mut Data) {
+    println!("請選擇掃描週期 (D: 日K, W: 週K, M: 月K, 1/5/10/15/30/60: 分K):");
+    let mut input = String::new();
+    io::stdin().read_line(&mut input).expect("讀取失敗");
+
+    match Timeframe::parse(&input) {
+        Some(timeframe) => data.scan_timeframe = timeframe,
+        None => {
+            println!("無效的選項，維持 {}", data.scan_timeframe);
+            return;
+        }
+    }
+    if data.scan_timeframe.is_intraday() {
+        println!("分K只會掃描已經用 18 或 26 抓過分K的股票");
+    }
+    println!("掃描週期: {}", data.scan_timeframe);
+}
+
+async fn menu_retry_failed_years(data: &mut Data) {
+    print_line();
+    data.retry_failed_years().await;
+    print_line();
+}
+
+fn menu_replay_archive(data: &mut Data) {
+    print_line();
+    data.replay_archive();
+    print_line();
+}
+
+fn menu_migrate_json_to_sqlite(data: &Data) {
+    print_line();
+    match storage::migrate::json_to_sqlite(&data.cfg) {
+        Ok(()) => println!("匯入完成，將 cfg.json 的 storage_backend 設為 \"sqlite\" 即可切換"),
+        Err(e) => println!("匯入失敗: {e}"),
+    }
     print_line();
 }
 
 fn menu_long_red_candle_date(data: &Data) {
-    let input = get_date_input();
+    let Some(input) = get_date_input(data) else {
+        return;
+    };
 
     print_line();
-    let mut results = scripts::candlestick::find_long_red_candle_date(data, &input);
-    results.sort_by(|a, b| b.stock_data.volume.cmp(&a.stock_data.volume)); // 按照成交量排序
+    let mut results =
+        scripts::candlestick::find_long_red_candle_date(data, &input, data.scan_timeframe);
+    results.sort_by_key(|a| Reverse(a.stock_data.volume)); // 按照成交量排序
     println!("總共有 {} 支股票在 {} 是長紅 K 棒", results.len(), input);
     print_lower_upper_30_percent_list(data, &results);
     print_line();
 }
 
 fn menu_doji_date_range_max_min(data: &Data) {
-    let input = get_date_input();
+    let Some(input) = get_date_input(data) else {
+        return;
+    };
 
-    let mut results = scripts::candlestick::find_doji_date_range_max_min(data, &input);
-    results.sort_by(|a, b| b.stock_data.volume.cmp(&a.stock_data.volume)); // 按照成交量排序
+    let mut results =
+        scripts::candlestick::find_doji_date_range_max_min(data, &input, data.scan_timeframe);
+    results.sort_by_key(|a| Reverse(a.stock_data.volume)); // 按照成交量排序
 
     print_line();
     println!("總共有 {} 支股票在 {} 是十字線", results.len(), input);
@@ -138,11 +629,17 @@ fn menu_doji_date_range_max_min(data: &Data) {
 }
 
 fn menu_macd_golden_cross_date(data: &Data) {
-    let input = get_date_input();
+    let Some(input) = get_date_input(data) else {
+        return;
+    };
 
     print_line();
-    let mut results = scripts::complex::find_macd_golden_cross_date_large_volume(data, &input);
-    results.sort_by(|a, b| b.stock_data.volume.cmp(&a.stock_data.volume)); // 按照成交量排序
+    let mut results = scripts::complex::find_macd_golden_cross_date_large_volume(
+        data,
+        &input,
+        data.scan_timeframe,
+    );
+    results.sort_by_key(|a| Reverse(a.stock_data.volume)); // 按照成交量排序
 
     println!(
         "總共有 {} 支股票在 {input} 是 MACD 黃金交叉且大成交量",
@@ -152,10 +649,214 @@ fn menu_macd_golden_cross_date(data: &Data) {
     print_line();
 }
 
+fn menu_macd_screen_date(data: &Data) {
+    println!(
+        "請選擇 MACD 條件 (1: 零軸下黃金交叉, 2: 零軸上黃金交叉, 3: 零軸上死亡交叉, 4: 柱狀體連續縮小):"
+    );
+    let mut choice = String::new();
+    io::stdin().read_line(&mut choice).expect("讀取失敗");
+    let cross = match choice.trim() {
+        "1" => Some((CrossType::GoldenCross, MacdZone::BelowZero)),
+        "2" => Some((CrossType::GoldenCross, MacdZone::AboveZero)),
+        "3" => Some((CrossType::DeathCross, MacdZone::AboveZero)),
+        "4" => None,
+        text => {
+            println!("無效的選項: {text}");
+            return;
+        }
+    };
+    let Some(input) = get_date_input(data) else {
+        return;
+    };
+
+    print_line();
+    let (label, mut results) = match cross {
+        Some((cross_type, zone)) => (
+            format!("MACD {zone}{cross_type}"),
+            scripts::macd::find_macd_cross_zone_date(
+                data,
+                &input,
+                data.scan_timeframe,
+                cross_type,
+                zone,
+            ),
+        ),
+        None => (
+            "MACD 柱狀體連續縮小".to_string(),
+            scripts::macd::find_macd_histogram_shrinking_date(data, &input, data.scan_timeframe),
+        ),
+    };
+    results.sort_by_key(|a| Reverse(a.stock_data.volume)); // 按照成交量排序
+
+    println!("總共有 {} 支股票在 {input} 是 {label}", results.len());
+    print_detail_list(data, &results);
+    print_line();
+}
+
+fn menu_macd_divergence_date(data: &Data) {
+    let Some(input) = get_date_input(data) else {
+        return;
+    };
+
+    print_line();
+    let mut results = scripts::macd::find_macd_divergence_date(data, &input, data.scan_timeframe);
+    results.sort_by_key(|(a, _)| Reverse(a.stock_data.volume)); // 按照成交量排序
+
+    println!("總共有 {} 支股票在 {input} 出現 MACD 背離", results.len());
+    let stocks: Vec<StockDataWithNo> = results.iter().map(|(r, _)| r.clone()).collect();
+    print_detail_list(data, &stocks);
+    println!("背離 (波段點日期 價格 (指標值)):");
+    for (result, divergences) in &results {
+        for divergence in divergences {
+            println!("    {} {divergence}", result.stock_no);
+        }
+    }
+    print_line();
+}
+
+fn menu_kd_screen_date(data: &Data) {
+    println!(
+        "請選擇 KD 條件 (1: 黃金交叉, 2: 死亡交叉, 3: 低檔黃金交叉, 4: 高檔死亡交叉, 5: 高檔鈍化, 6: 低檔鈍化):"
+    );
+    let mut choice = String::new();
+    io::stdin().read_line(&mut choice).expect("讀取失敗");
+    let choice = choice.trim().to_string();
+    if !["1", "2", "3", "4", "5", "6"].contains(&choice.as_str()) {
+        println!("無效的選項: {choice}");
+        return;
+    }
+    let Some(input) = get_date_input(data) else {
+        return;
+    };
+
+    print_line();
+    let timeframe = data.scan_timeframe;
+    let (label, mut results) = match choice.as_str() {
+        "1" => (
+            "KD 黃金交叉".to_string(),
+            scripts::kd::find_kd_golden_cross_date(data, &input, timeframe),
+        ),
+        "2" => (
+            "KD 死亡交叉".to_string(),
+            scripts::kd::find_kd_death_cross_date(data, &input, timeframe),
+        ),
+        "3" | "4" => {
+            let (cross_type, zone) = if choice == "3" {
+                (CrossType::GoldenCross, KdZone::Low)
+            } else {
+                (CrossType::DeathCross, KdZone::High)
+            };
+            (
+                format!("KD {zone}{cross_type}"),
+                scripts::kd::find_kd_cross_zone_date(data, &input, timeframe, cross_type, zone),
+            )
+        }
+        _ => {
+            let passivation = if choice == "5" {
+                KdPassivation::High
+            } else {
+                KdPassivation::Low
+            };
+            (
+                format!("KD {passivation}"),
+                scripts::kd::find_kd_passivation_date(data, &input, timeframe, passivation),
+            )
+        }
+    };
+    results.sort_by_key(|a| Reverse(a.stock_data.volume)); // 按照成交量排序
+
+    println!("總共有 {} 支股票在 {input} 是 {label}", results.len());
+    print_detail_list(data, &results);
+    print_line();
+}
+
+fn menu_moving_average_date(data: &Data) {
+    println!("請選擇均線條件:");
+    println!("    1: MA{MA_SHORT} 黃金交叉 MA{MA_MID}    2: MA{MA_SHORT} 死亡交叉 MA{MA_MID}");
+    println!("    3: MA{MA_MID} 黃金交叉 MA{MA_LONG}   4: MA{MA_MID} 死亡交叉 MA{MA_LONG}");
+    println!("    5: 剛形成多頭排列      6: 剛形成空頭排列");
+    println!("    7: MA{MA_MID} 扣低即將上揚     8: MA{MA_MID} 扣高即將下彎");
+    let mut choice = String::new();
+    io::stdin().read_line(&mut choice).expect("讀取失敗");
+    let choice = choice.trim().to_string();
+    if !(1..=8).any(|option| choice == option.to_string()) {
+        println!("無效的選項: {choice}");
+        return;
+    }
+    // 扣抵值只對簡單移動平均有意義
+    let kind = if ["7", "8"].contains(&choice.as_str()) {
+        MaKind::Sma
+    } else {
+        println!("請選擇均線種類 (S: SMA, E: EMA, W: WMA，空白為 SMA):");
+        let mut input = String::new();
+        io::stdin().read_line(&mut input).expect("讀取失敗");
+        match input.trim().to_uppercase().as_str() {
+            "" | "S" => MaKind::Sma,
+            "E" => MaKind::Ema,
+            "W" => MaKind::Wma,
+            text => {
+                println!("無效的均線種類: {text}");
+                return;
+            }
+        }
+    };
+    let Some(input) = get_date_input(data) else {
+        return;
+    };
+
+    print_line();
+    let timeframe = data.scan_timeframe;
+    let mut results = match choice.as_str() {
+        "1" | "2" | "3" | "4" => {
+            let periods = if ["1", "2"].contains(&choice.as_str()) {
+                (MA_SHORT, MA_MID)
+            } else {
+                (MA_MID, MA_LONG)
+            };
+            let cross_type = if ["1", "3"].contains(&choice.as_str()) {
+                CrossType::GoldenCross
+            } else {
+                CrossType::DeathCross
+            };
+            scripts::moving_average::find_ma_cross_date(
+                data, &input, timeframe, kind, periods, cross_type,
+            )
+        }
+        "5" | "6" => {
+            let alignment = if choice == "5" {
+                Alignment::Bullish
+            } else {
+                Alignment::Bearish
+            };
+            scripts::moving_average::find_ma_alignment_date(
+                data, &input, timeframe, kind, alignment,
+            )
+        }
+        _ => {
+            let outlook = if choice == "7" {
+                DeductionOutlook::TurningUp
+            } else {
+                DeductionOutlook::TurningDown
+            };
+            scripts::moving_average::find_ma_deduction_date(
+                data, &input, timeframe, MA_MID, outlook,
+            )
+        }
+    };
+    results.sort_by_key(|a| Reverse(a.stock_data.volume)); // 按照成交量排序
+
+    println!("總共有 {} 支股票在 {input} 符合條件", results.len());
+    print_detail_list(data, &results);
+    print_line();
+}
+
 fn menu_bullish_engulfing_date(data: &Data) {
-    let input = get_date_input();
+    let Some(input) = get_date_input(data) else {
+        return;
+    };
 
-    let results = scripts::bullish_engulfing::find_bullish_engulfing_date(data, &input);
+    let results =
+        scripts::bullish_engulfing::find_bullish_engulfing_date(data, &input, data.scan_timeframe);
 
     print_line();
     println!("總共有 {} 支股票在 {input} 是 陽吞噬形態", results.len());
@@ -164,10 +865,13 @@ fn menu_bullish_engulfing_date(data: &Data) {
 }
 
 fn menu_complex_hanging_man_date(data: &Data) {
-    let input = get_date_input();
+    let Some(input) = get_date_input(data) else {
+        return;
+    };
 
     print_line();
-    let results = scripts::complex::find_complex_hanging_man_date(data, &input);
+    let results =
+        scripts::complex::find_complex_hanging_man_date(data, &input, data.scan_timeframe);
     println!(
         "總共有 {} 支股票在 {input} 是 複合條件: 單日吊人線且前兩天都是漲停",
         results.len(),
@@ -177,10 +881,13 @@ fn menu_complex_hanging_man_date(data: &Data) {
 }
 
 fn menu_dark_cloud_cover_date(data: &Data) {
-    let input = get_date_input();
+    let Some(input) = get_date_input(data) else {
+        return;
+    };
 
     print_line();
-    let results = scripts::complex::find_complex_dark_cloud_cover(data, &input);
+    let results =
+        scripts::complex::find_complex_dark_cloud_cover(data, &input, data.scan_timeframe);
     println!(
         "總共有 {} 支股票在 {input} 是 複合條件: 單日黑雲壓頂",
         results.len(),
@@ -190,10 +897,13 @@ fn menu_dark_cloud_cover_date(data: &Data) {
 }
 
 fn menu_bullish_harami_pattern_date(data: &Data) {
-    let input = get_date_input();
+    let Some(input) = get_date_input(data) else {
+        return;
+    };
 
     print_line();
-    let results = scripts::complex::find_complex_bullish_harami_pattern(data, &input);
+    let results =
+        scripts::complex::find_complex_bullish_harami_pattern(data, &input, data.scan_timeframe);
     println!(
         "總共有 {} 支股票在 {input} 是 複合條件: 多頭母子",
         results.len(),
@@ -203,10 +913,13 @@ fn menu_bullish_harami_pattern_date(data: &Data) {
 }
 
 fn menu_bearish_harami_pattern_date(data: &Data) {
-    let input = get_date_input();
+    let Some(input) = get_date_input(data) else {
+        return;
+    };
 
     print_line();
-    let results = scripts::complex::find_complex_bearish_harami_pattern(data, &input);
+    let results =
+        scripts::complex::find_complex_bearish_harami_pattern(data, &input, data.scan_timeframe);
     println!(
         "總共有 {} 支股票在 {input} 是 複合條件: 空頭母子",
         results.len(),
@@ -216,11 +929,16 @@ fn menu_bearish_harami_pattern_date(data: &Data) {
 }
 
 fn menu_complex_bullish_harami_three_day_reversal_date(data: &Data) {
-    let input = get_date_input();
+    let Some(input) = get_date_input(data) else {
+        return;
+    };
 
     print_line();
-    let results =
-        scripts::complex::find_complex_bullish_harami_three_day_reversal_date(data, &input);
+    let results = scripts::complex::find_complex_bullish_harami_three_day_reversal_date(
+        data,
+        &input,
+        data.scan_timeframe,
+    );
     println!(
         "總共有 {} 支股票在 {input} 是 複合條件: 內困三日翻紅",
         results.len(),
@@ -230,11 +948,16 @@ fn menu_complex_bullish_harami_three_day_reversal_date(data: &Data) {
 }
 
 fn menu_complex_bearish_harami_three_day_reversal_date(data: &Data) {
-    let input = get_date_input();
+    let Some(input) = get_date_input(data) else {
+        return;
+    };
 
     print_line();
-    let results =
-        scripts::complex::find_complex_bearish_harami_three_day_reversal_date(data, &input);
+    let results = scripts::complex::find_complex_bearish_harami_three_day_reversal_date(
+        data,
+        &input,
+        data.scan_timeframe,
+    );
     println!(
         "總共有 {} 支股票在 {input} 是 複合條件: 內困三日翻黑",
         results.len(),
@@ -244,10 +967,13 @@ fn menu_complex_bearish_harami_three_day_reversal_date(data: &Data) {
 }
 
 fn menu_upside_gap_two_crows_date(data: &Data) {
-    let input = get_date_input();
+    let Some(input) = get_date_input(data) else {
+        return;
+    };
 
     print_line();
-    let results = scripts::complex::find_complex_upside_gap_two_crows(data, &input);
+    let results =
+        scripts::complex::find_complex_upside_gap_two_crows(data, &input, data.scan_timeframe);
     println!(
         "總共有 {} 支股票在 {input} 是 複合條件: 烏鴉躍空",
         results.len(),
@@ -260,63 +986,97 @@ fn menu_upside_gap_two_crows_date(data: &Data) {
 //  Helper functions
 //
 
-fn get_date_input() -> String {
+// 回傳 YYYYMMDD，空白代表最近的交易日，非交易日會往前找最近的交易日
+fn get_date_input(data: &Data) -> Option<String> {
     println!("請輸入日期 (YYYYMMDD):");
     let mut input = String::new();
     io::stdin().read_line(&mut input).expect("讀取失敗");
-    let mut input = input.trim().to_string();
-    if input.is_empty() {
-        input = get_today_date();
-        println!("使用今天的日期: {input}");
-    }
-    input
+    let input = input.trim();
+
+    let date = if input.is_empty() {
+        let date = data.calendar.latest_trading_day(Local::now().date_naive());
+        println!("使用最近的交易日: {}", date.format("%Y%m%d"));
+        date
+    } else {
+        let date = match calendar::trading::parse_date(input) {
+            Ok(date) => date,
+            Err(e) => {
+                println!("{e}");
+                return None;
+            }
+        };
+        if data.calendar.is_trading_day(date) {
+            date
+        } else {
+            let trading_day = data.calendar.latest_trading_day(date);
+            println!(
+                "{input} 不是交易日 ({})，改用 {}",
+                data.calendar.describe_closure(date),
+                trading_day.format("%Y%m%d")
+            );
+            trading_day
+        }
+    };
+
+    Some(date.format("%Y%m%d").to_string())
 }
 
 fn print_upper_30_percent_list(data: &Data, results: &[StockDataWithNo]) {
     println!(
-        "{:<9}{:<5}{:>6}{:>6}{:>8}  公司名稱",
+        "{:<9}{:<5}{:>6}{:>6}{:>8}  市場  公司名稱",
         "日期", "台股", "成交張數", "收盤價", "+30%",
     );
     for result in results {
         println!(
-            "{:<11}{:<6}{:>10}{:>9.2}{:>9.2}  {:<20}",
+            "{:<11}{:<6}{:>10}{:>9.2}{:>9.2}  {}  {:<20}",
             result.stock_data.date,
             result.stock_no,
             common::str_volume(result.stock_data.volume),
             result.stock_data.close,
             result.stock_data.close * 1.3,
-            data.company_map.get_name(&result.stock_no),
+            data.company_map.display_market(&result.stock_no),
+            data.company_map.display_name(&result.stock_no),
         );
     }
 }
 
 fn print_lower_upper_30_percent_list(data: &Data, results: &[StockDataWithNo]) {
     println!(
-        "{:<9}{:<5}{:>6}{:>6}{:>8}{:>8}  公司名稱",
+        "{:<9}{:<5}{:>6}{:>6}{:>8}{:>8}  市場  公司名稱",
         "日期", "台股", "成交張數", "收盤價", "+30%", "-30%",
     );
     for result in results {
         println!(
-            "{:<11}{:<6}{:>10}{:>9.2}{:>9.2}{:>9.2}  {:<20}",
+            "{:<11}{:<6}{:>10}{:>9.2}{:>9.2}{:>9.2}  {}  {:<20}",
             result.stock_data.date,
             result.stock_no,
             common::str_volume(result.stock_data.volume),
             result.stock_data.close,
             result.stock_data.close * 1.3,
             result.stock_data.close * 0.7,
-            data.company_map.get_name(&result.stock_no),
+            data.company_map.display_market(&result.stock_no),
+            data.company_map.display_name(&result.stock_no),
         );
     }
 }
 
 fn print_detail_list(data: &Data, results: &[StockDataWithNo]) {
     println!(
-        "{:<9}{:<5}{:>6}{:>6}{:>6}{:>6}{:>6}{:>6}  公司名稱",
-        "日期", "台股", "成交張數", "開盤價", "收盤價", "最高價", "最低價", "漲跌",
+        "{:<9}{:<5}{:>6}{:>6}{:>6}{:>6}{:>6}{:>6}{:>6}{:>5}  市場  公司名稱",
+        "日期",
+        "台股",
+        "成交張數",
+        "開盤價",
+        "收盤價",
+        "最高價",
+        "最低價",
+        "漲跌",
+        "週轉率%",
+        "市值(億)",
     );
     for result in results {
         println!(
-            "{:<11}{:<6}{:>10}{:>9.2}{:>9.2}{:>9.2}{:>9.2}{:>9.2}  {:<20}",
+            "{:<11}{:<6}{:>10}{:>9.2}{:>9.2}{:>9.2}{:>9.2}{:>9}{:>9}{:>9}  {}  {:<20}",
             result.stock_data.date,
             result.stock_no,
             common::str_volume(result.stock_data.volume),
@@ -324,8 +1084,17 @@ fn print_detail_list(data: &Data, results: &[StockDataWithNo]) {
             result.stock_data.close,
             result.stock_data.high,
             result.stock_data.low,
-            result.stock_data.change,
-            data.company_map.get_name(&result.stock_no),
+            // 不比價的日子沒有漲跌，照 TWSE 顯示 X
+            result
+                .stock_data
+                .change
+                .map_or("X".to_string(), |change| format!("{change:.2}")),
+            data.company_map
+                .display_turnover_rate(&result.stock_no, result.stock_data.volume),
+            data.company_map
+                .display_market_cap(&result.stock_no, result.stock_data.close),
+            data.company_map.display_market(&result.stock_no),
+            data.company_map.display_name(&result.stock_no),
         );
     }
 }
@@ -1,5 +1,7 @@
 use std::io;
 
+use chrono::Local;
+
 use crate::common;
 use crate::scripts;
 use crate::stocks::data::Data;
@@ -11,14 +13,6 @@ fn print_line() {
 pub fn menu(data: &Data) {
     println!("回歸測試選單");
 
-    // use 2317 as base stock to find date list
-    let mut date_list = Vec::new();
-    let company_data = common::get_company_data(data, "2317");
-
-    for data in &company_data.stock_data {
-        date_list.push(data.date.clone());
-    }
-
     loop {
         println!("1. 烏雲罩頂");
         println!("2. 烏鴉躍空");
@@ -32,8 +26,8 @@ pub fn menu(data: &Data) {
         let input = input.trim();
 
         match input {
-            "1" => menu_dark_cloud_cover(data, &date_list),
-            "2" => menu_upside_gap_two_crows(data, &date_list),
+            "1" => menu_dark_cloud_cover(data),
+            "2" => menu_upside_gap_two_crows(data),
             "q" | "e" => {
                 println!("退出選單");
                 break;
@@ -45,43 +39,45 @@ pub fn menu(data: &Data) {
     }
 }
 
-fn get_year() -> Option<String> {
+/// 輸入年分，回傳該年第一個交易日 (YYYYMMDD) 到最近交易日之間的所有交易日
+fn get_year_date_list(data: &Data) -> Option<(String, Vec<String>)> {
+    let year_from = data.cfg.year_from + 1;
+    let year_to = *data.cfg.years().end();
     println!("請輸入年分 (格式: YYYY):");
     let mut input = String::new();
     io::stdin().read_line(&mut input).expect("讀取失敗");
     let input = input.trim();
 
-    match input {
-        "2020" => Some("20200102".to_string()),
-        "2021" => Some("20210104".to_string()),
-        "2022" => Some("20220103".to_string()),
-        "2023" => Some("20230103".to_string()),
-        "2024" => Some("20240102".to_string()),
-        "2025" => Some("20250102".to_string()),
-        "2026" => Some("20260102".to_string()),
+    let first_date = match input.parse::<i32>() {
+        Ok(year) if (year_from..=year_to).contains(&year) => {
+            data.calendar.first_trading_day_of_year(year)?
+        }
         _ => {
-            println!("無效的年分，請輸入正確的格式 (YYYY) 2020-2026");
-            None
+            println!("無效的年分，請輸入正確的格式 (YYYY) {year_from}-{year_to}");
+            return None;
         }
-    }
+    };
+    let last_date = data.calendar.latest_trading_day(Local::now().date_naive());
+
+    let date_list = data
+        .calendar
+        .trading_days_between(first_date, last_date)
+        .iter()
+        .map(|date| date.format("%Y%m%d").to_string())
+        .collect();
+
+    Some((first_date.format("%Y%m%d").to_string(), date_list))
 }
 
-fn menu_dark_cloud_cover(data: &Data, date_list: &[String]) {
-    let input = match get_year() {
-        Some(year) => year,
-        None => return,
+fn menu_dark_cloud_cover(data: &Data) {
+    let Some((input, date_list)) = get_year_date_list(data) else {
+        return;
     };
 
     let mut results = Vec::new();
-    let date_index = date_list
-        .iter()
-        .position(|d| d == &common::convert_date_to_fugle_format(&input))
-        .unwrap();
-    for date in &date_list[date_index..] {
-        let date_results = scripts::complex::find_complex_dark_cloud_cover(
-            data,
-            &common::convert_fugle_date_to_yyyymmdd(date),
-        );
+    for date in &date_list {
+        let date_results =
+            scripts::complex::find_complex_dark_cloud_cover(data, date, data.scan_timeframe);
         results.extend(date_results);
     }
 
@@ -94,22 +90,15 @@ fn menu_dark_cloud_cover(data: &Data, date_list: &[String]) {
     print_line();
 }
 
-fn menu_upside_gap_two_crows(data: &Data, date_list: &[String]) {
-    let input = match get_year() {
-        Some(year) => year,
-        None => return,
+fn menu_upside_gap_two_crows(data: &Data) {
+    let Some((input, date_list)) = get_year_date_list(data) else {
+        return;
     };
 
     let mut results = Vec::new();
-    let date_index = date_list
-        .iter()
-        .position(|d| d == &common::convert_date_to_fugle_format(&input))
-        .unwrap();
-    for date in &date_list[date_index..] {
-        let date_results = scripts::complex::find_complex_upside_gap_two_crows(
-            data,
-            &common::convert_fugle_date_to_yyyymmdd(date),
-        );
+    for date in &date_list {
+        let date_results =
+            scripts::complex::find_complex_upside_gap_two_crows(data, date, data.scan_timeframe);
         results.extend(date_results);
     }
 
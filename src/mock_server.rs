@@ -0,0 +1,110 @@
+use std::sync::{Arc, Mutex};
+
+use tokio::io::{AsyncReadExt, AsyncWriteExt};
+use tokio::net::{TcpListener, TcpStream};
+
+/// 預先準備好的一個 HTTP 回應
+#[derive(Clone)]
+pub struct MockResponse {
+    status: u16,
+    headers: Vec<(String, String)>,
+    body: String,
+}
+
+impl MockResponse {
+    pub fn new(status: u16, body: &str) -> Self {
+        MockResponse {
+            status,
+            headers: Vec::new(),
+            body: body.to_string(),
+        }
+    }
+
+    pub fn ok(body: &str) -> Self {
+        MockResponse::new(200, body)
+    }
+
+    pub fn header(mut self, name: &str, value: &str) -> Self {
+        self.headers.push((name.to_string(), value.to_string()));
+        self
+    }
+
+    fn to_http(&self) -> String {
+        let reason = match self.status {
+            200 => "OK",
+            429 => "Too Many Requests",
+            500 => "Internal Server Error",
+            _ => "Mock",
+        };
+        let mut http = format!("HTTP/1.1 {} {reason}\r\n", self.status);
+        http.push_str("Content-Type: application/json\r\n");
+        http.push_str(&format!("Content-Length: {}\r\n", self.body.len()));
+        // 每個連線只回一次，不讓 reqwest 重用連線
+        http.push_str("Connection: close\r\n");
+        for (name, value) in &self.headers {
+            http.push_str(&format!("{name}: {value}\r\n"));
+        }
+        http.push_str("\r\n");
+        http.push_str(&self.body);
+        http
+    }
+}
+
+/// 測試用的本機 HTTP 伺服器，依序回放 responses，用完之後一直回最後一個
+pub struct MockServer {
+    pub base_url: String,
+    requests: Arc<Mutex<Vec<String>>>,
+}
+
+impl MockServer {
+    pub async fn start(responses: Vec<MockResponse>) -> Self {
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let base_url = format!("http://{}", listener.local_addr().unwrap());
+        let requests = Arc::new(Mutex::new(Vec::new()));
+
+        let recorded = requests.clone();
+        tokio::spawn(async move {
+            let mut index = 0;
+            while let Ok((mut socket, _)) = listener.accept().await {
+                let path = read_request_path(&mut socket).await;
+                recorded.lock().unwrap().push(path);
+
+                let response = &responses[index.min(responses.len() - 1)];
+                index += 1;
+                let _ = socket.write_all(response.to_http().as_bytes()).await;
+                let _ = socket.shutdown().await;
+            }
+        });
+
+        MockServer { base_url, requests }
+    }
+
+    /// 收到的請求路徑 (含 query string)，依收到的順序
+    pub fn requests(&self) -> Vec<String> {
+        self.requests.lock().unwrap().clone()
+    }
+}
+
+/// 讀到 header 結束為止，只取 request line 的路徑，GET 沒有 body
+async fn read_request_path(socket: &mut TcpStream) -> String {
+    let mut head = Vec::new();
+    let mut buf = [0u8; 1024];
+    while !head.windows(4).any(|window| window == b"\r\n\r\n") {
+        match socket.read(&mut buf).await {
+            Ok(0) | Err(_) => break,
+            Ok(n) => head.extend_from_slice(&buf[..n]),
+        }
+    }
+
+    let head = String::from_utf8_lossy(&head);
+    head.split_whitespace()
+        .nth(1)
+        .unwrap_or_default()
+        .to_string()
+}
+
+/// 沒有人在聽的本機位址，用來測試連線失敗
+pub async fn unused_base_url() -> String {
+    let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+    format!("http://{}", listener.local_addr().unwrap())
+}
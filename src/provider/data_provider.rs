@@ -0,0 +1,48 @@
+use chrono::NaiveDate;
+
+use crate::error::{Error, Result};
+use crate::stocks::data_company::{StockData, StockDataWithNo};
+use crate::stocks::timeframe::Timeframe;
+
+/// K 棒資料來源，每個來源只需要實作它支援的抓法
+pub trait DataProvider {
+    fn name(&self) -> &'static str;
+
+    /// 一支股票 from ~ to (YYYY-MM-DD，含頭尾) 的原始日K，回補歷史用
+    fn fetch_range(
+        &self,
+        stock_no: &str,
+        from: &str,
+        to: &str,
+    ) -> impl Future<Output = Result<Vec<StockData>>> + Send {
+        let error = Error::Api(format!(
+            "{} 不提供個股區間日K ({stock_no} {from} ~ {to})",
+            self.name()
+        ));
+        async move { Err(error) }
+    }
+
+    /// 一支股票 from ~ to (YYYY-MM-DD，含頭尾) 的分K
+    fn fetch_intraday(
+        &self,
+        stock_no: &str,
+        timeframe: Timeframe,
+        from: &str,
+        to: &str,
+    ) -> impl Future<Output = Result<Vec<StockData>>> + Send {
+        let error = Error::Api(format!(
+            "{} 不提供{timeframe} ({stock_no} {from} ~ {to})",
+            self.name()
+        ));
+        async move { Err(error) }
+    }
+
+    /// 全市場某個交易日的日K，一次請求拿到所有股票，每日更新用
+    fn fetch_market_day(
+        &self,
+        date: NaiveDate,
+    ) -> impl Future<Output = Result<Vec<StockDataWithNo>>> + Send {
+        let error = Error::Api(format!("{} 不提供全市場日K ({date})", self.name()));
+        async move { Err(error) }
+    }
+}
@@ -0,0 +1,64 @@
+use std::sync::Arc;
+
+use crate::cfg::data::CfgData;
+use crate::error::Result;
+use crate::fugle::stocks;
+use crate::provider::data_provider::DataProvider;
+use crate::provider::rate_limiter::RateLimiter;
+use crate::stocks::data_company::StockData;
+use crate::stocks::timeframe::Timeframe;
+
+/// Fugle 個股歷史日K與分K，一支股票一個請求
+pub struct FugleProvider {
+    cfg: CfgData,
+    client: reqwest::Client,
+    limiter: Arc<RateLimiter>,
+}
+
+impl FugleProvider {
+    pub fn new(cfg: &CfgData) -> Result<Self> {
+        Ok(FugleProvider {
+            cfg: cfg.clone(),
+            client: stocks::client()?,
+            limiter: Arc::new(RateLimiter::new(cfg.fugle_requests_per_minute)),
+        })
+    }
+}
+
+impl DataProvider for FugleProvider {
+    fn name(&self) -> &'static str {
+        "Fugle"
+    }
+
+    async fn fetch_range(&self, stock_no: &str, from: &str, to: &str) -> Result<Vec<StockData>> {
+        stocks::fetch_range(
+            &self.cfg,
+            &self.client,
+            &self.limiter,
+            stock_no,
+            Timeframe::Day,
+            from,
+            to,
+        )
+        .await
+    }
+
+    async fn fetch_intraday(
+        &self,
+        stock_no: &str,
+        timeframe: Timeframe,
+        from: &str,
+        to: &str,
+    ) -> Result<Vec<StockData>> {
+        stocks::fetch_range(
+            &self.cfg,
+            &self.client,
+            &self.limiter,
+            stock_no,
+            timeframe,
+            from,
+            to,
+        )
+        .await
+    }
+}
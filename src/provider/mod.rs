@@ -0,0 +1,5 @@
+pub mod data_provider;
+pub mod fugle;
+pub mod pool;
+pub mod rate_limiter;
+pub mod twse;
@@ -0,0 +1,59 @@
+use std::sync::Arc;
+
+use tokio::task::JoinSet;
+
+use crate::error::Result;
+use crate::provider::data_provider::DataProvider;
+use crate::stocks::data_company::StockData;
+use crate::stocks::timeframe::Timeframe;
+
+const MODULE_NAME: &str = "provider::pool";
+
+/// 一個抓取工作：某支股票 from ~ to (YYYY-MM-DD) 的日K或分K
+#[derive(Debug, Clone)]
+pub struct FetchJob {
+    pub stock_no: String,
+    pub from: String,
+    pub to: String,
+    pub timeframe: Timeframe,
+}
+
+/// 同時最多 concurrency 個工作，請求速率由 provider 自己控制；每完成一個工作就呼叫 on_result
+pub async fn fetch_all<P>(
+    provider: &Arc<P>,
+    concurrency: usize,
+    jobs: Vec<FetchJob>,
+    mut on_result: impl FnMut(FetchJob, Result<Vec<StockData>>),
+) where
+    P: DataProvider + Send + Sync + 'static,
+{
+    let mut pending = jobs.into_iter();
+    let mut running = JoinSet::new();
+
+    loop {
+        while running.len() < concurrency.max(1) {
+            let Some(job) = pending.next() else {
+                break;
+            };
+            let provider = Arc::clone(provider);
+            running.spawn(async move {
+                let result = if job.timeframe.is_intraday() {
+                    provider
+                        .fetch_intraday(&job.stock_no, job.timeframe, &job.from, &job.to)
+                        .await
+                } else {
+                    provider
+                        .fetch_range(&job.stock_no, &job.from, &job.to)
+                        .await
+                };
+                (job, result)
+            });
+        }
+
+        match running.join_next().await {
+            Some(Ok((job, result))) => on_result(job, result),
+            Some(Err(e)) => println!("[{MODULE_NAME}] 抓取工作異常結束: {e}"),
+            None => break,
+        }
+    }
+}
@@ -0,0 +1,79 @@
+use tokio::sync::Mutex;
+use tokio::time::{Duration, Instant};
+
+const MODULE_NAME: &str = "provider::rate_limiter";
+
+struct Bucket {
+    tokens: f64,
+    last_refill: Instant,
+    paused_until: Option<Instant>,
+}
+
+/// 所有抓取工作共用的 token bucket，每分鐘最多 requests_per_minute 個請求
+pub struct RateLimiter {
+    capacity: f64,
+    refill_per_sec: f64,
+    bucket: Mutex<Bucket>,
+}
+
+impl RateLimiter {
+    pub fn new(requests_per_minute: u32) -> Self {
+        let capacity = requests_per_minute.max(1) as f64;
+        RateLimiter {
+            capacity,
+            refill_per_sec: capacity / 60.0,
+            bucket: Mutex::new(Bucket {
+                // 只給一個 token 起跑，避免和上一輪剛用掉的額度疊在一起
+                tokens: 1.0,
+                last_refill: Instant::now(),
+                paused_until: None,
+            }),
+        }
+    }
+
+    /// 等到拿到一個 token 才回傳
+    pub async fn acquire(&self) {
+        loop {
+            let wait = {
+                let mut bucket = self.bucket.lock().await;
+                let now = Instant::now();
+
+                match bucket.paused_until {
+                    Some(paused_until) if paused_until > now => paused_until - now,
+                    _ => {
+                        bucket.paused_until = None;
+                        let elapsed = now.duration_since(bucket.last_refill).as_secs_f64();
+                        bucket.tokens =
+                            (bucket.tokens + elapsed * self.refill_per_sec).min(self.capacity);
+                        bucket.last_refill = now;
+
+                        if bucket.tokens >= 1.0 {
+                            bucket.tokens -= 1.0;
+                            return;
+                        }
+                        Duration::from_secs_f64((1.0 - bucket.tokens) / self.refill_per_sec)
+                    }
+                }
+            };
+
+            tokio::time::sleep(wait).await;
+        }
+    }
+
+    /// 伺服器回 429 時，所有工作一起暫停 duration，並清空已累積的 token
+    pub async fn pause(&self, duration: Duration) {
+        let mut bucket = self.bucket.lock().await;
+        let until = Instant::now() + duration;
+        if bucket
+            .paused_until
+            .is_none_or(|paused_until| paused_until < until)
+        {
+            println!(
+                "[{MODULE_NAME}] 超過 API 額度，暫停 {} 秒",
+                duration.as_secs_f64()
+            );
+            bucket.paused_until = Some(until);
+            bucket.tokens = 0.0;
+        }
+    }
+}
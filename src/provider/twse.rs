@@ -0,0 +1,44 @@
+use chrono::NaiveDate;
+
+use crate::cfg::data::CfgData;
+use crate::error::{Error, Result};
+use crate::provider::data_provider::DataProvider;
+use crate::provider::rate_limiter::RateLimiter;
+use crate::stocks::data_company::StockDataWithNo;
+use crate::storage::archive::Archive;
+use crate::twse::daily_quotes;
+
+/// TWSE 每日收盤行情，一個交易日一個請求就有全部上市股票
+pub struct TwseProvider {
+    client: reqwest::Client,
+    base_url: String,
+    limiter: RateLimiter,
+    offline: bool,
+    archive: Option<Archive>,
+}
+
+impl TwseProvider {
+    pub fn new(cfg: &CfgData) -> Result<Self> {
+        Ok(TwseProvider {
+            client: daily_quotes::client()?,
+            base_url: cfg.twse_base_url.clone(),
+            limiter: RateLimiter::new(cfg.twse_requests_per_minute),
+            offline: cfg.offline,
+            archive: Archive::from_cfg(cfg),
+        })
+    }
+}
+
+impl DataProvider for TwseProvider {
+    fn name(&self) -> &'static str {
+        "TWSE"
+    }
+
+    async fn fetch_market_day(&self, date: NaiveDate) -> Result<Vec<StockDataWithNo>> {
+        if self.offline {
+            return Err(Error::Offline(format!("TWSE {date} 收盤行情")));
+        }
+        self.limiter.acquire().await;
+        daily_quotes::fetch(&self.client, &self.base_url, date, self.archive.as_ref()).await
+    }
+}
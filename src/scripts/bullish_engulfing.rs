@@ -1,20 +1,28 @@
 use crate::analysis;
+use crate::cfg::data::PriceView;
 use crate::stocks::data::Data;
 use crate::stocks::data_company::StockDataWithNo;
+use crate::stocks::timeframe::Timeframe;
 
 const MODULE_NAME: &str = "scripts::bullish_engulfing";
 
-pub fn find_bullish_engulfing_date(data: &Data, date: &str) -> Vec<StockDataWithNo> {
-    println!("[{MODULE_NAME}] 分析 {date} 的陽吞噬形態");
+pub fn find_bullish_engulfing_date(
+    data: &Data,
+    date: &str,
+    timeframe: Timeframe,
+) -> Vec<StockDataWithNo> {
+    println!("[{MODULE_NAME}] 分析 {date} {timeframe} 的陽吞噬形態");
 
     let mut bullish_engulfing_data_all = Vec::new();
 
-    for company in &data.company_map.stock_map {
+    for (company, bars) in data.scan_bars("bullish_engulfing", timeframe, PriceView::Raw, date) {
         let stock_no = &company.stock_no;
-
-        let data_company = data.data_company.get(stock_no).expect("找不到股票資料");
-        let result =
-            analysis::bullish_engulfing::find_bullish_engulfing_date_company(data_company, date);
+        let Some(last) = bars.last() else {
+            continue;
+        };
+        let result = analysis::bullish_engulfing::find_bullish_engulfing_date_company(
+            stock_no, &bars, &last.date,
+        );
         bullish_engulfing_data_all.extend(result);
     }
 
@@ -1,76 +1,70 @@
 use crate::analysis;
-use crate::common;
+use crate::cfg::data::PriceView;
 use crate::stocks::data::Data;
 use crate::stocks::data_company::StockDataWithNo;
+use crate::stocks::timeframe::Timeframe;
 
 const MODULE_NAME: &str = "scripts::long_red_candle";
-const RANGE: usize = 20 * 6;
 
-pub fn find_long_red_candle_date(data: &Data, date: &str) -> Vec<StockDataWithNo> {
-    println!("[{MODULE_NAME}] 分析 {date} 的長紅 K 棒");
+pub fn find_long_red_candle_date(
+    data: &Data,
+    date: &str,
+    timeframe: Timeframe,
+) -> Vec<StockDataWithNo> {
+    println!("[{MODULE_NAME}] 分析 {date} {timeframe} 的長紅 K 棒");
 
     let mut long_red_candle_data_all = Vec::new();
 
-    for company in &data.company_map.stock_map {
+    for (company, bars) in data.scan_bars("long_red_candle", timeframe, PriceView::Raw, date) {
         let stock_no = &company.stock_no;
-        let company_name = data.company_map.get_name(stock_no);
-
-        let data_company = data.data_company.get(stock_no).expect("找不到股票資料");
-        let date_fugle_format = common::convert_date_to_fugle_format(date);
-        let stock_data_for_date = data_company.get_stock_data_by_date(&date_fugle_format);
-
-        if let Some(stock_data) = stock_data_for_date {
-            if analysis::candlestick::anal_candlestick(stock_data)
+        if let Some(stock_data) = bars.last()
+            && analysis::candlestick::anal_candlestick(stock_data, data.cfg.long_body_ratio)
                 == analysis::candlestick::CandlestickType::LongRedCandle
-            {
-                long_red_candle_data_all.push(StockDataWithNo {
-                    stock_no: stock_no.clone(),
-                    stock_data: stock_data.clone(),
-                });
-            }
-        } else {
-            println!("[{MODULE_NAME}] 無法找到 {stock_no} ({company_name}) 在 {date} 的資料");
+        {
+            long_red_candle_data_all.push(StockDataWithNo {
+                stock_no: stock_no.clone(),
+                stock_data: stock_data.clone(),
+            });
         }
     }
 
     long_red_candle_data_all
 }
 
-pub fn find_doji_date_range_max_min(data: &Data, date: &str) -> Vec<StockDataWithNo> {
-    println!("[{MODULE_NAME}] 分析 {date} 的十字線");
+pub fn find_doji_date_range_max_min(
+    data: &Data,
+    date: &str,
+    timeframe: Timeframe,
+) -> Vec<StockDataWithNo> {
+    println!("[{MODULE_NAME}] 分析 {date} {timeframe} 的十字線");
 
     let mut doji_data_all = Vec::new();
 
-    for company in &data.company_map.stock_map {
+    for (company, bars) in data.scan_bars("doji", timeframe, PriceView::Raw, date) {
         let stock_no = &company.stock_no;
-        let company_name = data.company_map.get_name(stock_no);
-
-        let data_company = data.data_company.get(stock_no).expect("找不到股票資料");
-        let date_fugle_format = common::convert_date_to_fugle_format(date);
-        let stock_data_for_date = data_company.get_stock_data_by_date(&date_fugle_format);
-
-        if let Some(stock_data) = stock_data_for_date {
-            if analysis::candlestick::anal_candlestick(stock_data)
+        if let Some(stock_data) = bars.last()
+            && analysis::candlestick::anal_candlestick(stock_data, data.cfg.long_body_ratio)
                 == analysis::candlestick::CandlestickType::Doji
-                && let Some((max_price, min_price)) =
-                    analysis::volume::find_max_min_date_range_company(data_company, date, RANGE)
-            {
-                if max_price > stock_data.close * 1.3 {
-                    doji_data_all.push(StockDataWithNo {
-                        stock_no: stock_no.clone(),
-                        stock_data: stock_data.clone(),
-                    });
-                }
-
-                if min_price < stock_data.close * 0.7 {
-                    doji_data_all.push(StockDataWithNo {
-                        stock_no: stock_no.clone(),
-                        stock_data: stock_data.clone(),
-                    });
-                }
+            && let Some((max_price, min_price)) = analysis::volume::find_max_min_date_range(
+                stock_no,
+                &bars,
+                &stock_data.date,
+                data.cfg.doji_range,
+            )
+        {
+            if max_price > stock_data.close * 1.3 {
+                doji_data_all.push(StockDataWithNo {
+                    stock_no: stock_no.clone(),
+                    stock_data: stock_data.clone(),
+                });
+            }
+
+            if min_price < stock_data.close * 0.7 {
+                doji_data_all.push(StockDataWithNo {
+                    stock_no: stock_no.clone(),
+                    stock_data: stock_data.clone(),
+                });
             }
-        } else {
-            println!("[{MODULE_NAME}] 無法找到 {stock_no} ({company_name}) 在 {date} 的資料");
         }
     }
 
@@ -78,30 +72,25 @@ pub fn find_doji_date_range_max_min(data: &Data, date: &str) -> Vec<StockDataWit
 }
 
 #[allow(dead_code)]
-pub fn find_lower_shadow_date(data: &Data, date: &str) -> Vec<StockDataWithNo> {
-    println!("[{MODULE_NAME}] 分析 {date} 的長下影線");
+pub fn find_lower_shadow_date(
+    data: &Data,
+    date: &str,
+    timeframe: Timeframe,
+) -> Vec<StockDataWithNo> {
+    println!("[{MODULE_NAME}] 分析 {date} {timeframe} 的長下影線");
 
     let mut result = Vec::new();
 
-    for company in &data.company_map.stock_map {
+    for (company, bars) in data.scan_bars("lower_shadow", timeframe, PriceView::Raw, date) {
         let stock_no = &company.stock_no;
-        let company_name = data.company_map.get_name(stock_no);
-
-        let data_company = data.data_company.get(stock_no).expect("找不到股票資料");
-        let date_fugle_format = common::convert_date_to_fugle_format(date);
-        let stock_data_for_date = data_company.get_stock_data_by_date(&date_fugle_format);
-
-        if let Some(stock_data) = stock_data_for_date {
-            if analysis::candlestick::anal_candlestick(stock_data)
+        if let Some(stock_data) = bars.last()
+            && analysis::candlestick::anal_candlestick(stock_data, data.cfg.long_body_ratio)
                 == analysis::candlestick::CandlestickType::LongLowerShadow
-            {
-                result.push(StockDataWithNo {
-                    stock_no: stock_no.clone(),
-                    stock_data: stock_data.clone(),
-                });
-            }
-        } else {
-            println!("[{MODULE_NAME}] 無法找到 {stock_no} ({company_name}) 在 {date} 的資料");
+        {
+            result.push(StockDataWithNo {
+                stock_no: stock_no.clone(),
+                stock_data: stock_data.clone(),
+            });
         }
     }
 
@@ -109,30 +98,25 @@ pub fn find_lower_shadow_date(data: &Data, date: &str) -> Vec<StockDataWithNo> {
 }
 
 #[allow(dead_code)]
-pub fn find_hanging_man_date(data: &Data, date: &str) -> Vec<StockDataWithNo> {
-    println!("[{MODULE_NAME}] 分析 {date} 的吊人線");
+pub fn find_hanging_man_date(
+    data: &Data,
+    date: &str,
+    timeframe: Timeframe,
+) -> Vec<StockDataWithNo> {
+    println!("[{MODULE_NAME}] 分析 {date} {timeframe} 的吊人線");
 
     let mut result = Vec::new();
 
-    for company in &data.company_map.stock_map {
+    for (company, bars) in data.scan_bars("hanging_man", timeframe, PriceView::Raw, date) {
         let stock_no = &company.stock_no;
-        let company_name = data.company_map.get_name(stock_no);
-
-        let data_company = data.data_company.get(stock_no).expect("找不到股票資料");
-        let date_fugle_format = common::convert_date_to_fugle_format(date);
-        let stock_data_for_date = data_company.get_stock_data_by_date(&date_fugle_format);
-
-        if let Some(stock_data) = stock_data_for_date {
-            if analysis::candlestick::anal_candlestick(stock_data)
+        if let Some(stock_data) = bars.last()
+            && analysis::candlestick::anal_candlestick(stock_data, data.cfg.long_body_ratio)
                 == analysis::candlestick::CandlestickType::HangingMan
-            {
-                result.push(StockDataWithNo {
-                    stock_no: stock_no.clone(),
-                    stock_data: stock_data.clone(),
-                });
-            }
-        } else {
-            println!("[{MODULE_NAME}] 無法找到 {stock_no} ({company_name}) 在 {date} 的資料");
+        {
+            result.push(StockDataWithNo {
+                stock_no: stock_no.clone(),
+                stock_data: stock_data.clone(),
+            });
         }
     }
 
@@ -1,42 +1,50 @@
 use crate::analysis;
-use crate::common;
+use crate::cfg::data::PriceView;
 use crate::scripts;
 use crate::stocks::data::Data;
-use crate::stocks::data_company::DataCompany;
 use crate::stocks::data_company::{StockData, StockDataWithNo};
+use crate::stocks::timeframe::Timeframe;
 
 const MODULE_NAME: &str = "scripts::complex";
-const LARGE_VOLUME: u64 = 2000;
-const LOOK_BACK_DAYS: usize = 20 * 3; // 看三個月的資料
 
-pub fn find_macd_golden_cross_date_large_volume(data: &Data, date: &str) -> Vec<StockDataWithNo> {
-    println!("[{MODULE_NAME}] 分析 {date} 的 MACD 黃金交叉且大成交量");
+pub fn find_macd_golden_cross_date_large_volume(
+    data: &Data,
+    date: &str,
+    timeframe: Timeframe,
+) -> Vec<StockDataWithNo> {
+    println!("[{MODULE_NAME}] 分析 {date} {timeframe} 的 MACD 黃金交叉且大成交量");
 
-    let mut crosses = scripts::macd::find_macd_golden_cross_date(data, date);
+    let mut crosses = scripts::macd::find_macd_golden_cross_date(data, date, timeframe);
 
-    // remove the crosses that the volume is smaller than LARGE_VOLUME
-    crosses.retain(|cross| cross.stock_data.volume >= LARGE_VOLUME * 1000);
+    // remove the crosses that the volume is smaller than large_volume
+    let large_volume = data.cfg.large_volume;
+    crosses.retain(|cross| cross.stock_data.volume >= large_volume * 1000);
 
     crosses
 }
 
 #[allow(clippy::collapsible_if)]
-pub fn find_complex_hanging_man_date(data: &Data, date: &str) -> Vec<StockDataWithNo> {
-    // println!("[{MODULE_NAME}] 分析 {date} 的複合條件: 單日吊人線且前兩天都是漲停");
+pub fn find_complex_hanging_man_date(
+    data: &Data,
+    date: &str,
+    timeframe: Timeframe,
+) -> Vec<StockDataWithNo> {
+    // println!("[{MODULE_NAME}] 分析 {date} {timeframe} 的複合條件: 單日吊人線且前兩天都是漲停");
     let mut results = Vec::new();
-    for company in &data.company_map.stock_map {
-        let company_data = common::get_company_data(data, &company.stock_no);
-        let curr_date_index = match get_current_index_by_date(&company_data.stock_data, date, 2) {
+    for (company, company_data) in
+        data.scan_bars("complex_hanging_man", timeframe, PriceView::Raw, date)
+    {
+        let curr_date_index = match get_current_index(&company_data, 2) {
             Some(index) => index,
             None => continue, // 如果找不到日期，跳過這家公司
         };
 
-        let prev_stock_data = &company_data.stock_data[curr_date_index - 1];
-        let prev_prev_stock_data = &company_data.stock_data[curr_date_index - 2];
-        let curr_stock_data = &company_data.stock_data[curr_date_index];
+        let prev_stock_data = &company_data[curr_date_index - 1];
+        let prev_prev_stock_data = &company_data[curr_date_index - 2];
+        let curr_stock_data = &company_data[curr_date_index];
 
         // 兩根漲停+吊人線
-        if analysis::candlestick::anal_candlestick(curr_stock_data)
+        if analysis::candlestick::anal_candlestick(curr_stock_data, data.cfg.long_body_ratio)
             == analysis::candlestick::CandlestickType::HangingMan
         {
             if analysis::candlestick::anal_limit_up(prev_stock_data, curr_stock_data) {
@@ -50,7 +58,7 @@ pub fn find_complex_hanging_man_date(data: &Data, date: &str) -> Vec<StockDataWi
         }
 
         // 一根漲停+當天是漲停且吊人線
-        if analysis::candlestick::anal_candlestick(curr_stock_data)
+        if analysis::candlestick::anal_candlestick(curr_stock_data, data.cfg.long_body_ratio)
             == analysis::candlestick::CandlestickType::HangingMan
         {
             if analysis::candlestick::anal_limit_up(curr_stock_data, curr_stock_data) {
@@ -67,19 +75,23 @@ pub fn find_complex_hanging_man_date(data: &Data, date: &str) -> Vec<StockDataWi
     results
 }
 
-pub fn find_complex_dark_cloud_cover(data: &Data, date: &str) -> Vec<StockDataWithNo> {
-    println!("[{MODULE_NAME}] 分析 {date} 的複合條件: 單日黑雲罩頂");
+pub fn find_complex_dark_cloud_cover(
+    data: &Data,
+    date: &str,
+    timeframe: Timeframe,
+) -> Vec<StockDataWithNo> {
+    println!("[{MODULE_NAME}] 分析 {date} {timeframe} 的複合條件: 單日黑雲罩頂");
     let mut results = Vec::new();
-    for company in &data.company_map.stock_map {
-        let company_data = common::get_company_data(data, &company.stock_no);
-        let curr_date_index =
-            match get_current_index_by_date(&company_data.stock_data, date, LOOK_BACK_DAYS) {
-                Some(index) => index,
-                None => continue, // 如果找不到日期，跳過這家公司
-            };
+    for (company, company_data) in
+        data.scan_bars("dark_cloud_cover", timeframe, PriceView::Raw, date)
+    {
+        let curr_date_index = match get_current_index(&company_data, data.cfg.look_back_days) {
+            Some(index) => index,
+            None => continue, // 如果找不到日期，跳過這家公司
+        };
 
-        let prev_stock_data = &company_data.stock_data[curr_date_index - 1];
-        let curr_stock_data = &company_data.stock_data[curr_date_index];
+        let prev_stock_data = &company_data[curr_date_index - 1];
+        let curr_stock_data = &company_data[curr_date_index];
 
         // 當天是黑K
         if curr_stock_data.close >= curr_stock_data.open {
@@ -98,7 +110,7 @@ pub fn find_complex_dark_cloud_cover(data: &Data, date: &str) -> Vec<StockDataWi
         // 黑K創新高，創新高看個 5 天
         let mut ignore = false;
         for i in 1..=5 {
-            let past_stock_data = &company_data.stock_data[curr_date_index - i];
+            let past_stock_data = &company_data[curr_date_index - i];
             if curr_stock_data.open <= past_stock_data.close
                 || curr_stock_data.open <= past_stock_data.open
             {
@@ -118,7 +130,12 @@ pub fn find_complex_dark_cloud_cover(data: &Data, date: &str) -> Vec<StockDataWi
         }
 
         // 檢視一下波段，要有低點
-        if !is_swing_low(company_data, curr_stock_data, date) {
+        if !is_swing_low(
+            &company.stock_no,
+            &company_data,
+            curr_stock_data,
+            data.cfg.look_back_days,
+        ) {
             continue;
         }
 
@@ -149,28 +166,36 @@ fn is_bullish_harami_pattern(prev: &StockData, curr: &StockData) -> bool {
     true
 }
 
-pub fn find_complex_bullish_harami_pattern(data: &Data, date: &str) -> Vec<StockDataWithNo> {
+pub fn find_complex_bullish_harami_pattern(
+    data: &Data,
+    date: &str,
+    timeframe: Timeframe,
+) -> Vec<StockDataWithNo> {
     println!(
-        "[{MODULE_NAME}] 分析 {date} 的複合條件: 單日多頭孕線: 當天是紅K且昨天是黑K且紅K實體被黑K實體包覆"
+        "[{MODULE_NAME}] 分析 {date} {timeframe} 的複合條件: 單日多頭孕線: 當天是紅K且昨天是黑K且紅K實體被黑K實體包覆"
     );
     let mut results = Vec::new();
-    for company in &data.company_map.stock_map {
-        let company_data = common::get_company_data(data, &company.stock_no);
-        let curr_date_index =
-            match get_current_index_by_date(&company_data.stock_data, date, LOOK_BACK_DAYS) {
-                Some(index) => index,
-                None => continue, // 如果找不到日期，跳過這家公司
-            };
+    for (company, company_data) in data.scan_bars("bullish_harami", timeframe, PriceView::Raw, date)
+    {
+        let curr_date_index = match get_current_index(&company_data, data.cfg.look_back_days) {
+            Some(index) => index,
+            None => continue, // 如果找不到日期，跳過這家公司
+        };
 
-        let prev_stock_data = &company_data.stock_data[curr_date_index - 1];
-        let curr_stock_data = &company_data.stock_data[curr_date_index];
+        let prev_stock_data = &company_data[curr_date_index - 1];
+        let curr_stock_data = &company_data[curr_date_index];
 
         if !is_bullish_harami_pattern(prev_stock_data, curr_stock_data) {
             continue;
         }
 
         // 檢視一下波段，要有高點
-        if !is_swing_high(company_data, curr_stock_data, date) {
+        if !is_swing_high(
+            &company.stock_no,
+            &company_data,
+            curr_stock_data,
+            data.cfg.look_back_days,
+        ) {
             continue;
         }
 
@@ -201,28 +226,36 @@ fn is_bearish_harami_pattern(prev: &StockData, curr: &StockData) -> bool {
     true
 }
 
-pub fn find_complex_bearish_harami_pattern(data: &Data, date: &str) -> Vec<StockDataWithNo> {
+pub fn find_complex_bearish_harami_pattern(
+    data: &Data,
+    date: &str,
+    timeframe: Timeframe,
+) -> Vec<StockDataWithNo> {
     println!(
-        "[{MODULE_NAME}] 分析 {date} 的複合條件: 單日空頭孕線: 當天是黑K且昨天是紅K且黑K實體被紅K實體包覆"
+        "[{MODULE_NAME}] 分析 {date} {timeframe} 的複合條件: 單日空頭孕線: 當天是黑K且昨天是紅K且黑K實體被紅K實體包覆"
     );
     let mut results = Vec::new();
-    for company in &data.company_map.stock_map {
-        let company_data = common::get_company_data(data, &company.stock_no);
-        let curr_date_index =
-            match get_current_index_by_date(&company_data.stock_data, date, LOOK_BACK_DAYS) {
-                Some(index) => index,
-                None => continue, // 如果找不到日期，跳過這家公司
-            };
+    for (company, company_data) in data.scan_bars("bearish_harami", timeframe, PriceView::Raw, date)
+    {
+        let curr_date_index = match get_current_index(&company_data, data.cfg.look_back_days) {
+            Some(index) => index,
+            None => continue, // 如果找不到日期，跳過這家公司
+        };
 
-        let prev_stock_data = &company_data.stock_data[curr_date_index - 1];
-        let curr_stock_data = &company_data.stock_data[curr_date_index];
+        let prev_stock_data = &company_data[curr_date_index - 1];
+        let curr_stock_data = &company_data[curr_date_index];
 
         if !is_bearish_harami_pattern(prev_stock_data, curr_stock_data) {
             continue;
         }
 
         // 檢視一下波段，要有低點
-        if !is_swing_low(company_data, curr_stock_data, date) {
+        if !is_swing_low(
+            &company.stock_no,
+            &company_data,
+            curr_stock_data,
+            data.cfg.look_back_days,
+        ) {
             continue;
         }
 
@@ -238,20 +271,21 @@ pub fn find_complex_bearish_harami_pattern(data: &Data, date: &str) -> Vec<Stock
 pub fn find_complex_bullish_harami_three_day_reversal_date(
     data: &Data,
     date: &str,
+    timeframe: Timeframe,
 ) -> Vec<StockDataWithNo> {
-    println!("[{MODULE_NAME}] 分析 {date} 的複合條件: 內困三日翻紅");
+    println!("[{MODULE_NAME}] 分析 {date} {timeframe} 的複合條件: 內困三日翻紅");
     let mut results = Vec::new();
-    for company in &data.company_map.stock_map {
-        let company_data = common::get_company_data(data, &company.stock_no);
-        let curr_date_index =
-            match get_current_index_by_date(&company_data.stock_data, date, LOOK_BACK_DAYS) {
-                Some(index) => index,
-                None => continue, // 如果找不到日期，跳過這家公司
-            };
-
-        let prev_prev_stock_data = &company_data.stock_data[curr_date_index - 2];
-        let prev_stock_data = &company_data.stock_data[curr_date_index - 1];
-        let curr_stock_data = &company_data.stock_data[curr_date_index];
+    for (company, company_data) in
+        data.scan_bars("bullish_harami_reversal", timeframe, PriceView::Raw, date)
+    {
+        let curr_date_index = match get_current_index(&company_data, data.cfg.look_back_days) {
+            Some(index) => index,
+            None => continue, // 如果找不到日期，跳過這家公司
+        };
+
+        let prev_prev_stock_data = &company_data[curr_date_index - 2];
+        let prev_stock_data = &company_data[curr_date_index - 1];
+        let curr_stock_data = &company_data[curr_date_index];
 
         // 前兩天是多頭孕線
         if !is_bullish_harami_pattern(prev_prev_stock_data, prev_stock_data) {
@@ -269,8 +303,9 @@ pub fn find_complex_bullish_harami_three_day_reversal_date(
         }
 
         // 當天紅K要帶量
-        let yesterday_date = common::convert_fugle_date_to_yyyymmdd(&prev_stock_data.date);
-        if let Some(mv) = analysis::volume::find_mv(company_data, &yesterday_date) {
+        if let Some(mv) =
+            analysis::volume::find_mv(&company.stock_no, &company_data, &prev_stock_data.date)
+        {
             // println!(
             //     "[{MODULE_NAME}] {} 昨天 MV5: {}, MV10: {}, MV20: {}",
             //     company.stock_no,
@@ -293,7 +328,12 @@ pub fn find_complex_bullish_harami_three_day_reversal_date(
         }
 
         // 檢視一下波段，要有高點
-        if !is_swing_high(company_data, curr_stock_data, date) {
+        if !is_swing_high(
+            &company.stock_no,
+            &company_data,
+            curr_stock_data,
+            data.cfg.look_back_days,
+        ) {
             continue;
         }
 
@@ -306,14 +346,9 @@ pub fn find_complex_bullish_harami_three_day_reversal_date(
     results
 }
 
-fn get_current_index_by_date(
-    company_data: &[StockData],
-    date: &str,
-    range: usize,
-) -> Option<usize> {
-    let curr_date_index = company_data
-        .iter()
-        .position(|d| d.date == common::convert_date_to_fugle_format(date))?;
+/// bars 的最後一根就是當天，前面至少要有 range 根 K 棒
+fn get_current_index(bars: &[StockData], range: usize) -> Option<usize> {
+    let curr_date_index = bars.len().checked_sub(1)?;
     if curr_date_index < range {
         None
     } else {
@@ -324,20 +359,21 @@ fn get_current_index_by_date(
 pub fn find_complex_bearish_harami_three_day_reversal_date(
     data: &Data,
     date: &str,
+    timeframe: Timeframe,
 ) -> Vec<StockDataWithNo> {
-    println!("[{MODULE_NAME}] 分析 {date} 的複合條件: 內困三日翻黑");
+    println!("[{MODULE_NAME}] 分析 {date} {timeframe} 的複合條件: 內困三日翻黑");
     let mut results = Vec::new();
-    for company in &data.company_map.stock_map {
-        let company_data = common::get_company_data(data, &company.stock_no);
-        let curr_date_index =
-            match get_current_index_by_date(&company_data.stock_data, date, LOOK_BACK_DAYS) {
-                Some(index) => index,
-                None => continue, // 如果找不到日期，跳過這家公司
-            };
-
-        let prev_prev_stock_data = &company_data.stock_data[curr_date_index - 2];
-        let prev_stock_data = &company_data.stock_data[curr_date_index - 1];
-        let curr_stock_data = &company_data.stock_data[curr_date_index];
+    for (company, company_data) in
+        data.scan_bars("bearish_harami_reversal", timeframe, PriceView::Raw, date)
+    {
+        let curr_date_index = match get_current_index(&company_data, data.cfg.look_back_days) {
+            Some(index) => index,
+            None => continue, // 如果找不到日期，跳過這家公司
+        };
+
+        let prev_prev_stock_data = &company_data[curr_date_index - 2];
+        let prev_stock_data = &company_data[curr_date_index - 1];
+        let curr_stock_data = &company_data[curr_date_index];
 
         // 前兩天是空頭孕線
         if !is_bearish_harami_pattern(prev_prev_stock_data, prev_stock_data) {
@@ -355,8 +391,9 @@ pub fn find_complex_bearish_harami_three_day_reversal_date(
         }
 
         // 當天黑K不能帶量
-        let yesterday_date = common::convert_fugle_date_to_yyyymmdd(&prev_stock_data.date);
-        if let Some(mv) = analysis::volume::find_mv(company_data, &yesterday_date) {
+        if let Some(mv) =
+            analysis::volume::find_mv(&company.stock_no, &company_data, &prev_stock_data.date)
+        {
             // println!(
             //     "[{MODULE_NAME}] {} 昨天 MV5: {}, MV10: {}, MV20: {}",
             //     company.stock_no,
@@ -379,7 +416,12 @@ pub fn find_complex_bearish_harami_three_day_reversal_date(
         }
 
         // 檢視一下波段，要有低點
-        if !is_swing_low(company_data, curr_stock_data, date) {
+        if !is_swing_low(
+            &company.stock_no,
+            &company_data,
+            curr_stock_data,
+            data.cfg.look_back_days,
+        ) {
             continue;
         }
 
@@ -392,13 +434,21 @@ pub fn find_complex_bearish_harami_three_day_reversal_date(
     results
 }
 
-fn is_swing_low(company_data: &DataCompany, curr_stock_data: &StockData, date: &str) -> bool {
-    let (_max_price, min_price) =
-        match analysis::volume::find_max_min_date_range_company(company_data, date, LOOK_BACK_DAYS)
-        {
-            Some((_max_price, min_price)) => (_max_price, min_price),
-            None => return false,
-        };
+fn is_swing_low(
+    stock_no: &str,
+    company_data: &[StockData],
+    curr_stock_data: &StockData,
+    look_back_days: usize,
+) -> bool {
+    let (_max_price, min_price) = match analysis::volume::find_max_min_date_range(
+        stock_no,
+        company_data,
+        &curr_stock_data.date,
+        look_back_days,
+    ) {
+        Some((_max_price, min_price)) => (_max_price, min_price),
+        None => return false,
+    };
 
     if curr_stock_data.close * 0.7 < min_price {
         return false;
@@ -407,13 +457,21 @@ fn is_swing_low(company_data: &DataCompany, curr_stock_data: &StockData, date: &
     true
 }
 
-fn is_swing_high(company_data: &DataCompany, curr_stock_data: &StockData, date: &str) -> bool {
-    let (max_price, _min_price) =
-        match analysis::volume::find_max_min_date_range_company(company_data, date, LOOK_BACK_DAYS)
-        {
-            Some((max_price, _min_price)) => (max_price, _min_price),
-            None => return false,
-        };
+fn is_swing_high(
+    stock_no: &str,
+    company_data: &[StockData],
+    curr_stock_data: &StockData,
+    look_back_days: usize,
+) -> bool {
+    let (max_price, _min_price) = match analysis::volume::find_max_min_date_range(
+        stock_no,
+        company_data,
+        &curr_stock_data.date,
+        look_back_days,
+    ) {
+        Some((max_price, _min_price)) => (max_price, _min_price),
+        None => return false,
+    };
 
     if curr_stock_data.close * 1.3 > max_price {
         return false;
@@ -422,21 +480,25 @@ fn is_swing_high(company_data: &DataCompany, curr_stock_data: &StockData, date:
     true
 }
 
-pub fn find_complex_upside_gap_two_crows(data: &Data, date: &str) -> Vec<StockDataWithNo> {
-    println!("[{MODULE_NAME}] 分析 {date} 的複合條件: 烏鴉躍空");
+pub fn find_complex_upside_gap_two_crows(
+    data: &Data,
+    date: &str,
+    timeframe: Timeframe,
+) -> Vec<StockDataWithNo> {
+    println!("[{MODULE_NAME}] 分析 {date} {timeframe} 的複合條件: 烏鴉躍空");
 
     let mut results = Vec::new();
-    for company in &data.company_map.stock_map {
-        let company_data = common::get_company_data(data, &company.stock_no);
-        let curr_date_index =
-            match get_current_index_by_date(&company_data.stock_data, date, LOOK_BACK_DAYS) {
-                Some(index) => index,
-                None => continue, // 如果找不到日期，跳過這家公司
-            };
-
-        let prev_prev_stock_data = &company_data.stock_data[curr_date_index - 2];
-        let prev_stock_data = &company_data.stock_data[curr_date_index - 1];
-        let curr_stock_data = &company_data.stock_data[curr_date_index];
+    for (company, company_data) in
+        data.scan_bars("upside_gap_two_crows", timeframe, PriceView::Raw, date)
+    {
+        let curr_date_index = match get_current_index(&company_data, data.cfg.look_back_days) {
+            Some(index) => index,
+            None => continue, // 如果找不到日期，跳過這家公司
+        };
+
+        let prev_prev_stock_data = &company_data[curr_date_index - 2];
+        let prev_stock_data = &company_data[curr_date_index - 1];
+        let curr_stock_data = &company_data[curr_date_index];
 
         // 前兩天是紅K
         if prev_prev_stock_data.close <= prev_prev_stock_data.open {
@@ -463,7 +525,7 @@ pub fn find_complex_upside_gap_two_crows(data: &Data, date: &str) -> Vec<StockDa
         // 黑K創新高，創新高看個 5 天
         let mut ignore = false;
         for i in 2..=6 {
-            let past_stock_data = &company_data.stock_data[curr_date_index - i];
+            let past_stock_data = &company_data[curr_date_index - i];
             if curr_stock_data.open <= past_stock_data.close
                 || curr_stock_data.open <= past_stock_data.open
             {
@@ -482,7 +544,12 @@ pub fn find_complex_upside_gap_two_crows(data: &Data, date: &str) -> Vec<StockDa
         }
 
         // 檢視一下波段，要有低點
-        if !is_swing_low(company_data, curr_stock_data, date) {
+        if !is_swing_low(
+            &company.stock_no,
+            &company_data,
+            curr_stock_data,
+            data.cfg.look_back_days,
+        ) {
             continue;
         }
 
@@ -0,0 +1,94 @@
+use crate::analysis::cross::{self, CrossType};
+use crate::analysis::kd;
+use crate::analysis::kd::{KdCalculator, KdCross, KdPassivation, KdResult, KdZone};
+use crate::stocks::data::Data;
+use crate::stocks::data_company::StockDataWithNo;
+use crate::stocks::timeframe::Timeframe;
+
+const MODULE_NAME: &str = "scripts::kd";
+
+/// 用 matcher 檢查每家公司到 date 為止的 KD 序列與交叉，符合的話列出 date 那根 K 棒
+fn find_kd_date(
+    data: &Data,
+    date: &str,
+    timeframe: Timeframe,
+    matcher: impl Fn(&[KdResult], &[KdCross]) -> bool,
+) -> Vec<StockDataWithNo> {
+    let mut kd_data_all = Vec::new();
+    for (company, bars, last) in data.scan_indicator_bars("kd", timeframe, date) {
+        let mut kd_calculator = KdCalculator::new(company.stock_no.clone());
+        if let Some((results, crosses)) = kd_calculator.calc(&bars, &last.date)
+            && matcher(&results, &crosses)
+        {
+            kd_data_all.push(StockDataWithNo {
+                stock_no: company.stock_no.clone(),
+                stock_data: last,
+            });
+        }
+    }
+    kd_data_all
+}
+
+/// 最後一根 K 棒的交叉，zone 為 None 時不限高低檔
+fn crossed_on_last_bar(
+    results: &[KdResult],
+    crosses: &[KdCross],
+    cross_type: CrossType,
+    zone: Option<KdZone>,
+) -> bool {
+    results
+        .last()
+        .is_some_and(|last| cross::crossed_on(crosses, &last.date, cross_type, zone))
+}
+
+pub fn find_kd_golden_cross_date(
+    data: &Data,
+    date: &str,
+    timeframe: Timeframe,
+) -> Vec<StockDataWithNo> {
+    println!("[{MODULE_NAME}] 分析 {date} {timeframe} 的 KD 黃金交叉");
+
+    find_kd_date(data, date, timeframe, |results, crosses| {
+        crossed_on_last_bar(results, crosses, CrossType::GoldenCross, None)
+    })
+}
+
+pub fn find_kd_death_cross_date(
+    data: &Data,
+    date: &str,
+    timeframe: Timeframe,
+) -> Vec<StockDataWithNo> {
+    println!("[{MODULE_NAME}] 分析 {date} {timeframe} 的 KD 死亡交叉");
+
+    find_kd_date(data, date, timeframe, |results, crosses| {
+        crossed_on_last_bar(results, crosses, CrossType::DeathCross, None)
+    })
+}
+
+/// 指定高低檔的交叉，例如 20 以下的黃金交叉、80 以上的死亡交叉
+pub fn find_kd_cross_zone_date(
+    data: &Data,
+    date: &str,
+    timeframe: Timeframe,
+    cross_type: CrossType,
+    zone: KdZone,
+) -> Vec<StockDataWithNo> {
+    println!("[{MODULE_NAME}] 分析 {date} {timeframe} 的 KD {zone}{cross_type}");
+
+    find_kd_date(data, date, timeframe, |results, crosses| {
+        crossed_on_last_bar(results, crosses, cross_type, Some(zone))
+    })
+}
+
+pub fn find_kd_passivation_date(
+    data: &Data,
+    date: &str,
+    timeframe: Timeframe,
+    passivation: KdPassivation,
+) -> Vec<StockDataWithNo> {
+    println!("[{MODULE_NAME}] 分析 {date} {timeframe} 的 KD {passivation}");
+
+    find_kd_date(data, date, timeframe, |results, _| {
+        kd::passivation(results) == Some(passivation)
+    })
+}
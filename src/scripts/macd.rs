@@ -1,54 +1,117 @@
-use crate::analysis;
-use crate::common;
+use crate::analysis::cross::{self, CrossType};
+use crate::analysis::divergence;
+use crate::analysis::divergence::Divergence;
+use crate::analysis::macd;
+use crate::analysis::macd::{MacdCalculator, MacdCross, MacdResult, MacdZone};
 use crate::stocks::data::Data;
 use crate::stocks::data_company::StockDataWithNo;
+use crate::stocks::timeframe::Timeframe;
 
 const MODULE_NAME: &str = "scripts::macd";
 
-pub fn find_macd_golden_cross_date_company(
+/// 柱狀體縮小要連續看幾根
+const SHRINKING_BARS: usize = 3;
+
+/// 用 matcher 檢查每家公司到 date 為止的 MACD 序列與交叉，符合的話列出 date 那根 K 棒
+fn find_macd_date(
     data: &Data,
-    stock_no: String,
     date: &str,
+    timeframe: Timeframe,
+    matcher: impl Fn(&[MacdResult], &[MacdCross]) -> bool,
 ) -> Vec<StockDataWithNo> {
-    let date_fugle_format = common::convert_date_to_fugle_format(date);
-    let mut macd_golden_cross_data_all = Vec::new();
-
-    let data_company = data.data_company.get(&stock_no).expect("找不到股票資料");
-
-    let mut macd_calculator = analysis::macd::MacdCalculator::new(stock_no.clone());
-    if let Some((_res, crosses)) = macd_calculator.calc(data_company, date) {
-        for cross in &crosses {
-            if cross.date == date_fugle_format
-                && cross.cross_type == analysis::macd::MacdCrossType::GoldenCross
-            {
-                let stock_date = data_company
-                    .stock_data
-                    .iter()
-                    .find(|d| d.date == date_fugle_format)
-                    .unwrap();
-                let clone_cross = StockDataWithNo {
-                    stock_no: cross.stock_no.clone(),
-                    stock_data: stock_date.clone(),
-                };
-                macd_golden_cross_data_all.push(clone_cross);
-            }
+    let mut macd_data_all = Vec::new();
+    for (company, bars, last) in data.scan_indicator_bars("macd", timeframe, date) {
+        let mut macd_calculator = MacdCalculator::new(company.stock_no.clone());
+        if let Some((results, crosses)) = macd_calculator.calc(&bars, &last.date)
+            && matcher(&results, &crosses)
+        {
+            macd_data_all.push(StockDataWithNo {
+                stock_no: company.stock_no.clone(),
+                stock_data: last,
+            });
         }
     }
+    macd_data_all
+}
+
+/// 最後一根 K 棒的交叉，zone 為 None 時不限零軸上下
+fn crossed_on_last_bar(
+    results: &[MacdResult],
+    crosses: &[MacdCross],
+    cross_type: CrossType,
+    zone: Option<MacdZone>,
+) -> bool {
+    results
+        .last()
+        .is_some_and(|last| cross::crossed_on(crosses, &last.date, cross_type, zone))
+}
+
+pub fn find_macd_golden_cross_date(
+    data: &Data,
+    date: &str,
+    timeframe: Timeframe,
+) -> Vec<StockDataWithNo> {
+    println!("[{MODULE_NAME}] 分析 {date} {timeframe} 的 MACD 黃金交叉");
+
+    find_macd_date(data, date, timeframe, |results, crosses| {
+        crossed_on_last_bar(results, crosses, CrossType::GoldenCross, None)
+    })
+}
+
+/// 指定零軸上或零軸下的交叉，例如零軸下的黃金交叉
+pub fn find_macd_cross_zone_date(
+    data: &Data,
+    date: &str,
+    timeframe: Timeframe,
+    cross_type: CrossType,
+    zone: MacdZone,
+) -> Vec<StockDataWithNo> {
+    println!("[{MODULE_NAME}] 分析 {date} {timeframe} 的 MACD {zone}{cross_type}");
 
-    macd_golden_cross_data_all
+    find_macd_date(data, date, timeframe, |results, crosses| {
+        crossed_on_last_bar(results, crosses, cross_type, Some(zone))
+    })
 }
 
-pub fn find_macd_golden_cross_date(data: &Data, date: &str) -> Vec<StockDataWithNo> {
-    println!("[{MODULE_NAME}] 分析 {date} 的 MACD 黃金交叉");
+/// 柱狀體連續縮小，紅柱縮短代表漲勢減弱、綠柱縮短代表跌勢減弱
+pub fn find_macd_histogram_shrinking_date(
+    data: &Data,
+    date: &str,
+    timeframe: Timeframe,
+) -> Vec<StockDataWithNo> {
+    println!("[{MODULE_NAME}] 分析 {date} {timeframe} 的 MACD 柱狀體連續 {SHRINKING_BARS} 根縮小");
 
-    // let date_fugle_format = common::convert_date_to_fugle_format(date);
-    let mut macd_golden_cross_data_all = Vec::new();
+    find_macd_date(data, date, timeframe, |results, _| {
+        macd::histogram_shrinking(results, SHRINKING_BARS)
+    })
+}
 
-    for company in &data.company_map.stock_map {
-        let macd_golden_cross_data_company =
-            find_macd_golden_cross_date_company(data, company.stock_no.clone(), date);
-        macd_golden_cross_data_all.extend(macd_golden_cross_data_company);
+/// date 那天確認了波段點的 MACD 背離 (DIF 或柱狀體，一般或隱藏)，一家公司可能同時有好幾個
+pub fn find_macd_divergence_date(
+    data: &Data,
+    date: &str,
+    timeframe: Timeframe,
+) -> Vec<(StockDataWithNo, Vec<Divergence>)> {
+    println!("[{MODULE_NAME}] 分析 {date} {timeframe} 的 MACD 背離");
+
+    let mut results = Vec::new();
+    // 波段點與 MACD 用同一種價格
+    for (company, bars, last) in data.scan_indicator_bars("macd_divergence", timeframe, date) {
+        let mut macd_calculator = MacdCalculator::new(company.stock_no.clone());
+        let Some((macd_results, _)) = macd_calculator.calc(&bars, &last.date) else {
+            continue;
+        };
+        let divergences = divergence::find_divergences_on_last_bar(&bars, &macd_results);
+        if !divergences.is_empty() {
+            results.push((
+                StockDataWithNo {
+                    stock_no: company.stock_no.clone(),
+                    stock_data: last,
+                },
+                divergences,
+            ));
+        }
     }
 
-    macd_golden_cross_data_all
+    results
 }
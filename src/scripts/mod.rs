@@ -1,4 +1,6 @@
 pub mod bullish_engulfing;
 pub mod candlestick;
 pub mod complex;
+pub mod kd;
 pub mod macd;
+pub mod moving_average;
@@ -0,0 +1,86 @@
+use crate::analysis::cross::CrossType;
+use crate::analysis::moving_average;
+use crate::analysis::moving_average::{
+    Alignment, DeductionOutlook, MA_LONG, MA_MID, MA_SHORT, MaKind,
+};
+use crate::stocks::data::Data;
+use crate::stocks::data_company::{StockData, StockDataWithNo};
+use crate::stocks::timeframe::Timeframe;
+
+const MODULE_NAME: &str = "scripts::moving_average";
+
+/// 用 matcher 檢查每家公司到 date 為止的 K 棒 (指標價格)，符合的話列出 date 那根實際成交價
+fn find_ma_date(
+    data: &Data,
+    date: &str,
+    timeframe: Timeframe,
+    matcher: impl Fn(&[StockData]) -> bool,
+) -> Vec<StockDataWithNo> {
+    let mut results = Vec::new();
+    for (company, bars, last) in data.scan_indicator_bars("moving_average", timeframe, date) {
+        if matcher(&bars) {
+            results.push(StockDataWithNo {
+                stock_no: company.stock_no.clone(),
+                stock_data: last,
+            });
+        }
+    }
+    results
+}
+
+/// date 那根 fast 日均線穿過 slow 日均線，例如 MA5 黃金交叉 MA20
+pub fn find_ma_cross_date(
+    data: &Data,
+    date: &str,
+    timeframe: Timeframe,
+    kind: MaKind,
+    (fast, slow): (usize, usize),
+    cross_type: CrossType,
+) -> Vec<StockDataWithNo> {
+    println!("[{MODULE_NAME}] 分析 {date} {timeframe} 的 {kind}{fast} {cross_type} {kind}{slow}");
+
+    find_ma_date(data, date, timeframe, |bars| {
+        let fast = moving_average::moving_average(bars, kind, fast);
+        let slow = moving_average::moving_average(bars, kind, slow);
+        moving_average::cross_at(&fast, &slow, bars.len() - 1) == Some(cross_type)
+    })
+}
+
+/// date 那根剛形成 MA5、MA20、MA60 的多頭 (空頭) 排列，前一根還不是
+pub fn find_ma_alignment_date(
+    data: &Data,
+    date: &str,
+    timeframe: Timeframe,
+    kind: MaKind,
+    alignment: Alignment,
+) -> Vec<StockDataWithNo> {
+    println!(
+        "[{MODULE_NAME}] 分析 {date} {timeframe} 剛形成 {kind}{MA_SHORT}/{kind}{MA_MID}/{kind}{MA_LONG} {alignment}"
+    );
+
+    find_ma_date(data, date, timeframe, |bars| {
+        let short = moving_average::moving_average(bars, kind, MA_SHORT);
+        let mid = moving_average::moving_average(bars, kind, MA_MID);
+        let long = moving_average::moving_average(bars, kind, MA_LONG);
+        let curr = bars.len() - 1;
+        moving_average::alignment_at(&short, &mid, &long, curr) == Some(alignment)
+            && curr > 0
+            && moving_average::alignment_at(&short, &mid, &long, curr - 1) != Some(alignment)
+    })
+}
+
+/// 用扣抵值判斷 period 日均線明天可能轉向的股票，扣抵只適用 SMA
+pub fn find_ma_deduction_date(
+    data: &Data,
+    date: &str,
+    timeframe: Timeframe,
+    period: usize,
+    outlook: DeductionOutlook,
+) -> Vec<StockDataWithNo> {
+    println!("[{MODULE_NAME}] 分析 {date} {timeframe} 的 MA{period} {outlook}");
+
+    find_ma_date(data, date, timeframe, |bars| {
+        let average = moving_average::moving_average(bars, MaKind::Sma, period);
+        moving_average::deduction_outlook(bars, &average, period, bars.len() - 1) == Some(outlook)
+    })
+}
@@ -0,0 +1,145 @@
+use serde::{Deserialize, Serialize};
+
+use crate::stocks::data_company::StockData;
+
+/// 除權息、減資等公司行為造成的還原因子
+///
+/// date 是除權息 (或減資恢復買賣) 當天，date 之前的價格乘上 factor 就是還原價
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq)]
+pub struct Adjustment {
+    pub date: String,
+    pub factor: f64,
+}
+
+/// 從原始日K找出 from ~ to (含) 之間的除權息日
+///
+/// 一般交易日的參考價 (close - change) 等於前一天收盤價，
+/// 除權息日的參考價會被交易所調整，兩者的比例就是還原因子；沒有漲跌的日子無法判斷，略過
+pub fn derive(stock_data: &[StockData], from: &str, to: &str) -> Vec<Adjustment> {
+    stock_data
+        .windows(2)
+        .filter(|pair| (from..=to).contains(&pair[1].date.as_str()))
+        .filter_map(|pair| {
+            let (prev, curr) = (&pair[0], &pair[1]);
+            let reference = curr.close - curr.change?;
+            // 價格只到小數第二位，差半分錢以內視為同一個價格
+            if prev.close <= 0.0 || reference <= 0.0 || (reference - prev.close).abs() < 0.005 {
+                return None;
+            }
+            Some(Adjustment {
+                date: curr.date.clone(),
+                factor: reference / prev.close,
+            })
+        })
+        .collect()
+}
+
+/// 把原始日K換算成還原日K，adjustments 需要依日期排序
+pub fn apply(stock_data: &[StockData], adjustments: &[Adjustment]) -> Vec<StockData> {
+    let mut adjusted = stock_data.to_vec();
+    let mut pending = adjustments.iter().rev().peekable();
+    let mut factor = 1.0;
+
+    // 從最新的資料往回乘，越早的資料累積越多次除權息
+    for data in adjusted.iter_mut().rev() {
+        while let Some(adjustment) = pending.next_if(|a| a.date > data.date) {
+            factor *= adjustment.factor;
+        }
+        if factor != 1.0 {
+            data.open *= factor;
+            data.high *= factor;
+            data.low *= factor;
+            data.close *= factor;
+            data.change = data.change.map(|change| change * factor);
+        }
+    }
+
+    adjusted
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn bar(date: &str, close: f64, change: f64) -> StockData {
+        StockData {
+            change: Some(change),
+            ..unknown_change(date, close)
+        }
+    }
+
+    fn unknown_change(date: &str, close: f64) -> StockData {
+        StockData {
+            date: date.to_string(),
+            open: close,
+            high: close,
+            low: close,
+            close,
+            volume: 1,
+            turnover: 0,
+            change: None,
+        }
+    }
+
+    fn history() -> Vec<StockData> {
+        vec![
+            bar("2025-06-09", 100.0, 1.0),
+            // 除息 5 元: 參考價 95，收 96 漲 1
+            bar("2025-06-10", 96.0, 1.0),
+            bar("2025-06-11", 97.0, 1.0),
+            bar("2025-06-12", 100.0, 3.0),
+            // 1 拆 4: 參考價 25，收 26 漲 1
+            bar("2025-06-13", 26.0, 1.0),
+        ]
+    }
+
+    #[test]
+    fn derives_ex_dividend_and_split_factors() {
+        let adjustments = derive(&history(), "2025-01-01", "2025-12-31");
+
+        assert_eq!(
+            adjustments,
+            [
+                Adjustment {
+                    date: "2025-06-10".to_string(),
+                    factor: 0.95,
+                },
+                Adjustment {
+                    date: "2025-06-13".to_string(),
+                    factor: 0.25,
+                },
+            ]
+        );
+        // 範圍外的除權息日不會出現
+        assert_eq!(derive(&history(), "2025-06-11", "2025-06-12"), []);
+    }
+
+    #[test]
+    fn applies_factors_before_each_ex_date() {
+        let stock_data = history();
+        let adjusted = apply(
+            &stock_data,
+            &derive(&stock_data, "2025-01-01", "2025-12-31"),
+        );
+        let closes: Vec<f64> = adjusted.iter().map(|d| d.close).collect();
+
+        // 除息前乘 0.95 再乘 0.25，拆分前乘 0.25，拆分當天以後不變
+        assert_eq!(closes, [23.75, 24.0, 24.25, 25.0, 26.0]);
+        assert_eq!(adjusted[0].change, Some(0.2375));
+        assert_eq!(adjusted[4].change, Some(1.0));
+        // 還原後除權息當天的漲跌就是跟前一天還原收盤價的差
+        let reference = |d: &StockData| d.close - d.change.unwrap();
+        assert!((reference(&adjusted[1]) - adjusted[0].close).abs() < 1e-9);
+        assert!((reference(&adjusted[4]) - adjusted[3].close).abs() < 1e-9);
+    }
+
+    #[test]
+    fn unknown_change_is_not_an_ex_date() {
+        // TWSE 不比價的日子沒有漲跌，不能當成參考價等於收盤價
+        let stock_data = [
+            bar("2025-06-09", 100.0, 1.0),
+            unknown_change("2025-06-10", 96.0),
+        ];
+        assert_eq!(derive(&stock_data, "2025-01-01", "2025-12-31"), []);
+    }
+}
@@ -0,0 +1,201 @@
+use std::collections::HashMap;
+use std::fmt;
+use std::fs::File;
+use std::io;
+use std::path::Path;
+
+use serde::{Deserialize, Serialize};
+
+use crate::error::Result;
+use crate::stocks::company_map::{CompanyInfo, Market};
+
+/// 公司清單的一筆異動
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq)]
+#[serde(tag = "kind", rename_all = "snake_case")]
+pub enum ChangeKind {
+    Listed,
+    Delisted,
+    Renamed { from: String, to: String },
+    Reclassified { from: String, to: String },
+    MarketChanged { from: Market, to: Market },
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct CompanyEvent {
+    /// YYYY-MM-DD，上市用上市日期，其他異動是發現異動的那次更新日期
+    pub date: String,
+    pub stock_no: String,
+    pub name: String,
+    #[serde(flatten)]
+    pub kind: ChangeKind,
+}
+
+impl fmt::Display for CompanyEvent {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{} {} {} ", self.date, self.stock_no, self.name)?;
+        match &self.kind {
+            ChangeKind::Listed => write!(f, "掛牌"),
+            ChangeKind::Delisted => write!(f, "下市 (櫃)"),
+            ChangeKind::Renamed { from, to } => write!(f, "更名 {from} → {to}"),
+            ChangeKind::Reclassified { from, to } => write!(f, "產業別 {from} → {to}"),
+            ChangeKind::MarketChanged { from, to } => write!(f, "轉市場 {from} → {to}"),
+        }
+    }
+}
+
+/// 依日期排序的公司異動紀錄，存在 company_history.json
+pub struct CompanyHistory {
+    path: String,
+    pub events: Vec<CompanyEvent>,
+}
+
+impl CompanyHistory {
+    pub fn new(path: &str) -> Result<Self> {
+        let events = if Path::new(path).exists() {
+            let reader = io::BufReader::new(File::open(path)?);
+            serde_json::from_reader(reader)?
+        } else {
+            Vec::new()
+        };
+
+        Ok(CompanyHistory {
+            path: path.to_string(),
+            events,
+        })
+    }
+
+    pub fn save(&self) -> Result<()> {
+        let json = serde_json::to_string_pretty(&self.events)?;
+        std::fs::write(&self.path, json)?;
+        Ok(())
+    }
+
+    pub fn extend(&mut self, events: Vec<CompanyEvent>) {
+        self.events.extend(events);
+        self.events.sort_by(|a, b| a.date.cmp(&b.date));
+    }
+
+    /// 最近一次下市 (櫃) 以後最早的掛牌日期 (YYYY-MM-DD)，轉上市的公司是當初上櫃的日期
+    pub fn first_listed_date(&self, stock_no: &str) -> Option<&str> {
+        let mut first = None;
+        for event in self
+            .events
+            .iter()
+            .filter(|event| event.stock_no == stock_no)
+        {
+            match event.kind {
+                ChangeKind::Listed if first.is_none() => first = Some(event.date.as_str()),
+                ChangeKind::Delisted => first = None,
+                _ => {}
+            }
+        }
+        first
+    }
+}
+
+/// 比對新舊兩份公司清單，date 是這次更新的日期 (YYYY-MM-DD)
+pub fn diff(old: &[CompanyInfo], new: &[CompanyInfo], date: &str) -> Vec<CompanyEvent> {
+    let old_map: HashMap<&str, &CompanyInfo> =
+        old.iter().map(|c| (c.stock_no.as_str(), c)).collect();
+    let new_map: HashMap<&str, &CompanyInfo> =
+        new.iter().map(|c| (c.stock_no.as_str(), c)).collect();
+    let event = |company: &CompanyInfo, date: &str, kind| CompanyEvent {
+        date: date.to_string(),
+        stock_no: company.stock_no.clone(),
+        name: company.name.clone(),
+        kind,
+    };
+
+    let mut events = Vec::new();
+    for company in new {
+        let Some(previous) = old_map.get(company.stock_no.as_str()) else {
+            let listed = company.listing_date().unwrap_or_else(|| date.to_string());
+            events.push(event(company, &listed, ChangeKind::Listed));
+            continue;
+        };
+        if previous.name != company.name {
+            events.push(event(
+                company,
+                date,
+                ChangeKind::Renamed {
+                    from: previous.name.clone(),
+                    to: company.name.clone(),
+                },
+            ));
+        }
+        if previous.industry != company.industry {
+            events.push(event(
+                company,
+                date,
+                ChangeKind::Reclassified {
+                    from: previous.industry.clone(),
+                    to: company.industry.clone(),
+                },
+            ));
+        }
+        if previous.market != company.market {
+            events.push(event(
+                company,
+                date,
+                ChangeKind::MarketChanged {
+                    from: previous.market,
+                    to: company.market,
+                },
+            ));
+        }
+    }
+    for company in old {
+        if !new_map.contains_key(company.stock_no.as_str()) {
+            events.push(event(company, date, ChangeKind::Delisted));
+        }
+    }
+
+    events.sort_by(|a, b| a.date.cmp(&b.date));
+    events
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn event(date: &str, kind: ChangeKind) -> CompanyEvent {
+        CompanyEvent {
+            date: date.to_string(),
+            stock_no: "6488".to_string(),
+            name: "環球晶".to_string(),
+            kind,
+        }
+    }
+
+    #[test]
+    fn first_listed_date_keeps_the_otc_listing() {
+        let history = CompanyHistory {
+            path: String::new(),
+            events: vec![
+                event("2015-09-25", ChangeKind::Listed),
+                event(
+                    "2026-03-02",
+                    ChangeKind::MarketChanged {
+                        from: Market::Tpex,
+                        to: Market::Twse,
+                    },
+                ),
+            ],
+        };
+        assert_eq!(history.first_listed_date("6488"), Some("2015-09-25"));
+        assert_eq!(history.first_listed_date("2330"), None);
+    }
+
+    #[test]
+    fn first_listed_date_restarts_after_delisting() {
+        let history = CompanyHistory {
+            path: String::new(),
+            events: vec![
+                event("2010-01-04", ChangeKind::Listed),
+                event("2018-06-01", ChangeKind::Delisted),
+                event("2022-11-15", ChangeKind::Listed),
+            ],
+        };
+        assert_eq!(history.first_listed_date("6488"), Some("2022-11-15"));
+    }
+}
@@ -1,16 +1,46 @@
-use std::collections::HashMap;
+use std::collections::{BTreeMap, HashMap};
+use std::fmt;
+use std::fs;
 use std::fs::File;
 use std::io;
 use std::path::Path;
+use std::time::Duration;
 
+use chrono::{Local, NaiveDate};
 use serde::{Deserialize, Serialize};
 
+use crate::cfg::data::CfgData;
+use crate::error::{Error, Result};
+use crate::stocks::company_history;
+use crate::stocks::company_history::{CompanyEvent, CompanyHistory};
+use crate::tpex;
 use crate::twse;
 
 const MODULE_NAME: &str = "stocks::company_map";
-const COMPANY_MAP: &str = "company_map.json";
 
-#[derive(Debug, Deserialize, Serialize)]
+/// ETF 沒有產業別，用這個代碼跟一般公司區分
+pub const ETF_INDUSTRY: &str = "ETF";
+
+/// 掛牌市場
+#[derive(Debug, Deserialize, Serialize, Default, Clone, Copy, PartialEq, Eq)]
+pub enum Market {
+    #[default]
+    #[serde(rename = "上市")]
+    Twse,
+    #[serde(rename = "上櫃")]
+    Tpex,
+}
+
+impl fmt::Display for Market {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Market::Twse => write!(f, "上市"),
+            Market::Tpex => write!(f, "上櫃"),
+        }
+    }
+}
+
+#[derive(Debug, Deserialize, Serialize, Clone)]
 pub struct CompanyInfo {
     #[serde(rename = "公司代號")]
     pub stock_no: String,
@@ -18,21 +48,186 @@ pub struct CompanyInfo {
     pub name: String,
     #[serde(rename = "產業別")]
     pub industry: String,
+    /// 舊的 company_map.json 沒有這個欄位，都是上市公司
+    #[serde(rename = "市場別", default)]
+    pub market: Market,
+    // 以下欄位舊的 company_map.json 都沒有，格式照 TWSE 原始資料存字串
+    #[serde(rename = "公司名稱", default)]
+    pub full_name: String,
+    /// YYYYMMDD
+    #[serde(rename = "上市日期", default)]
+    pub listed_on: String,
+    /// 新台幣元
+    #[serde(rename = "實收資本額", default)]
+    pub paid_in_capital: String,
+    #[serde(rename = "已發行普通股數或TDR原股發行股數", default)]
+    pub shares_issued: String,
+}
+
+impl CompanyInfo {
+    /// 上市 (櫃) 日期，轉成 YYYY-MM-DD
+    pub fn listing_date(&self) -> Option<String> {
+        NaiveDate::parse_from_str(&self.listed_on, "%Y%m%d")
+            .ok()
+            .map(|date| date.format("%Y-%m-%d").to_string())
+    }
+
+    /// 實收資本額 (億元)
+    pub fn capital_100m(&self) -> Option<f64> {
+        parse_amount(&self.paid_in_capital).map(|capital| capital / 1e8)
+    }
+
+    pub fn shares(&self) -> Option<f64> {
+        parse_amount(&self.shares_issued).filter(|shares| *shares > 0.0)
+    }
+
+    /// 市值 (億元)
+    pub fn market_cap_100m(&self, close: f64) -> Option<f64> {
+        self.shares().map(|shares| shares * close / 1e8)
+    }
+
+    /// 週轉率 (%) = 成交股數 / 發行股數
+    pub fn turnover_rate(&self, volume: u64) -> Option<f64> {
+        self.shares().map(|shares| volume as f64 / shares * 100.0)
+    }
+}
+
+fn parse_amount(text: &str) -> Option<f64> {
+    text.replace(',', "").trim().parse().ok()
 }
 
 pub struct CompanyMap {
     pub stock_map: Vec<CompanyInfo>,
+    pub history: CompanyHistory,
     industry_map: HashMap<String, String>,
+    company_map_path: String,
+    urls: ListingUrls,
+    etfs: BTreeMap<String, String>,
+    offline: bool,
+}
+
+/// 上市、上櫃公司清單 API 的網址
+struct ListingUrls {
+    twse: String,
+    tpex: String,
 }
 
 impl CompanyMap {
-    pub async fn new() -> Self {
+    pub async fn new(cfg: &CfgData) -> Result<Self> {
         let industry_map = build_industry_map();
-        let stock_map = get_company_map().await;
+        let urls = ListingUrls {
+            twse: cfg.twse_openapi_url.clone(),
+            tpex: cfg.tpex_openapi_url.clone(),
+        };
+        let mut history = CompanyHistory::new(&cfg.company_history_path)?;
+        let stored = read_company_map(&cfg.company_map_path)?;
+        let stock_map = match stored {
+            // 離線時不管多舊都直接用
+            Some(stock_map) if cfg.offline => stock_map,
+            None if cfg.offline => {
+                return Err(Error::Offline(format!(
+                    "{} 不存在，需要下載公司清單",
+                    cfg.company_map_path
+                )));
+            }
+            Some(stock_map)
+                if !needs_refresh(
+                    &cfg.company_map_path,
+                    &stock_map,
+                    cfg.company_map_refresh_days,
+                ) =>
+            {
+                stock_map
+            }
+            stored => {
+                let (stock_map, events) =
+                    refresh_snapshot(&urls, &cfg.company_map_path, stored.unwrap_or_default())
+                        .await?;
+                if !events.is_empty() {
+                    print_events(&events);
+                    history.extend(events);
+                    history.save()?;
+                }
+                stock_map
+            }
+        };
 
-        CompanyMap {
+        let mut company_map = CompanyMap {
             stock_map,
+            history,
             industry_map,
+            company_map_path: cfg.company_map_path.clone(),
+            urls,
+            etfs: cfg.etfs.clone(),
+            offline: cfg.offline,
+        };
+        company_map.add_etfs();
+        Ok(company_map)
+    }
+
+    /// 重新下載公司清單，跟目前的清單比對後記錄異動，回傳這次的異動
+    pub async fn refresh(&mut self) -> Result<Vec<CompanyEvent>> {
+        if self.offline {
+            return Err(Error::Offline("更新公司清單".to_string()));
+        }
+        let old: Vec<CompanyInfo> = self
+            .stock_map
+            .iter()
+            .filter(|company| company.industry != ETF_INDUSTRY)
+            .cloned()
+            .collect();
+        let (stock_map, events) = refresh_snapshot(&self.urls, &self.company_map_path, old).await?;
+        if !events.is_empty() {
+            self.history.extend(events.clone());
+            self.history.save()?;
+        }
+
+        self.stock_map = stock_map;
+        self.add_etfs();
+        Ok(events)
+    }
+
+    // ETF 不在公司清單裡，由設定檔列出
+    fn add_etfs(&mut self) {
+        for (stock_no, name) in &self.etfs {
+            if !self
+                .stock_map
+                .iter()
+                .any(|company| &company.stock_no == stock_no)
+            {
+                self.stock_map.push(CompanyInfo {
+                    stock_no: stock_no.clone(),
+                    name: name.clone(),
+                    industry: ETF_INDUSTRY.to_string(),
+                    market: Market::Twse,
+                    full_name: name.clone(),
+                    listed_on: String::new(),
+                    paid_in_capital: String::new(),
+                    shares_issued: String::new(),
+                });
+            }
+        }
+        self.stock_map.sort_by(|a, b| a.stock_no.cmp(&b.stock_no));
+    }
+
+    pub fn get_company(&self, stock_no: &str) -> Option<&CompanyInfo> {
+        self.stock_map
+            .iter()
+            .find(|company| company.stock_no == stock_no)
+    }
+
+    /// 開始有交易資料的日期 (YYYY-MM-DD)：公司清單與異動紀錄裡較早的掛牌日期，
+    /// 轉上市的公司清單上是上市日期，異動紀錄裡還有當初的上櫃日期
+    pub fn listing_date(&self, stock_no: &str) -> Option<String> {
+        let listed = self
+            .stock_map
+            .iter()
+            .find(|company| company.stock_no == stock_no)
+            .and_then(CompanyInfo::listing_date);
+        let first_listed = self.history.first_listed_date(stock_no).map(str::to_string);
+        match (listed, first_listed) {
+            (Some(listed), Some(first_listed)) => Some(listed.min(first_listed)),
+            (listed, first_listed) => listed.or(first_listed),
         }
     }
 
@@ -42,20 +237,54 @@ impl CompanyMap {
     //     }
     // }
 
-    pub fn get_name(&self, stock_no: &str) -> String {
+    pub fn get_name(&self, stock_no: &str) -> Result<String> {
         for company in &self.stock_map {
             if company.stock_no == stock_no {
-                return format!(
+                return Ok(format!(
                     "{}/{}",
                     company.name,
                     self.industry_map
                         .get(&company.industry)
                         .unwrap_or(&"未知產業".to_string())
-                );
+                ));
             }
         }
 
-        panic!("[{MODULE_NAME}] Cannot find company name for stock no: {stock_no}");
+        Err(Error::UnknownStock(stock_no.to_string()))
+    }
+
+    pub fn get_market(&self, stock_no: &str) -> Option<Market> {
+        self.stock_map
+            .iter()
+            .find(|company| company.stock_no == stock_no)
+            .map(|company| company.market)
+    }
+
+    /// 給列表輸出用，找不到公司時顯示 "--"
+    pub fn display_market(&self, stock_no: &str) -> String {
+        self.get_market(stock_no)
+            .map(|market| market.to_string())
+            .unwrap_or_else(|| "--".to_string())
+    }
+
+    /// 給列表輸出用，沒有發行股數時顯示 "--"
+    pub fn display_market_cap(&self, stock_no: &str, close: f64) -> String {
+        self.get_company(stock_no)
+            .and_then(|company| company.market_cap_100m(close))
+            .map(|market_cap| format!("{market_cap:.0}"))
+            .unwrap_or_else(|| "--".to_string())
+    }
+
+    pub fn display_turnover_rate(&self, stock_no: &str, volume: u64) -> String {
+        self.get_company(stock_no)
+            .and_then(|company| company.turnover_rate(volume))
+            .map(|rate| format!("{rate:.2}"))
+            .unwrap_or_else(|| "--".to_string())
+    }
+
+    /// 給列表輸出用，找不到公司時顯示錯誤訊息而不中斷
+    pub fn display_name(&self, stock_no: &str) -> String {
+        self.get_name(stock_no).unwrap_or_else(|e| e.to_string())
     }
 }
 
@@ -98,6 +327,7 @@ fn build_industry_map() -> HashMap<String, String> {
         ("36", "數位雲端"),
         ("37", "運動休閒"),
         ("38", "居家生活"),
+        (ETF_INDUSTRY, "ETF"),
     ];
 
     // 將 Vec 轉換為 HashMap
@@ -107,32 +337,90 @@ fn build_industry_map() -> HashMap<String, String> {
         .collect()
 }
 
-async fn get_company_map() -> Vec<CompanyInfo> {
-    // if the company map JSON file already exists, read from it instead of fetching from the API
-    let mut save_map = false;
-    let mut stock_map = if Path::new(COMPANY_MAP).exists() {
-        let file = File::open(COMPANY_MAP).unwrap();
-        let reader = io::BufReader::new(file);
-        let stock_map: Vec<CompanyInfo> = serde_json::from_reader(reader).unwrap();
-        stock_map
-    } else {
-        save_map = true;
-        twse::company_map::fetch().await.unwrap()
-    };
-
-    // 過濾掉不需要的股票
-    stock_map.retain(|company| {
-        // skip 金融保險業
-        company.industry != "17"
-    });
+fn read_company_map(company_map_path: &str) -> Result<Option<Vec<CompanyInfo>>> {
+    if !Path::new(company_map_path).exists() {
+        return Ok(None);
+    }
+    let reader = io::BufReader::new(File::open(company_map_path)?);
+    Ok(Some(serde_json::from_reader(reader)?))
+}
+
+/// 清單超過 refresh_days 天沒更新 (0 代表不自動更新)，或是舊版只有上市、沒有金融股的清單
+fn needs_refresh(company_map_path: &str, stock_map: &[CompanyInfo], refresh_days: u64) -> bool {
+    let outdated = refresh_days > 0
+        && fs::metadata(company_map_path)
+            .and_then(|metadata| metadata.modified())
+            .ok()
+            .and_then(|modified| modified.elapsed().ok())
+            .is_some_and(|age| age > Duration::from_secs(refresh_days * 24 * 60 * 60));
+    let old_format = !stock_map.iter().any(|c| c.market == Market::Tpex)
+        || !stock_map.iter().any(|c| c.industry == "17")
+        || !stock_map.iter().any(|c| !c.shares_issued.is_empty());
+    outdated || old_format
+}
 
-    stock_map.sort_by(|a, b| a.stock_no.cmp(&b.stock_no));
+/// 下載上市、上櫃公司清單並存檔，跟 old 比對出異動
+///
+/// 某個市場抓不到時沿用 old 裡那個市場的公司；old 裡沒有的市場視為第一次建立，不記錄異動
+async fn refresh_snapshot(
+    urls: &ListingUrls,
+    company_map_path: &str,
+    old: Vec<CompanyInfo>,
+) -> Result<(Vec<CompanyInfo>, Vec<CompanyEvent>)> {
+    println!("[{MODULE_NAME}] 從 TWSE / TPEx 下載公司清單");
+    let mut stock_map = Vec::new();
+    let mut refreshed = Vec::new();
+    let mut last_error = None;
+    for (market, result) in [
+        (Market::Twse, twse::company_map::fetch(&urls.twse).await),
+        (Market::Tpex, tpex::company_map::fetch(&urls.tpex).await),
+    ] {
+        match result {
+            Ok(companies) => {
+                stock_map.extend(companies);
+                refreshed.push(market);
+            }
+            Err(e) => {
+                println!("[{MODULE_NAME}] 無法下載{market}公司清單: {e}");
+                last_error = Some(e);
+            }
+        }
+    }
+    if refreshed.is_empty() {
+        if let Some(e) = last_error
+            && old.is_empty()
+        {
+            return Err(e);
+        }
+        println!("[{MODULE_NAME}] 沿用 {company_map_path}");
+        return Ok((old, Vec::new()));
+    }
 
-    // Save the company map to a JSON file for future use
-    if save_map {
-        let json = serde_json::to_string_pretty(&stock_map).unwrap();
-        std::fs::write(COMPANY_MAP, json).unwrap();
+    for company in &old {
+        if !refreshed.contains(&company.market)
+            && !stock_map.iter().any(|c| c.stock_no == company.stock_no)
+        {
+            stock_map.push(company.clone());
+        }
     }
+    stock_map.sort_by(|a, b| a.stock_no.cmp(&b.stock_no));
 
-    stock_map
+    let old_markets: Vec<Market> = old.iter().map(|c| c.market).collect();
+    let comparable: Vec<CompanyInfo> = stock_map
+        .iter()
+        .filter(|c| old_markets.contains(&c.market))
+        .cloned()
+        .collect();
+    let today = Local::now().format("%Y-%m-%d").to_string();
+    let events = company_history::diff(&old, &comparable, &today);
+
+    let json = serde_json::to_string_pretty(&stock_map)?;
+    std::fs::write(company_map_path, json)?;
+    Ok((stock_map, events))
+}
+
+fn print_events(events: &[CompanyEvent]) {
+    for event in events {
+        println!("[{MODULE_NAME}] {event}");
+    }
 }
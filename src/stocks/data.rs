@@ -1,65 +1,912 @@
-use std::collections::HashMap;
+use std::borrow::Cow;
+use std::collections::{BTreeMap, BTreeSet, HashMap};
+use std::sync::{Arc, Mutex};
 
-use crate::cfg::data::CfgData;
-use crate::stocks::company_map::CompanyMap;
-use crate::stocks::data_company::DataCompany;
+use chrono::{Datelike, Days, Local, NaiveDate};
+
+use crate::calendar::trading::TradingCalendar;
+use crate::cfg::data::{CfgData, PriceView};
+use crate::common;
+use crate::error::{Error, Result};
+use crate::fugle;
+use crate::provider::data_provider::DataProvider;
+use crate::provider::fugle::FugleProvider;
+use crate::provider::pool;
+use crate::provider::pool::FetchJob;
+use crate::provider::twse::TwseProvider;
+use crate::stocks::company_history::CompanyEvent;
+use crate::stocks::company_map::{CompanyInfo, CompanyMap, Market};
+use crate::stocks::data_company::{DataCompany, StockData};
+use crate::stocks::fetch_journal::{FetchJournal, JobStatus};
+use crate::stocks::resample;
+use crate::stocks::suspension::Suspensions;
+use crate::stocks::timeframe::Timeframe;
+use crate::stocks::validator;
+use crate::stocks::validator::StockReport;
+use crate::storage;
+use crate::storage::archive::Archive;
+use crate::storage::store::{RAW_PRICES, StockStore};
+use crate::twse::daily_quotes;
 
 const MODULE_NAME: &str = "stocks::data";
+/// Fugle 分K只提供近期資料，每次抓最近這麼多天
+const INTRADAY_LOOK_BACK_DAYS: u64 = 30;
+/// 年度抓取每完成這麼多家公司寫一次 fetch_journal
+const JOURNAL_SAVE_EVERY: usize = 20;
+
+/// 依時間排序的一整個分K序列，快取與掃描共用
+type IntradayCandles = Arc<Vec<StockData>>;
 
 pub struct Data {
     pub cfg: CfgData,
     pub company_map: CompanyMap,
+    pub calendar: TradingCalendar,
+    pub suspensions: Suspensions,
+    pub store: Box<dyn StockStore>,
+    /// 個股歷史日K，回補用
+    pub fugle: Arc<FugleProvider>,
+    /// 全市場每日收盤行情，每日更新用
+    pub twse: TwseProvider,
+    pub journal: FetchJournal,
     pub data_company: HashMap<String, DataCompany>,
+    /// 掃描時只看這個市場，None 代表上市上櫃都看
+    pub market_filter: Option<Market>,
+    /// 掃描時用的 K 棒週期
+    pub scan_timeframe: Timeframe,
+    /// 儲存裡是舊版的 Fugle 還原價，要全部重抓原始價格才能用
+    pub legacy_prices: bool,
+    /// 讀過的分K序列，掃描時每家公司只從儲存讀一次，合併新的分K後清掉
+    intraday_cache: Mutex<HashMap<(String, Timeframe), IntradayCandles>>,
 }
 
 impl Data {
-    pub async fn new() -> Self {
-        let cfg = CfgData::new();
-        let company_map = CompanyMap::new().await;
+    pub async fn new() -> Result<Self> {
+        let cfg = CfgData::new()?;
+        let company_map = CompanyMap::new(&cfg).await?;
+        let calendar = TradingCalendar::new(&cfg.calendar_path)?;
+        let suspensions = Suspensions::new(&cfg.suspensions_path)?;
+        let store = storage::store::open(&cfg)?;
+        let fugle = Arc::new(FugleProvider::new(&cfg)?);
+        let twse = TwseProvider::new(&cfg)?;
+        let journal = FetchJournal::new(&cfg.fetch_journal_path)?;
         let mut data_company = HashMap::new();
 
-        println!("[{MODULE_NAME}] Reading data for all companies...");
+        println!(
+            "[{MODULE_NAME}] Reading data for all companies from {} storage...",
+            store.name()
+        );
 
         for company in &company_map.stock_map {
-            data_company.insert(
-                company.stock_no.clone(),
-                DataCompany::new(company.stock_no.clone()),
+            // 單一公司的資料壞掉時只略過這家公司
+            let company_data = DataCompany::new(store.as_ref(), company.stock_no.clone())
+                .unwrap_or_else(|e| {
+                    println!("[{MODULE_NAME}] 無法讀取 {} 的資料: {e}", company.stock_no);
+                    DataCompany::empty(company.stock_no.clone())
+                });
+            data_company.insert(company.stock_no.clone(), company_data);
+        }
+
+        // 沒有任何資料時直接當成新格式；有資料卻沒有標記的是舊版的還原價，照樣可以分析
+        let legacy_prices = match store.price_format()? {
+            Some(format) => format != RAW_PRICES,
+            None if data_company.values().all(|c| c.stock_data.is_empty()) => {
+                if !cfg.offline {
+                    store.save_price_format(RAW_PRICES)?;
+                }
+                false
+            }
+            None => true,
+        };
+        if legacy_prices {
+            println!(
+                "[{MODULE_NAME}] {} 儲存的是舊版的 Fugle 還原價，沒有還原因子，原始價格與還原價都以還原價計算",
+                store.name()
             );
+            if cfg.offline {
+                println!(
+                    "[{MODULE_NAME}] 離線模式只讀取現有的還原價，連網後可用選單 27 重抓原始價格"
+                );
+            }
         }
 
-        Data {
+        Ok(Data {
             cfg,
             company_map,
+            calendar,
+            suspensions,
+            store,
+            fugle,
+            twse,
+            journal,
             data_company,
+            market_filter: None,
+            scan_timeframe: Timeframe::Day,
+            legacy_prices,
+            intraday_cache: Mutex::new(HashMap::new()),
+        })
+    }
+
+    /// 某個掃描要看的公司：該掃描的股票池，再套用目前的市場篩選
+    pub fn scan_companies(&self, scan: &str) -> impl Iterator<Item = &CompanyInfo> {
+        let universe = self.cfg.universe_for(scan);
+        self.company_map.stock_map.iter().filter(move |company| {
+            universe.contains(company)
+                && self
+                    .market_filter
+                    .is_none_or(|market| company.market == market)
+        })
+    }
+
+    /// 某個掃描每家公司到 date 為止的 K 棒，找不到公司或 date 沒有資料的印出原因後略過
+    pub fn scan_bars(
+        &self,
+        scan: &str,
+        timeframe: Timeframe,
+        view: PriceView,
+        date: &str,
+    ) -> impl Iterator<Item = (&CompanyInfo, Cow<'_, [StockData]>)> {
+        self.scan_companies(scan).filter_map(move |company| {
+            let stock_no = &company.stock_no;
+            match self.try_bars(stock_no, timeframe, view, date) {
+                Ok(Some(bars)) => Some((company, bars)),
+                Ok(None) => {
+                    println!(
+                        "[{MODULE_NAME}] 無法找到 {stock_no} ({}) 在 {date} 的資料",
+                        self.company_map.display_name(stock_no)
+                    );
+                    None
+                }
+                Err(e) => {
+                    println!("[{MODULE_NAME}] {e}");
+                    None
+                }
+            }
+        })
+    }
+
+    /// 指標掃描用：每家公司以 cfg.indicator_price_view 計算指標的 K 棒，以及 date 那根實際成交價的 K 棒
+    pub fn scan_indicator_bars(
+        &self,
+        scan: &str,
+        timeframe: Timeframe,
+        date: &str,
+    ) -> impl Iterator<Item = (&CompanyInfo, Cow<'_, [StockData]>, StockData)> {
+        let view = self.cfg.indicator_price_view;
+        self.scan_bars(scan, timeframe, view, date)
+            .filter_map(move |(company, bars)| {
+                let last = match view {
+                    PriceView::Raw => bars.last().cloned(),
+                    PriceView::Adjusted => self
+                        .bars(&company.stock_no, timeframe, PriceView::Raw, date)
+                        .and_then(|raw| raw.last().cloned()),
+                }?;
+                Some((company, bars, last))
+            })
+    }
+
+    /// 掃描用的 K 棒序列，最後一根就是 date (YYYYMMDD) 那天的 K 棒，date 沒有資料時回傳 None
+    pub fn bars(
+        &self,
+        stock_no: &str,
+        timeframe: Timeframe,
+        view: PriceView,
+        date: &str,
+    ) -> Option<Cow<'_, [StockData]>> {
+        self.try_bars(stock_no, timeframe, view, date)
+            .ok()
+            .flatten()
+    }
+
+    /// 同 bars，找不到公司或讀不到分K時回傳錯誤
+    ///
+    /// 週K/月K只用 date 以前的日K合併，分K取到當天最後一根；分K沒有還原因子，一律是原始價格
+    pub fn try_bars(
+        &self,
+        stock_no: &str,
+        timeframe: Timeframe,
+        view: PriceView,
+        date: &str,
+    ) -> Result<Option<Cow<'_, [StockData]>>> {
+        let date = common::convert_date_to_fugle_format(date);
+        if timeframe.is_intraday() {
+            let candles = self.cached_intraday(stock_no, timeframe)?;
+            let end = candles.partition_point(|candle| {
+                candle
+                    .date
+                    .get(..10)
+                    .is_some_and(|day| day <= date.as_str())
+            });
+            let found = end > 0 && candles[end - 1].date.starts_with(&date);
+            return Ok(found.then(|| Cow::Owned(candles[..end].to_vec())));
         }
+
+        let data_company = self
+            .data_company
+            .get(stock_no)
+            .ok_or_else(|| Error::UnknownStock(stock_no.to_string()))?;
+        let Some(end) = data_company.stock_data.iter().position(|d| d.date == date) else {
+            return Ok(None);
+        };
+        let daily = match data_company.view(view) {
+            Cow::Borrowed(daily) => Cow::Borrowed(&daily[..=end]),
+            Cow::Owned(mut daily) => {
+                daily.truncate(end + 1);
+                Cow::Owned(daily)
+            }
+        };
+        Ok(Some(match timeframe {
+            Timeframe::Day => daily,
+            _ => Cow::Owned(resample::resample(&daily, timeframe)),
+        }))
+    }
+
+    /// 最近一個交易日所在的年度，重抓整年時的預設年度
+    pub fn current_year(&self) -> String {
+        self.calendar
+            .latest_trading_day(Local::now().date_naive())
+            .year()
+            .to_string()
     }
 
+    /// 某個年度抓得到資料的最後一天 (YYYY-MM-DD)，今年是最近的交易日
+    fn year_until(&self, year: &str) -> String {
+        let latest = self
+            .calendar
+            .latest_trading_day(Local::now().date_naive())
+            .format("%Y-%m-%d")
+            .to_string();
+        latest.min(format!("{year}-12-31"))
+    }
+
+    /// 依股票代號順序抓整個年度，上次中斷的話從 fetch_journal 記錄的進度繼續
     pub async fn fetch_year(&mut self, year: &str) {
         println!("[{MODULE_NAME}] Fetching data for all companies for year: {year}...");
-        let mut index = 1;
-        let total = self.data_company.len();
-        for data_company in self.data_company.values_mut() {
+        // 今年只抓得到最近的交易日，上一輪抓到的日期比較早時不沿用
+        let until = self.year_until(year);
+        let resumed = self.journal.begin_year(year, &until);
+
+        let mut stock_nos: Vec<String> = self
+            .company_map
+            .stock_map
+            .iter()
+            .map(|company| company.stock_no.clone())
+            .collect();
+        stock_nos.sort();
+        let mut settled = 0;
+        let mut jobs = Vec::new();
+        for stock_no in stock_nos {
+            if self.journal.is_settled(&stock_no, year) {
+                settled += 1;
+                continue;
+            }
+            match self.year_job(&stock_no, year) {
+                Some(job) => jobs.push(job),
+                None => self
+                    .journal
+                    .record(&stock_no, year, JobStatus::Skipped, None),
+            }
+        }
+        if resumed {
             println!(
-                "[{MODULE_NAME}] [{index}/{total}] Fetching and writing data year({year}) for stock: {} ({})...",
-                data_company.stock_no,
-                self.company_map.get_name(&data_company.stock_no)
+                "[{MODULE_NAME}] 接續上次中斷的 {year} 年抓取: {settled} 家已完成，剩下 {} 家",
+                jobs.len()
             );
-            data_company.fetch_year(&self.cfg, year).await;
+        }
+        self.save_journal();
+
+        self.fetch_and_save_years(jobs).await;
+        self.journal.finish_year(year);
+        self.save_journal();
+        self.print_year_summary(year);
+    }
 
-            // sleep to avoid hitting API rate limits
-            tokio::time::sleep(std::time::Duration::from_millis(950)).await;
-            index += 1;
+    /// 舊版存的是 Fugle 的還原價，而且沒有還原因子，year_from 到今年逐年重抓成原始價格
+    ///
+    /// 每個 (股票, 年度) 的結果都記在 fetch_journal，中斷後再執行會跳過已經抓完的年度與公司
+    pub async fn rebuild_raw_prices(&mut self) {
+        let first = self.cfg.year_from;
+        let last = self
+            .current_year()
+            .parse()
+            .unwrap_or(*self.cfg.years().end());
+        println!("[{MODULE_NAME}] 重抓 {first} ~ {last} 年全部股票的原始價格...");
+        for year in first..=last {
+            let year = year.to_string();
+            if self.journal.is_complete(&year, &self.year_until(&year)) {
+                println!("[{MODULE_NAME}] {year} 年已經抓過原始價格，略過");
+                continue;
+            }
+            self.fetch_year(&year).await;
         }
+        self.finish_raw_rebuild();
     }
 
-    pub async fn fetch_company_year(&mut self, stock_no: &str, year: &str) {
-        if let Some(data_company) = self.data_company.get_mut(stock_no) {
-            data_company.fetch_year(&self.cfg, year).await;
-        } else {
-            panic!("[{MODULE_NAME}] 找不到股票代號: {stock_no}",);
+    /// 全部重抓都成功之後才標記成原始價格，還有失敗的要先重試
+    fn finish_raw_rebuild(&mut self) {
+        if !self.legacy_prices {
+            return;
+        }
+        let failures = self.journal.failures().len();
+        if failures > 0 {
+            println!(
+                "[{MODULE_NAME}] 還有 {failures} 個年度抓取失敗，重試成功之前仍有部分舊版還原價"
+            );
+            return;
+        }
+        match self.store.save_price_format(RAW_PRICES) {
+            Ok(()) => {
+                self.legacy_prices = false;
+                println!("[{MODULE_NAME}] 已全部換成原始價格");
+            }
+            Err(e) => println!("[{MODULE_NAME}] 無法寫入價格格式: {e}"),
         }
     }
 
+    /// 重抓 fetch_journal 裡所有失敗的 (股票代號, 年度)
+    pub async fn retry_failed_years(&mut self) {
+        let targets = self.journal.failures();
+        if targets.is_empty() {
+            println!("[{MODULE_NAME}] 沒有失敗的年度抓取");
+            return;
+        }
+
+        self.refetch_years(&targets).await;
+        let mut years: Vec<String> = targets.iter().map(|(_, year)| year.to_string()).collect();
+        years.dedup();
+        for year in years {
+            self.print_year_summary(&year);
+        }
+        self.finish_raw_rebuild();
+    }
+
+    fn print_year_summary(&self, year: &str) {
+        let Some(run) = self.journal.years.get(year) else {
+            return;
+        };
+        println!(
+            "[{MODULE_NAME}] {year} 年: {} 家完成，{} 家失敗，{} 家略過 (尚未上市櫃)",
+            run.count(JobStatus::Done),
+            run.count(JobStatus::Failed),
+            run.count(JobStatus::Skipped)
+        );
+        for (stock_no, job) in &run.jobs {
+            if job.status == JobStatus::Failed {
+                println!(
+                    "    {stock_no} ({}) {}",
+                    self.company_map.display_name(stock_no),
+                    job.error.as_deref().unwrap_or_default()
+                );
+            }
+        }
+        if run.count(JobStatus::Failed) > 0 {
+            println!("[{MODULE_NAME}] 失敗的公司可以用選單 20 一次重試");
+        }
+    }
+
+    fn save_journal(&self) {
+        if let Err(e) = self.journal.save() {
+            println!(
+                "[{MODULE_NAME}] 無法寫入 {}: {e}",
+                self.cfg.fetch_journal_path
+            );
+        }
+    }
+
+    /// 重抓指定的 (股票代號, 年度)，整年覆蓋
+    pub async fn refetch_years(&mut self, targets: &[(String, i32)]) {
+        println!(
+            "[{MODULE_NAME}] Refetching {} stock years...",
+            targets.len()
+        );
+        let jobs: Vec<FetchJob> = targets
+            .iter()
+            .filter_map(|(stock_no, year)| self.year_job(stock_no, &year.to_string()))
+            .collect();
+
+        self.fetch_and_save_years(jobs).await;
+    }
+
+    /// 某家公司一整年的抓取工作，上市 (櫃) 之前不會有資料，從上市日開始抓；
+    /// 整年都在上市之前就不用抓
+    fn year_job(&self, stock_no: &str, year: &str) -> Option<FetchJob> {
+        let from = self.expected_from(stock_no, format!("{year}-01-01"));
+        let to = format!("{year}-12-31");
+        (from <= to).then(|| FetchJob {
+            stock_no: stock_no.to_string(),
+            from,
+            to,
+            timeframe: Timeframe::Day,
+        })
+    }
+
+    /// from 跟上市 (櫃) 日期取比較晚的那一天，已經存有上市日之前的資料時改用第一筆資料的日期
+    fn expected_from(&self, stock_no: &str, from: String) -> String {
+        let first_stored = self
+            .data_company
+            .get(stock_no)
+            .and_then(|data_company| data_company.stock_data.first())
+            .map(|bar| bar.date.as_str());
+        listing_start(from, self.company_map.listing_date(stock_no), first_stored)
+    }
+
+    /// 依資料完整性檢查每家公司，只回傳有問題的公司
+    pub fn validate(&self) -> Vec<StockReport> {
+        let until = self.calendar.latest_trading_day(Local::now().date_naive());
+        self.company_map
+            .stock_map
+            .iter()
+            .filter_map(|company| self.data_company.get(&company.stock_no))
+            .map(|data_company| {
+                validator::validate(
+                    data_company,
+                    &self.calendar,
+                    self.suspensions.periods(&data_company.stock_no),
+                    self.cfg.year_from,
+                    until,
+                )
+            })
+            .filter(|report| !report.issues.is_empty())
+            .collect()
+    }
+
+    // 每個 job 都是一整個年度，抓回來後覆蓋該年度檔，每個結果都記進 fetch_journal
+    async fn fetch_and_save_years(&mut self, jobs: Vec<FetchJob>) {
+        let total = jobs.len();
+        let mut index = 0;
+        pool::fetch_all(
+            &self.fugle,
+            self.cfg.fugle_concurrency,
+            jobs,
+            |job, result| {
+                index += 1;
+                let Some(data_company) = self.data_company.get_mut(&job.stock_no) else {
+                    return;
+                };
+                let year = &job.from[0..4];
+                let result = result.and_then(|yearly_data| {
+                    data_company.save_year(self.store.as_ref(), year, yearly_data)
+                });
+                match result {
+                    Ok(()) => {
+                        println!(
+                            "[{MODULE_NAME}] [{index}/{total}] Wrote data year({year}) for stock: {} ({})",
+                            job.stock_no,
+                            self.company_map.display_name(&job.stock_no)
+                        );
+                        self.journal
+                            .record(&job.stock_no, year, JobStatus::Done, None);
+                    }
+                    Err(e) => {
+                        println!(
+                            "[{MODULE_NAME}] [{index}/{total}] {} 抓取失敗: {e}",
+                            job.stock_no
+                        );
+                        self.journal.record(
+                            &job.stock_no,
+                            year,
+                            JobStatus::Failed,
+                            Some(e.to_string()),
+                        );
+                    }
+                }
+                // 每次都寫整份紀錄太慢，一批寫一次，中斷時最多重抓一批
+                if index % JOURNAL_SAVE_EVERY == 0
+                    && let Err(e) = self.journal.save()
+                {
+                    println!("[{MODULE_NAME}] 無法寫入 {}: {e}", self.cfg.fetch_journal_path);
+                }
+            },
+        )
+        .await;
+        self.save_journal();
+    }
+
+    /// 每家公司只補抓最後一筆資料之後缺少的日期
+    ///
+    /// 缺的交易日不多時用 TWSE 全市場日報，一天一個請求就能更新所有上市公司；
+    /// 上櫃公司、缺太多天或日報抓不到的公司再用 Fugle 逐檔回補
+    pub async fn fetch_catch_up(&mut self) {
+        // 抓到今天或之前最近的交易日為止，假日執行不會送出多餘的請求
+        let until = self.calendar.latest_trading_day(Local::now().date_naive());
+        let today = until.format("%Y-%m-%d").to_string();
+        println!("[{MODULE_NAME}] Catching up data for all companies until {today}...");
+
+        // 已經是最新的公司不送請求
+        let mut missing = Vec::new();
+        for company in &self.company_map.stock_map {
+            let Some(data_company) = self.data_company.get(&company.stock_no) else {
+                continue;
+            };
+            let from = self.expected_from(
+                &company.stock_no,
+                data_company.catch_up_from(self.cfg.year_from),
+            );
+            let Ok(from_date) = NaiveDate::parse_from_str(&from, "%Y-%m-%d") else {
+                continue;
+            };
+            let days = self.calendar.trading_days_between(from_date, until);
+            if !days.is_empty() {
+                missing.push((company.stock_no.clone(), company.market, from, days));
+            }
+        }
+        let (daily, backfill): (Vec<_>, Vec<_>) =
+            missing.into_iter().partition(|(_, market, _, days)| {
+                *market == Market::Twse && days.len() <= self.cfg.twse_daily_max_days
+            });
+
+        let market_days: BTreeSet<NaiveDate> = daily
+            .iter()
+            .flat_map(|(_, _, _, days)| days.iter().copied())
+            .collect();
+        let mut market_data: HashMap<String, Vec<StockData>> = HashMap::new();
+        let mut failed_days = BTreeSet::new();
+        for day in market_days {
+            match self.twse.fetch_market_day(day).await {
+                Ok(results) => {
+                    println!(
+                        "[{MODULE_NAME}] {} {day} 全市場日報 {} 筆",
+                        self.twse.name(),
+                        results.len()
+                    );
+                    for result in results {
+                        market_data
+                            .entry(result.stock_no)
+                            .or_default()
+                            .push(result.stock_data);
+                    }
+                }
+                Err(e) => {
+                    println!("[{MODULE_NAME}] {e}，改用 {} 回補", self.fugle.name());
+                    failed_days.insert(day);
+                }
+            }
+        }
+
+        let mut updated_companies = 0;
+        let mut new_bars = 0;
+        let mut jobs: Vec<FetchJob> = Vec::new();
+        for (stock_no, _, from, days) in daily {
+            if days.iter().any(|day| failed_days.contains(day)) {
+                jobs.extend(yearly_jobs(&stock_no, &from, &today));
+                continue;
+            }
+            let Some(data_company) = self.data_company.get_mut(&stock_no) else {
+                continue;
+            };
+            // 日報裡沒有的公司代表這幾天都沒有成交
+            let mut new_data = market_data.remove(&stock_no).unwrap_or_default();
+            new_data.retain(|d| d.date >= from);
+            // 不比價 (除權息等) 的日子日報沒有漲跌，算不出還原因子，改用 Fugle 回補
+            if new_data.iter().any(|d| d.change.is_none()) {
+                jobs.extend(yearly_jobs(&stock_no, &from, &today));
+                continue;
+            }
+            match data_company.merge_new_data(self.store.as_ref(), &new_data) {
+                Ok(count) if count > 0 => {
+                    updated_companies += 1;
+                    new_bars += count;
+                }
+                Ok(_) => {}
+                Err(e) => println!("[{MODULE_NAME}] {stock_no} 寫入失敗: {e}"),
+            }
+        }
+        // 沒有資料的公司從 year_from 開始補，依年度拆開，一個請求不會跨好幾年
+        jobs.extend(
+            backfill
+                .into_iter()
+                .flat_map(|(stock_no, _, from, _)| yearly_jobs(&stock_no, &from, &today)),
+        );
+        // 一家公司可能拆成好幾個年度的工作
+        let backfill_companies: BTreeSet<&str> =
+            jobs.iter().map(|job| job.stock_no.as_str()).collect();
+        println!(
+            "[{MODULE_NAME}] 全市場日報更新 {updated_companies} 家公司，{} 家 ({} 筆年度) 改用 {} 回補",
+            backfill_companies.len(),
+            jobs.len(),
+            self.fugle.name()
+        );
+
+        let total = jobs.len();
+        let mut index = 0;
+        let mut backfilled = BTreeSet::new();
+        pool::fetch_all(
+            &self.fugle,
+            self.cfg.fugle_concurrency,
+            jobs,
+            |job, result| {
+                index += 1;
+                let Some(data_company) = self.data_company.get_mut(&job.stock_no) else {
+                    return;
+                };
+                let result = result.and_then(|new_data| {
+                    data_company.merge_new_data(self.store.as_ref(), &new_data)
+                });
+                match result {
+                    Ok(count) => {
+                        println!(
+                            "[{MODULE_NAME}] [{index}/{total}] {} ({}) 從 {}，新增 {count} 筆",
+                            job.stock_no,
+                            self.company_map.display_name(&job.stock_no),
+                            job.from,
+                        );
+                        if count > 0 {
+                            backfilled.insert(job.stock_no.clone());
+                            new_bars += count;
+                        }
+                    }
+                    Err(e) => println!(
+                        "[{MODULE_NAME}] [{index}/{total}] {} 抓取失敗: {e}",
+                        job.stock_no
+                    ),
+                }
+            },
+        )
+        .await;
+
+        println!(
+            "[{MODULE_NAME}] 補抓完成: {} 家公司更新，共新增 {new_bars} 筆資料",
+            updated_companies + backfilled.len()
+        );
+    }
+
+    pub async fn fetch_company_year(&mut self, stock_no: &str, year: &str) -> Result<()> {
+        let job = self.year_job(stock_no, year).ok_or_else(|| {
+            Error::Api(format!("{stock_no} 在 {year} 年還沒有上市 (櫃)，沒有資料"))
+        })?;
+        let data_company = self
+            .data_company
+            .get_mut(stock_no)
+            .ok_or_else(|| Error::UnknownStock(stock_no.to_string()))?;
+        let yearly_data = self
+            .fugle
+            .fetch_range(&job.stock_no, &job.from, &job.to)
+            .await?;
+        data_company.save_year(self.store.as_ref(), year, yearly_data)
+    }
+
+    /// 抓最近的分K並合併進該週期的序列，回傳合併後的總筆數
+    pub async fn fetch_intraday(&self, stock_no: &str, timeframe: Timeframe) -> Result<usize> {
+        let job = self.intraday_job(stock_no, timeframe);
+        let candles = self
+            .fugle
+            .fetch_intraday(stock_no, timeframe, &job.from, &job.to)
+            .await?;
+        println!(
+            "[{MODULE_NAME}] {stock_no} {timeframe} {} ~ {} 抓到 {} 根",
+            job.from,
+            job.to,
+            candles.len()
+        );
+        self.merge_intraday(stock_no, timeframe, &candles)
+    }
+
+    /// 目前掃描市場的每家公司都抓最近的分K，分K掃描前先跑一次
+    pub async fn fetch_intraday_all(&self, timeframe: Timeframe) {
+        let jobs: Vec<FetchJob> = self
+            .company_map
+            .stock_map
+            .iter()
+            .filter(|company| {
+                self.market_filter
+                    .is_none_or(|market| company.market == market)
+            })
+            .map(|company| self.intraday_job(&company.stock_no, timeframe))
+            .collect();
+        let total = jobs.len();
+        println!("[{MODULE_NAME}] 抓取 {total} 家公司的{timeframe}...");
+
+        let mut index = 0;
+        pool::fetch_all(
+            &self.fugle,
+            self.cfg.fugle_concurrency,
+            jobs,
+            |job, result| {
+                index += 1;
+                let result = result.and_then(|candles| {
+                    self.merge_intraday(&job.stock_no, job.timeframe, &candles)
+                });
+                match result {
+                    Ok(merged) => println!(
+                        "[{MODULE_NAME}] [{index}/{total}] {} {timeframe} 共 {merged} 根",
+                        job.stock_no
+                    ),
+                    Err(e) => println!(
+                        "[{MODULE_NAME}] [{index}/{total}] {} 抓取失敗: {e}",
+                        job.stock_no
+                    ),
+                }
+            },
+        )
+        .await;
+    }
+
+    /// Fugle 分K只提供近期資料，抓最近 INTRADAY_LOOK_BACK_DAYS 天到今天
+    fn intraday_job(&self, stock_no: &str, timeframe: Timeframe) -> FetchJob {
+        let today = Local::now().date_naive();
+        let from = (today - Days::new(INTRADAY_LOOK_BACK_DAYS))
+            .format("%Y-%m-%d")
+            .to_string();
+        FetchJob {
+            stock_no: stock_no.to_string(),
+            from: self.expected_from(stock_no, from),
+            to: today.format("%Y-%m-%d").to_string(),
+            timeframe,
+        }
+    }
+
+    fn cached_intraday(&self, stock_no: &str, timeframe: Timeframe) -> Result<IntradayCandles> {
+        let key = (stock_no.to_string(), timeframe);
+        if let Some(candles) = self.intraday_cache.lock().unwrap().get(&key) {
+            return Ok(Arc::clone(candles));
+        }
+        let candles = Arc::new(self.read_intraday(stock_no, timeframe)?);
+        self.intraday_cache
+            .lock()
+            .unwrap()
+            .insert(key, Arc::clone(&candles));
+        Ok(candles)
+    }
+
+    fn merge_intraday(
+        &self,
+        stock_no: &str,
+        timeframe: Timeframe,
+        candles: &[StockData],
+    ) -> Result<usize> {
+        self.intraday_cache
+            .lock()
+            .unwrap()
+            .remove(&(stock_no.to_string(), timeframe));
+        self.store.merge_intraday(stock_no, timeframe, candles)
+    }
+
+    /// 已存檔的分K序列，依時間排序
+    pub fn read_intraday(&self, stock_no: &str, timeframe: Timeframe) -> Result<Vec<StockData>> {
+        self.store.read_intraday(stock_no, timeframe)
+    }
+
+    /// 不連網，把 archive_dir 裡封存的原始回應重新解析後寫進儲存，同一天的 K 棒以較晚抓到的為準
+    pub fn replay_archive(&mut self) {
+        let archive = Archive::new(&self.cfg.archive_dir);
+        let mut paths: Vec<_> = [fugle::stocks::ARCHIVE_SOURCE, daily_quotes::ARCHIVE_SOURCE]
+            .into_iter()
+            .flat_map(|source| {
+                archive
+                    .list(source)
+                    .into_iter()
+                    .map(move |path| (source, path))
+            })
+            .collect();
+        // 目錄是抓取日期、檔名是抓取時間，兩個來源合在一起依抓取時間排序
+        paths.sort_by_key(|(_, path)| {
+            let day = path
+                .parent()
+                .and_then(|dir| dir.file_name())
+                .map(|name| name.to_owned());
+            (day, path.file_name().map(|name| name.to_owned()))
+        });
+        println!(
+            "[{MODULE_NAME}] 從 {} 重播 {} 個封存的回應...",
+            self.cfg.archive_dir,
+            paths.len()
+        );
+
+        let mut daily: HashMap<String, BTreeMap<String, StockData>> = HashMap::new();
+        let mut intraday: HashMap<(String, Timeframe), BTreeMap<String, StockData>> =
+            HashMap::new();
+        let mut skipped = 0;
+        let mut failed = 0;
+        for (source, path) in &paths {
+            let response = match Archive::load(path) {
+                Ok(response) => response,
+                Err(e) => {
+                    println!("[{MODULE_NAME}] 無法讀取 {}: {e}", path.display());
+                    failed += 1;
+                    continue;
+                }
+            };
+            // 429 或其他錯誤的回應只是留著查問題用
+            if response.status != 200 {
+                skipped += 1;
+                continue;
+            }
+
+            let parsed = if *source == daily_quotes::ARCHIVE_SOURCE {
+                daily_quotes::parse_url(&response.url)
+                    .ok_or_else(|| Error::Api("網址裡沒有日期".to_string()))
+                    .and_then(|date| daily_quotes::parse(&response.body, date))
+                    .map(|results| {
+                        for result in results {
+                            insert_daily(
+                                daily.entry(result.stock_no).or_default(),
+                                result.stock_data,
+                            );
+                        }
+                    })
+            } else {
+                fugle::stocks::parse_url(&response.url)
+                    .ok_or_else(|| Error::Api("網址裡沒有股票代號".to_string()))
+                    .and_then(|(stock_no, timeframe)| {
+                        let candles = fugle::stocks::parse(&response.body, timeframe)?;
+                        match timeframe {
+                            Timeframe::Day => {
+                                let bars = daily.entry(stock_no).or_default();
+                                for candle in candles {
+                                    insert_daily(bars, candle);
+                                }
+                            }
+                            // 週K、月K由日K合併，不會單獨存檔
+                            Timeframe::Week | Timeframe::Month => {}
+                            _ => {
+                                let bars = intraday.entry((stock_no, timeframe)).or_default();
+                                for candle in candles {
+                                    bars.insert(candle.date.clone(), candle);
+                                }
+                            }
+                        }
+                        Ok(())
+                    })
+            };
+            if let Err(e) = parsed {
+                println!("[{MODULE_NAME}] 無法解析 {}: {e}", response.url);
+                failed += 1;
+            }
+        }
+
+        let mut updated_companies = 0;
+        let mut replayed_bars = 0;
+        for (stock_no, bars) in daily {
+            // 公司清單裡沒有的 (權證、已下市等) 不存
+            let Some(data_company) = self.data_company.get_mut(&stock_no) else {
+                continue;
+            };
+            // 已存的日K有漲跌時，重播的不比價日K不蓋掉它
+            let bars: Vec<StockData> = bars
+                .into_values()
+                .filter(|bar| {
+                    let stored = &data_company.stock_data;
+                    bar.change.is_some()
+                        || stored
+                            .binary_search_by(|stored| stored.date.cmp(&bar.date))
+                            .map_or(true, |index| stored[index].change.is_none())
+                })
+                .collect();
+            match data_company.merge_new_data(self.store.as_ref(), &bars) {
+                Ok(count) if count > 0 => {
+                    updated_companies += 1;
+                    replayed_bars += count;
+                }
+                Ok(_) => {}
+                Err(e) => println!("[{MODULE_NAME}] {stock_no} 寫入失敗: {e}"),
+            }
+        }
+        for ((stock_no, timeframe), bars) in intraday {
+            let bars: Vec<StockData> = bars.into_values().collect();
+            match self.merge_intraday(&stock_no, timeframe, &bars) {
+                Ok(_) => replayed_bars += bars.len(),
+                Err(e) => println!("[{MODULE_NAME}] {stock_no} {timeframe} 寫入失敗: {e}"),
+            }
+        }
+
+        println!(
+            "[{MODULE_NAME}] 重播完成: {updated_companies} 家公司日K、共 {replayed_bars} 根 K 棒，略過 {skipped} 個非 200 回應，{failed} 個失敗"
+        );
+    }
+
+    /// 重新下載公司清單，新掛牌的公司一併讀入資料
+    pub async fn refresh_company_map(&mut self) -> Result<Vec<CompanyEvent>> {
+        let events = self.company_map.refresh().await?;
+        for company in &self.company_map.stock_map {
+            if !self.data_company.contains_key(&company.stock_no) {
+                let data_company = DataCompany::new(self.store.as_ref(), company.stock_no.clone())
+                    .unwrap_or_else(|_| DataCompany::empty(company.stock_no.clone()));
+                self.data_company
+                    .insert(company.stock_no.clone(), data_company);
+            }
+        }
+        Ok(events)
+    }
+
     // pub fn print(&self) {
     //     self.cfg.print();
     //     self.company_map.print();
@@ -68,3 +915,98 @@ impl Data {
     //     }
     // }
 }
+
+/// 同一天的日K以較晚抓到的為準，但 TWSE 不比價 (沒有漲跌) 的那筆不蓋掉其他來源有漲跌的那筆
+fn insert_daily(bars: &mut BTreeMap<String, StockData>, bar: StockData) {
+    if bar.change.is_none() && bars.get(&bar.date).is_some_and(|old| old.change.is_some()) {
+        return;
+    }
+    bars.insert(bar.date.clone(), bar);
+}
+
+/// 上櫃轉上市、而且異動紀錄沒看過上櫃那段的公司，清單上只有上市日期，
+/// 之前的資料不能因此不抓，上市日與第一筆已存資料取比較早的當作起點
+fn listing_start(from: String, listed: Option<String>, first_stored: Option<&str>) -> String {
+    let Some(listed) = listed else {
+        return from;
+    };
+    let start = match first_stored {
+        Some(first) if first < listed.as_str() => first.to_string(),
+        _ => listed,
+    };
+    from.max(start)
+}
+
+/// from ~ to (YYYY-MM-DD) 依年度拆成多個抓取工作
+fn yearly_jobs(stock_no: &str, from: &str, to: &str) -> Vec<FetchJob> {
+    let year = |date: &str| date.get(..4).and_then(|year| year.parse::<i32>().ok());
+    let (Some(first), Some(last)) = (year(from), year(to)) else {
+        return Vec::new();
+    };
+    (first..=last)
+        .map(|year| FetchJob {
+            stock_no: stock_no.to_string(),
+            from: from.max(&format!("{year}-01-01")).to_string(),
+            to: to.min(&format!("{year}-12-31")).to_string(),
+            timeframe: Timeframe::Day,
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn bar(close: f64, change: Option<f64>) -> StockData {
+        StockData {
+            date: "2026-02-11".to_string(),
+            open: close,
+            high: close,
+            low: close,
+            close,
+            volume: 1000,
+            turnover: 0,
+            change,
+        }
+    }
+
+    #[test]
+    fn not_comparable_rows_keep_the_real_change() {
+        let mut bars = BTreeMap::new();
+        insert_daily(&mut bars, bar(100.0, Some(-20.0)));
+        insert_daily(&mut bars, bar(100.0, None));
+        assert_eq!(bars["2026-02-11"].change, Some(-20.0));
+
+        // 較晚抓到、有漲跌的照樣覆蓋
+        insert_daily(&mut bars, bar(101.0, Some(-19.0)));
+        assert_eq!(bars["2026-02-11"].close, 101.0);
+
+        let mut bars = BTreeMap::new();
+        insert_daily(&mut bars, bar(100.0, None));
+        insert_daily(&mut bars, bar(100.0, Some(-20.0)));
+        assert_eq!(bars["2026-02-11"].change, Some(-20.0));
+    }
+
+    #[test]
+    fn transferred_companies_keep_their_earlier_bars() {
+        let from = |year: &str, listed: &str, first_stored: Option<&str>| {
+            listing_start(
+                format!("{year}-01-01"),
+                Some(listed.to_string()),
+                first_stored,
+            )
+        };
+
+        // 2018 年上櫃、2022 年轉上市，清單上只剩上市日期
+        assert_eq!(from("2020", "2022-09-01", Some("2018-03-05")), "2020-01-01");
+        assert_eq!(from("2018", "2022-09-01", Some("2018-03-05")), "2018-03-05");
+        assert!(from("2017", "2022-09-01", Some("2018-03-05")).as_str() > "2017-12-31");
+        // 沒有上市日之前的資料時照舊從上市日開始
+        assert_eq!(from("2020", "2022-09-01", None), "2022-09-01");
+        assert_eq!(from("2022", "2022-09-01", Some("2022-09-01")), "2022-09-01");
+        assert_eq!(
+            listing_start("2020-01-01".to_string(), None, None),
+            "2020-01-01"
+        );
+    }
+}
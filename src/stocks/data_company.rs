@@ -1,19 +1,29 @@
+use std::borrow::Cow;
+
+use chrono::{Days, NaiveDate};
 use serde::{Deserialize, Serialize};
 
-use crate::cfg::data::CfgData;
-use crate::fugle::stocks::fetch as stocks_fetch;
-use crate::storage;
+use crate::cfg::data::PriceView;
+use crate::error::Result;
+use crate::stocks::adjustment;
+use crate::stocks::adjustment::Adjustment;
+use crate::storage::store::StockStore;
 
 #[derive(Debug, Serialize, Deserialize, Clone)]
 pub struct StockData {
+    /// 日K是 YYYY-MM-DD，分K是 K 棒的時間 YYYY-MM-DDTHH:MM:SS+08:00
     pub date: String,
     pub open: f64,
     pub high: f64,
     pub low: f64,
     pub close: f64,
     pub volume: u64,
+    /// 分K沒有成交金額與漲跌
+    #[serde(default)]
     pub turnover: u64,
-    pub change: f64,
+    /// 分K、以及 TWSE 標「X」不比價 (除權息、減資、新上市等) 的日子沒有漲跌
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub change: Option<f64>,
 }
 
 #[derive(Debug, Serialize, Deserialize, Clone)]
@@ -40,31 +50,113 @@ impl StockData {
 
 pub struct DataCompany {
     pub stock_no: String,
+    /// 實際成交的原始價格，舊版存的是還原價，重抓之前原始與還原都是它 (Data::legacy_prices)
     pub stock_data: Vec<StockData>,
+    /// 依日期排序的還原因子表
+    pub adjustments: Vec<Adjustment>,
 }
 
 impl DataCompany {
-    pub fn new(stock_no: String) -> Self {
-        let mut stock_data = storage::stocks::read(&stock_no);
-        stock_data.sort_by(|a, b| a.date.cmp(&b.date));
+    pub fn new(store: &dyn StockStore, stock_no: String) -> Result<Self> {
+        let mut data_company = DataCompany::empty(stock_no);
+        data_company.read(store)?;
+        Ok(data_company)
+    }
 
+    /// 讀不到資料時用的空白公司資料，分析時會被當成資料不足而略過
+    pub fn empty(stock_no: String) -> Self {
         DataCompany {
             stock_no,
-            stock_data,
+            stock_data: Vec::new(),
+            adjustments: Vec::new(),
         }
     }
 
-    pub fn read(&mut self) {
-        self.stock_data = storage::stocks::read(&self.stock_no);
+    pub fn read(&mut self, store: &dyn StockStore) -> Result<()> {
+        self.stock_data = store.read(&self.stock_no)?;
         self.stock_data.sort_by(|a, b| a.date.cmp(&b.date));
+        self.adjustments = store.read_adjustments(&self.stock_no)?;
+        self.adjustments.sort_by(|a, b| a.date.cmp(&b.date));
+        Ok(())
     }
 
-    pub async fn fetch_year(&mut self, cfg: &CfgData, year: &str) {
-        self.stock_data = stocks_fetch(cfg, &self.stock_no, year).await.unwrap();
-        self.stock_data.sort_by(|a, b| a.date.cmp(&b.date));
-        storage::stocks::save(&self.stock_no, year, &self.stock_data);
+    /// 依需要回傳原始或還原後的日K，沒有除權息時不會複製資料
+    pub fn view(&self, view: PriceView) -> Cow<'_, [StockData]> {
+        if view == PriceView::Raw || self.adjustments.is_empty() {
+            Cow::Borrowed(&self.stock_data)
+        } else {
+            Cow::Owned(adjustment::apply(&self.stock_data, &self.adjustments))
+        }
+    }
+
+    /// 重新計算 from ~ to 之間的還原因子，其他日期的因子保持不變
+    fn update_adjustments(&mut self, store: &dyn StockStore, from: &str, to: &str) -> Result<()> {
+        let derived = adjustment::derive(&self.stock_data, from, to);
+        let mut adjustments = self.adjustments.clone();
+        adjustments.retain(|a| !(from..=to).contains(&a.date.as_str()));
+        adjustments.extend(derived);
+        adjustments.sort_by(|a, b| a.date.cmp(&b.date));
+
+        if adjustments != self.adjustments {
+            store.save_adjustments(&self.stock_no, &adjustments)?;
+            self.adjustments = adjustments;
+        }
+        Ok(())
+    }
+
+    /// 以抓回來的資料覆蓋整個年度
+    pub fn save_year(
+        &mut self,
+        store: &dyn StockStore,
+        year: &str,
+        mut yearly_data: Vec<StockData>,
+    ) -> Result<()> {
+        yearly_data.sort_by(|a, b| a.date.cmp(&b.date));
+        store.save(&self.stock_no, year, &yearly_data)?;
+
+        self.read(store)?;
+        self.update_adjustments(store, &format!("{year}-01-01"), &format!("{year}-12-31"))
+    }
 
-        self.read();
+    /// 補抓的起始日期：最後一筆資料的隔天，沒有資料就從 year_from 開始
+    pub fn catch_up_from(&self, year_from: i32) -> String {
+        self.last_date()
+            .and_then(|last_date| NaiveDate::parse_from_str(last_date, "%Y-%m-%d").ok())
+            .map(|last_date| (last_date + Days::new(1)).format("%Y-%m-%d").to_string())
+            .unwrap_or_else(|| format!("{year_from}-01-01"))
+    }
+
+    /// 把補抓到的新資料依年份合併進對應的年度檔，回傳新增的筆數
+    pub fn merge_new_data(
+        &mut self,
+        store: &dyn StockStore,
+        new_data: &[StockData],
+    ) -> Result<usize> {
+        if new_data.is_empty() {
+            return Ok(0);
+        }
+
+        let mut years: Vec<&str> = new_data.iter().map(|d| &d.date[0..4]).collect();
+        years.sort();
+        years.dedup();
+        for year in years {
+            let yearly_data: Vec<StockData> = new_data
+                .iter()
+                .filter(|d| d.date.starts_with(year))
+                .cloned()
+                .collect();
+            store.merge(&self.stock_no, year, &yearly_data)?;
+        }
+
+        self.read(store)?;
+        if let (Some(first), Some(last)) = (new_data.first(), new_data.last()) {
+            self.update_adjustments(store, &first.date, &last.date)?;
+        }
+        Ok(new_data.len())
+    }
+
+    pub fn last_date(&self) -> Option<&str> {
+        self.stock_data.last().map(|d| d.date.as_str())
     }
 
     // pub fn print(&self) {
@@ -73,8 +165,4 @@ impl DataCompany {
     //         data.print();
     //     }
     // }
-
-    pub fn get_stock_data_by_date(&self, date: &str) -> Option<&StockData> {
-        self.stock_data.iter().find(|d| d.date == date)
-    }
 }
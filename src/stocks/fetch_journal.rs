@@ -0,0 +1,206 @@
+use std::collections::BTreeMap;
+use std::fs::File;
+use std::io;
+use std::path::Path;
+
+use chrono::Local;
+use serde::{Deserialize, Serialize};
+
+use crate::error::Result;
+
+#[derive(Debug, Serialize, Deserialize, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum JobStatus {
+    Done,
+    Failed,
+    /// 那一年還沒上市 (櫃)，不用抓
+    Skipped,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct JobRecord {
+    pub status: JobStatus,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub error: Option<String>,
+    /// RFC 3339
+    pub updated_at: String,
+}
+
+/// 某個年度的一輪抓取，finished 之前中斷的話下次從沒做完的公司繼續
+#[derive(Debug, Serialize, Deserialize, Clone, Default)]
+pub struct YearRun {
+    pub finished: bool,
+    /// 這一輪抓到哪一天為止 (YYYY-MM-DD)，今年的資料隔天就不是最新的
+    #[serde(default)]
+    pub until: String,
+    /// 股票代號 → 結果
+    pub jobs: BTreeMap<String, JobRecord>,
+}
+
+impl YearRun {
+    pub fn count(&self, status: JobStatus) -> usize {
+        self.jobs
+            .values()
+            .filter(|job| job.status == status)
+            .count()
+    }
+}
+
+/// 年度抓取的進度紀錄，每完成一批公司就寫回 fetch_journal.json，中斷後可以續傳
+pub struct FetchJournal {
+    path: String,
+    /// 年度 → 這一輪的進度
+    pub years: BTreeMap<String, YearRun>,
+}
+
+impl FetchJournal {
+    pub fn new(path: &str) -> Result<Self> {
+        let years = if Path::new(path).exists() {
+            let reader = io::BufReader::new(File::open(path)?);
+            serde_json::from_reader(reader)?
+        } else {
+            BTreeMap::new()
+        };
+
+        Ok(FetchJournal {
+            path: path.to_string(),
+            years,
+        })
+    }
+
+    pub fn save(&self) -> Result<()> {
+        // 先寫暫存檔再改名，寫到一半被中斷也不會弄壞原本的紀錄
+        let tmp = format!("{}.tmp", self.path);
+        std::fs::write(&tmp, serde_json::to_string_pretty(&self.years)?)?;
+        std::fs::rename(&tmp, &self.path)?;
+        Ok(())
+    }
+
+    /// 開始抓某個年度到 until (YYYY-MM-DD) 為止，上一輪已經跑完或抓到的日期比較早就重新開始，
+    /// 否則沿用上一輪的進度；回傳是否為續傳
+    pub fn begin_year(&mut self, year: &str, until: &str) -> bool {
+        let run = self.years.entry(year.to_string()).or_default();
+        if run.finished || run.until.as_str() < until {
+            *run = YearRun {
+                until: until.to_string(),
+                ..Default::default()
+            };
+        }
+        !run.jobs.is_empty()
+    }
+
+    /// 某個年度已經完整跑完一輪，而且抓到 until (YYYY-MM-DD) 或更晚
+    pub fn is_complete(&self, year: &str, until: &str) -> bool {
+        self.years
+            .get(year)
+            .is_some_and(|run| run.finished && run.until.as_str() >= until)
+    }
+
+    /// 這一輪已經有結果 (完成或略過) 的公司不用再抓，失敗的會重試
+    pub fn is_settled(&self, stock_no: &str, year: &str) -> bool {
+        self.years
+            .get(year)
+            .and_then(|run| run.jobs.get(stock_no))
+            .is_some_and(|job| job.status != JobStatus::Failed)
+    }
+
+    pub fn record(&mut self, stock_no: &str, year: &str, status: JobStatus, error: Option<String>) {
+        self.years.entry(year.to_string()).or_default().jobs.insert(
+            stock_no.to_string(),
+            JobRecord {
+                status,
+                error,
+                updated_at: Local::now().to_rfc3339(),
+            },
+        );
+    }
+
+    pub fn finish_year(&mut self, year: &str) {
+        self.years.entry(year.to_string()).or_default().finished = true;
+    }
+
+    /// 所有年度還是失敗的 (股票代號, 年度)
+    pub fn failures(&self) -> Vec<(String, i32)> {
+        self.years
+            .iter()
+            .filter_map(|(year, run)| Some((year.parse::<i32>().ok()?, run)))
+            .flat_map(|(year, run)| {
+                run.jobs
+                    .iter()
+                    .filter(|(_, job)| job.status == JobStatus::Failed)
+                    .map(move |(stock_no, _)| (stock_no.clone(), year))
+            })
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn journal() -> FetchJournal {
+        FetchJournal {
+            path: String::new(),
+            years: BTreeMap::new(),
+        }
+    }
+
+    #[test]
+    fn resumes_an_unfinished_run() {
+        let mut journal = journal();
+        assert!(!journal.begin_year("2025", "2025-12-31"));
+        journal.record("1101", "2025", JobStatus::Done, None);
+        journal.record("1102", "2025", JobStatus::Skipped, None);
+        journal.record(
+            "2330",
+            "2025",
+            JobStatus::Failed,
+            Some("HTTP 500".to_string()),
+        );
+
+        assert!(journal.begin_year("2025", "2025-12-31"));
+        assert!(journal.is_settled("1101", "2025"));
+        assert!(journal.is_settled("1102", "2025"));
+        // 失敗的會重試，沒抓過的也要抓
+        assert!(!journal.is_settled("2330", "2025"));
+        assert!(!journal.is_settled("2454", "2025"));
+        assert!(!journal.is_settled("1101", "2024"));
+    }
+
+    #[test]
+    fn restarts_finished_and_stale_runs() {
+        let mut journal = journal();
+        journal.begin_year("2025", "2025-12-31");
+        journal.record("1101", "2025", JobStatus::Done, None);
+        assert!(!journal.is_complete("2025", "2025-12-31"));
+        journal.finish_year("2025");
+        assert!(journal.is_complete("2025", "2025-12-31"));
+        assert!(!journal.begin_year("2025", "2025-12-31"));
+        assert!(!journal.is_settled("1101", "2025"));
+
+        // 今年沒跑完的那一輪只抓到 10/15，10/16 再抓時不能沿用
+        journal.begin_year("2026", "2026-10-15");
+        journal.record("1101", "2026", JobStatus::Done, None);
+        assert!(!journal.begin_year("2026", "2026-10-16"));
+        assert!(!journal.is_settled("1101", "2026"));
+        assert_eq!(journal.years["2026"].until, "2026-10-16");
+        journal.finish_year("2026");
+        assert!(!journal.is_complete("2026", "2026-10-19"));
+    }
+
+    #[test]
+    fn failures_list_every_year() {
+        let mut journal = journal();
+        journal.record("2330", "2024", JobStatus::Failed, None);
+        journal.record("1101", "2025", JobStatus::Done, None);
+        journal.record("2454", "2025", JobStatus::Failed, None);
+        assert_eq!(
+            journal.failures(),
+            [("2330".to_string(), 2024), ("2454".to_string(), 2025)]
+        );
+
+        // 重試成功後就不算失敗
+        journal.record("2330", "2024", JobStatus::Done, None);
+        assert_eq!(journal.failures(), [("2454".to_string(), 2025)]);
+    }
+}
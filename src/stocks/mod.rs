@@ -1,3 +1,11 @@
+pub mod adjustment;
+pub mod company_history;
 pub mod company_map;
 pub mod data;
 pub mod data_company;
+pub mod fetch_journal;
+pub mod resample;
+pub mod suspension;
+pub mod timeframe;
+pub mod universe;
+pub mod validator;
@@ -0,0 +1,141 @@
+use chrono::{Datelike, NaiveDate};
+
+use crate::stocks::data_company::StockData;
+use crate::stocks::timeframe::Timeframe;
+
+/// 把依日期排序的日K合併成週K或月K，其他週期原樣回傳
+///
+/// 週K以 ISO 週 (週一到週日) 分組，跨年的那一週算同一根；K 棒日期用該期間最後一個交易日，
+/// 所以只傳入某天以前的日K時，最後一根就是到那天為止還沒走完的週K/月K
+pub fn resample(daily: &[StockData], timeframe: Timeframe) -> Vec<StockData> {
+    let mut bars: Vec<StockData> = Vec::new();
+    let mut current_period = None;
+    for day in daily {
+        let Ok(date) = NaiveDate::parse_from_str(&day.date, "%Y-%m-%d") else {
+            continue;
+        };
+        let period = match timeframe {
+            Timeframe::Week => {
+                let week = date.iso_week();
+                (week.year(), week.week())
+            }
+            Timeframe::Month => (date.year(), date.month()),
+            _ => return daily.to_vec(),
+        };
+
+        match bars.last_mut() {
+            Some(bar) if current_period == Some(period) => {
+                bar.date = day.date.clone();
+                bar.high = bar.high.max(day.high);
+                bar.low = bar.low.min(day.low);
+                // 漲跌以期初的參考價 (第一天收盤價 - 漲跌) 計算
+                bar.change = bar.change.map(|change| change + day.close - bar.close);
+                bar.close = day.close;
+                bar.volume += day.volume;
+                bar.turnover += day.turnover;
+            }
+            _ => {
+                current_period = Some(period);
+                bars.push(day.clone());
+            }
+        }
+    }
+
+    bars
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn day(date: &str, close: f64, change: Option<f64>) -> StockData {
+        StockData {
+            date: date.to_string(),
+            open: close - 0.5,
+            high: close + 1.0,
+            low: close - 1.0,
+            close,
+            volume: 1000,
+            turnover: 10_000,
+            change,
+        }
+    }
+
+    fn dates(bars: &[StockData]) -> Vec<&str> {
+        bars.iter().map(|bar| bar.date.as_str()).collect()
+    }
+
+    #[test]
+    fn iso_weeks_span_the_year_boundary() {
+        let daily = [
+            // 2025-12-29 ~ 2026-01-02 都是 2026 年第 1 週
+            day("2025-12-26", 10.0, Some(0.0)),
+            day("2025-12-29", 10.0, Some(0.0)),
+            day("2025-12-31", 10.0, Some(0.0)),
+            day("2026-01-02", 10.0, Some(0.0)),
+            day("2026-01-05", 10.0, Some(0.0)),
+            // 2020 年有第 53 週，跟 2021 年第 1 週分開
+            day("2020-12-31", 10.0, Some(0.0)),
+            day("2021-01-04", 10.0, Some(0.0)),
+        ];
+        let bars = resample(&daily[..5], Timeframe::Week);
+        assert_eq!(dates(&bars), ["2025-12-26", "2026-01-02", "2026-01-05"]);
+        let bars = resample(&daily[5..], Timeframe::Week);
+        assert_eq!(dates(&bars), ["2020-12-31", "2021-01-04"]);
+    }
+
+    #[test]
+    fn months_end_on_their_last_trading_day() {
+        let daily = [
+            day("2026-01-29", 10.0, Some(0.0)),
+            day("2026-01-30", 10.0, Some(0.0)),
+            day("2026-02-02", 10.0, Some(0.0)),
+            day("2026-02-11", 10.0, Some(0.0)),
+            day("2026-02-23", 10.0, Some(0.0)),
+        ];
+        let bars = resample(&daily, Timeframe::Month);
+        assert_eq!(dates(&bars), ["2026-01-30", "2026-02-23"]);
+    }
+
+    #[test]
+    fn makeup_saturdays_stay_in_their_week() {
+        // 2016-06-04 (週六) 補行交易
+        let daily = [
+            day("2016-06-01", 10.0, Some(0.0)),
+            day("2016-06-03", 10.0, Some(0.0)),
+            day("2016-06-04", 10.0, Some(0.0)),
+            day("2016-06-06", 10.0, Some(0.0)),
+        ];
+        let bars = resample(&daily, Timeframe::Week);
+        assert_eq!(dates(&bars), ["2016-06-04", "2016-06-06"]);
+        assert_eq!(bars[0].volume, 3000);
+    }
+
+    #[test]
+    fn accumulates_volume_turnover_and_change() {
+        let daily = [
+            day("2026-03-02", 11.0, Some(1.0)),
+            day("2026-03-03", 9.0, Some(-2.0)),
+            day("2026-03-04", 13.0, Some(4.0)),
+        ];
+        let bars = resample(&daily, Timeframe::Week);
+        assert_eq!(bars.len(), 1);
+        let bar = &bars[0];
+        assert_eq!(bar.date, "2026-03-04");
+        assert_eq!(bar.open, 10.5);
+        assert_eq!(bar.high, 14.0);
+        assert_eq!(bar.low, 8.0);
+        assert_eq!(bar.close, 13.0);
+        assert_eq!(bar.volume, 3000);
+        assert_eq!(bar.turnover, 30_000);
+        // 期初參考價 10，週漲跌是 3
+        assert_eq!(bar.change, Some(3.0));
+
+        // 第一天不比價時整根都不知道漲跌
+        let daily = [
+            day("2026-03-02", 11.0, None),
+            day("2026-03-03", 12.0, Some(1.0)),
+        ];
+        assert_eq!(resample(&daily, Timeframe::Week)[0].change, None);
+    }
+}
@@ -0,0 +1,61 @@
+use std::collections::HashMap;
+use std::fs::File;
+use std::io;
+use std::path::Path;
+
+use serde::Deserialize;
+
+use crate::error::Result;
+
+const MODULE_NAME: &str = "stocks::suspension";
+
+/// 停止買賣 (暫停交易) 的期間，這段期間沒有資料是正常的
+#[derive(Debug, Deserialize, Clone)]
+pub struct Suspension {
+    pub stock_no: String,
+    /// YYYY-MM-DD，停止買賣的第一天
+    pub from: String,
+    /// YYYY-MM-DD，恢復交易的前一天，還沒恢復就不填
+    #[serde(default)]
+    pub to: Option<String>,
+}
+
+impl Suspension {
+    /// date (YYYY-MM-DD) 是否在停止買賣期間
+    pub fn covers(&self, date: &str) -> bool {
+        date >= self.from.as_str() && self.to.as_deref().is_none_or(|to| date <= to)
+    }
+}
+
+/// suspensions.json 裡的停止買賣紀錄，檔案不存在就當作沒有；原因等其他欄位只給人看，不會讀取
+#[derive(Default)]
+pub struct Suspensions {
+    by_stock: HashMap<String, Vec<Suspension>>,
+}
+
+impl Suspensions {
+    pub fn new(path: &str) -> Result<Self> {
+        if !Path::new(path).exists() {
+            return Ok(Suspensions::default());
+        }
+        let reader = io::BufReader::new(File::open(path)?);
+        let suspensions: Vec<Suspension> = serde_json::from_reader(reader)?;
+        println!(
+            "[{MODULE_NAME}] 從 {path} 載入 {} 筆停止買賣紀錄",
+            suspensions.len()
+        );
+
+        let mut by_stock: HashMap<String, Vec<Suspension>> = HashMap::new();
+        for suspension in suspensions {
+            by_stock
+                .entry(suspension.stock_no.clone())
+                .or_default()
+                .push(suspension);
+        }
+        Ok(Suspensions { by_stock })
+    }
+
+    pub fn periods(&self, stock_no: &str) -> &[Suspension] {
+        self.by_stock.get(stock_no).map_or(&[], Vec::as_slice)
+    }
+}
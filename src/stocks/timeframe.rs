@@ -0,0 +1,115 @@
+use std::fmt;
+
+use serde::{Deserialize, Serialize};
+
+/// K 棒週期，週K、月K由日K合併而成，分K是另外抓的序列
+#[derive(Debug, Serialize, Deserialize, Clone, Copy, PartialEq, Eq, Hash, Default)]
+pub enum Timeframe {
+    #[default]
+    Day,
+    Week,
+    Month,
+    Min1,
+    Min5,
+    Min10,
+    Min15,
+    Min30,
+    Min60,
+}
+
+pub const ALL: [Timeframe; 9] = [
+    Timeframe::Day,
+    Timeframe::Week,
+    Timeframe::Month,
+    Timeframe::Min1,
+    Timeframe::Min5,
+    Timeframe::Min10,
+    Timeframe::Min15,
+    Timeframe::Min30,
+    Timeframe::Min60,
+];
+
+pub const INTRADAY: [Timeframe; 6] = [
+    Timeframe::Min1,
+    Timeframe::Min5,
+    Timeframe::Min10,
+    Timeframe::Min15,
+    Timeframe::Min30,
+    Timeframe::Min60,
+];
+
+impl Timeframe {
+    pub fn is_intraday(self) -> bool {
+        INTRADAY.contains(&self)
+    }
+
+    /// Fugle historical candles 的 timeframe 參數
+    pub fn fugle_param(self) -> &'static str {
+        match self {
+            Timeframe::Day => "D",
+            Timeframe::Week => "W",
+            Timeframe::Month => "M",
+            Timeframe::Min1 => "1",
+            Timeframe::Min5 => "5",
+            Timeframe::Min10 => "10",
+            Timeframe::Min15 => "15",
+            Timeframe::Min30 => "30",
+            Timeframe::Min60 => "60",
+        }
+    }
+
+    /// 存檔用的名稱，分K的目錄名稱不能是純數字，否則會被當成年度檔
+    pub fn key(self) -> &'static str {
+        match self {
+            Timeframe::Day => "D",
+            Timeframe::Week => "W",
+            Timeframe::Month => "M",
+            Timeframe::Min1 => "1m",
+            Timeframe::Min5 => "5m",
+            Timeframe::Min10 => "10m",
+            Timeframe::Min15 => "15m",
+            Timeframe::Min30 => "30m",
+            Timeframe::Min60 => "60m",
+        }
+    }
+
+    /// 接受 fugle_param 或 key 的寫法，例如 "W"、"60" 或 "60m"
+    pub fn parse(text: &str) -> Option<Self> {
+        let text = text.trim();
+        ALL.into_iter().find(|timeframe| {
+            text.eq_ignore_ascii_case(timeframe.fugle_param())
+                || text.eq_ignore_ascii_case(timeframe.key())
+        })
+    }
+}
+
+impl fmt::Display for Timeframe {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Timeframe::Day => write!(f, "日K"),
+            Timeframe::Week => write!(f, "週K"),
+            Timeframe::Month => write!(f, "月K"),
+            other => write!(f, "{}分K", other.fugle_param()),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parses_fugle_params_and_keys() {
+        assert_eq!(Timeframe::parse("D"), Some(Timeframe::Day));
+        assert_eq!(Timeframe::parse(" w "), Some(Timeframe::Week));
+        assert_eq!(Timeframe::parse("M"), Some(Timeframe::Month));
+        assert_eq!(Timeframe::parse("60"), Some(Timeframe::Min60));
+        assert_eq!(Timeframe::parse("60M"), Some(Timeframe::Min60));
+        assert_eq!(Timeframe::parse("1m"), Some(Timeframe::Min1));
+        assert_eq!(Timeframe::parse("2"), None);
+        assert_eq!(Timeframe::parse(""), None);
+        for timeframe in ALL {
+            assert_eq!(Timeframe::parse(timeframe.key()), Some(timeframe));
+        }
+    }
+}
@@ -0,0 +1,134 @@
+use std::collections::BTreeMap;
+
+use serde::{Deserialize, Serialize};
+
+use crate::stocks::company_map::{CompanyInfo, ETF_INDUSTRY, Market};
+
+pub const DEFAULT_UNIVERSE: &str = "default";
+
+/// 掃描時要看哪些股票，每個條件空白代表不限
+#[derive(Debug, Serialize, Deserialize, Clone, Default)]
+#[serde(default)]
+pub struct Universe {
+    pub markets: Vec<Market>,
+    /// 只看這些產業別代碼
+    pub include_industries: Vec<String>,
+    pub exclude_industries: Vec<String>,
+    /// 是否包含 ETF，ETF 不套用產業條件
+    pub etfs: bool,
+    /// "2300-2499" 或單一代號 "2330"
+    pub stock_no_ranges: Vec<String>,
+    /// 實收資本額範圍 (億元)，公司清單沒有資本額的公司不符合
+    pub min_capital: Option<f64>,
+    pub max_capital: Option<f64>,
+}
+
+impl Universe {
+    pub fn contains(&self, company: &CompanyInfo) -> bool {
+        if !self.markets.is_empty() && !self.markets.contains(&company.market) {
+            return false;
+        }
+        if !self.stock_no_ranges.is_empty()
+            && !self
+                .stock_no_ranges
+                .iter()
+                .any(|range| in_range(range, &company.stock_no))
+        {
+            return false;
+        }
+
+        if self.min_capital.is_some() || self.max_capital.is_some() {
+            let Some(capital) = company.capital_100m() else {
+                return false;
+            };
+            if self.min_capital.is_some_and(|min| capital < min)
+                || self.max_capital.is_some_and(|max| capital > max)
+            {
+                return false;
+            }
+        }
+
+        if company.industry == ETF_INDUSTRY {
+            return self.etfs;
+        }
+        (self.include_industries.is_empty() || self.include_industries.contains(&company.industry))
+            && !self.exclude_industries.contains(&company.industry)
+    }
+}
+
+/// 範圍依代號的數字大小比較 (00878 是 878)，後面的英文字母 (例如 00632R、2881A) 不影響
+fn in_range(range: &str, stock_no: &str) -> bool {
+    match range.split_once('-') {
+        Some((from, to)) => match (
+            stock_no_number(from.trim()),
+            stock_no_number(to.trim()),
+            stock_no_number(stock_no),
+        ) {
+            (Some(from), Some(to), Some(number)) => (from..=to).contains(&number),
+            _ => false,
+        },
+        None => range.trim() == stock_no,
+    }
+}
+
+fn stock_no_number(stock_no: &str) -> Option<u32> {
+    let digits = stock_no
+        .find(|c: char| !c.is_ascii_digit())
+        .unwrap_or(stock_no.len());
+    stock_no[..digits].parse().ok()
+}
+
+/// 內建的股票池：default 跟以前一樣排除金融保險業與 ETF
+pub fn default_universes() -> BTreeMap<String, Universe> {
+    BTreeMap::from([
+        (
+            DEFAULT_UNIVERSE.to_string(),
+            Universe {
+                exclude_industries: vec!["17".to_string()],
+                ..Default::default()
+            },
+        ),
+        (
+            "all".to_string(),
+            Universe {
+                etfs: true,
+                ..Default::default()
+            },
+        ),
+        (
+            "financials".to_string(),
+            Universe {
+                include_industries: vec!["17".to_string()],
+                ..Default::default()
+            },
+        ),
+        (
+            "etfs".to_string(),
+            Universe {
+                etfs: true,
+                include_industries: vec![ETF_INDUSTRY.to_string()],
+                ..Default::default()
+            },
+        ),
+    ])
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn ranges_compare_stock_numbers() {
+        assert!(in_range("2300-2499", "2330"));
+        assert!(in_range("2300-2499", "2499"));
+        assert!(!in_range("2300-2499", "2500"));
+        // 00878 是 878，不因為比較長就排在 2499 後面
+        assert!(in_range("0050-2499", "00878"));
+        assert!(!in_range("2300-2499", "00878"));
+        assert!(in_range("00800-00899", "00878"));
+        assert!(in_range("2880-2892", "2881A"));
+        assert!(in_range("2330", "2330"));
+        assert!(!in_range("2330", "23300"));
+        assert!(!in_range("abc-2499", "2330"));
+    }
+}
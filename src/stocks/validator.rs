@@ -0,0 +1,245 @@
+use std::collections::BTreeSet;
+use std::fmt;
+
+use chrono::{Datelike, Days, NaiveDate};
+
+use crate::calendar::trading::TradingCalendar;
+use crate::stocks::data_company::DataCompany;
+use crate::stocks::suspension::Suspension;
+
+/// 單筆資料檢查出來的問題
+#[derive(Debug, Clone)]
+pub enum Issue {
+    /// 成交量為 0
+    ZeroVolume(String),
+    /// 最高價低於最低價
+    HighBelowLow(String),
+    /// 收盤價不在 [最低價, 最高價] 之間
+    CloseOutOfRange(String),
+    /// 同一天有多筆資料
+    DuplicateDate(String),
+    /// 非交易日卻有資料
+    NonTradingDay(String),
+    /// 兩筆資料之間或最後一筆之後缺少的交易日 (起, 迄, 天數)，成交量為 0 的日子 Fugle 不會回傳
+    MissingDays(String, String, usize),
+    /// 應該有資料的年度完全沒有資料
+    EmptyYear(i32),
+}
+
+impl Issue {
+    /// 問題所在的年度，用來決定要重抓哪幾年
+    pub fn years(&self) -> Vec<i32> {
+        let year_of = |date: &str| date[0..4].parse().unwrap_or_default();
+        match self {
+            Issue::ZeroVolume(date)
+            | Issue::HighBelowLow(date)
+            | Issue::CloseOutOfRange(date)
+            | Issue::DuplicateDate(date)
+            | Issue::NonTradingDay(date) => vec![year_of(date)],
+            // 跨年的缺漏兩個年度都要重抓
+            Issue::MissingDays(from, to, _) => vec![year_of(from), year_of(to)],
+            Issue::EmptyYear(year) => vec![*year],
+        }
+    }
+}
+
+impl fmt::Display for Issue {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Issue::ZeroVolume(date) => write!(f, "{date} 成交量為 0"),
+            Issue::HighBelowLow(date) => write!(f, "{date} 最高價低於最低價"),
+            Issue::CloseOutOfRange(date) => write!(f, "{date} 收盤價超出最高最低價範圍"),
+            Issue::DuplicateDate(date) => write!(f, "{date} 日期重複"),
+            Issue::NonTradingDay(date) => write!(f, "{date} 不是交易日卻有資料"),
+            Issue::MissingDays(from, to, _) if from == to => {
+                write!(f, "{from} 缺少 1 個交易日")
+            }
+            Issue::MissingDays(from, to, count) => {
+                write!(f, "{from} ~ {to} 缺少 {count} 個交易日")
+            }
+            Issue::EmptyYear(year) => write!(f, "{year} 年沒有任何資料"),
+        }
+    }
+}
+
+/// 單一股票的檢查結果
+pub struct StockReport {
+    pub stock_no: String,
+    pub issues: Vec<Issue>,
+}
+
+impl StockReport {
+    /// 有問題、需要重抓的年度
+    pub fn years(&self) -> BTreeSet<i32> {
+        self.issues.iter().flat_map(Issue::years).collect()
+    }
+}
+
+/// 檢查一家公司的資料，`year_from` 到 `until` 之間都應該要有資料，停止買賣期間除外
+pub fn validate(
+    data_company: &DataCompany,
+    calendar: &TradingCalendar,
+    suspensions: &[Suspension],
+    year_from: i32,
+    until: NaiveDate,
+) -> StockReport {
+    let mut issues = Vec::new();
+    let stock_data = &data_company.stock_data;
+    // from ~ to (含頭尾) 之間沒有停止買賣的交易日都算缺漏
+    let missing_days = |from: NaiveDate, to: NaiveDate| {
+        let mut missing = calendar.trading_days_between(from, to);
+        missing.retain(|day| {
+            let day = day.format("%Y-%m-%d").to_string();
+            !suspensions.iter().any(|s| s.covers(&day))
+        });
+        let (first, last) = (missing.first()?, missing.last()?);
+        Some(Issue::MissingDays(
+            first.format("%Y-%m-%d").to_string(),
+            last.format("%Y-%m-%d").to_string(),
+            missing.len(),
+        ))
+    };
+
+    let mut prev: Option<NaiveDate> = None;
+    for (i, data) in stock_data.iter().enumerate() {
+        if data.volume == 0 {
+            issues.push(Issue::ZeroVolume(data.date.clone()));
+        }
+        if data.high < data.low {
+            issues.push(Issue::HighBelowLow(data.date.clone()));
+        } else if data.close < data.low || data.close > data.high {
+            issues.push(Issue::CloseOutOfRange(data.date.clone()));
+        }
+        if i > 0 && stock_data[i - 1].date == data.date {
+            issues.push(Issue::DuplicateDate(data.date.clone()));
+            continue;
+        }
+
+        let Ok(date) = NaiveDate::parse_from_str(&data.date, "%Y-%m-%d") else {
+            continue;
+        };
+        if !calendar.is_trading_day(date) {
+            issues.push(Issue::NonTradingDay(data.date.clone()));
+        }
+        // 前一筆與這一筆之間 (不含頭尾) 的交易日
+        if let Some(prev) = prev {
+            issues.extend(missing_days(prev + Days::new(1), date - Days::new(1)));
+        }
+        prev = Some(date);
+    }
+    // 最後一筆之後到 until 的交易日
+    if let Some(prev) = prev {
+        issues.extend(missing_days(prev + Days::new(1), until));
+    }
+
+    // 上市之前的年度本來就沒有資料，從第一筆資料的年度開始檢查
+    let first_year = stock_data
+        .first()
+        .and_then(|d| d.date.get(0..4)?.parse().ok())
+        .unwrap_or(year_from)
+        .max(year_from);
+    let years_with_data: BTreeSet<&str> =
+        stock_data.iter().filter_map(|d| d.date.get(0..4)).collect();
+    for year in first_year..=until.year() {
+        if years_with_data.contains(year.to_string().as_str()) {
+            continue;
+        }
+        // 整年都在停止買賣的話本來就沒有資料
+        let (Some(first), Some(last)) = (
+            NaiveDate::from_ymd_opt(year, 1, 1),
+            NaiveDate::from_ymd_opt(year, 12, 31),
+        ) else {
+            continue;
+        };
+        if missing_days(first, last.min(until)).is_some() {
+            issues.push(Issue::EmptyYear(year));
+        }
+    }
+
+    StockReport {
+        stock_no: data_company.stock_no.clone(),
+        issues,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::stocks::data_company::StockData;
+
+    fn company(dates: &[&str]) -> DataCompany {
+        let mut data_company = DataCompany::empty("2330".to_string());
+        data_company.stock_data = dates
+            .iter()
+            .map(|date| StockData {
+                date: date.to_string(),
+                open: 10.0,
+                high: 10.0,
+                low: 10.0,
+                close: 10.0,
+                volume: 1,
+                turnover: 10,
+                change: Some(0.0),
+            })
+            .collect();
+        data_company
+    }
+
+    fn suspension(from: &str, to: Option<&str>) -> Suspension {
+        Suspension {
+            stock_no: "2330".to_string(),
+            from: from.to_string(),
+            to: to.map(str::to_string),
+        }
+    }
+
+    fn check(dates: &[&str], suspensions: &[Suspension], until: &str) -> Vec<String> {
+        let calendar = TradingCalendar::new("calendar.test.missing.json").unwrap();
+        let until = NaiveDate::parse_from_str(until, "%Y-%m-%d").unwrap();
+        validate(&company(dates), &calendar, suspensions, 2026, until)
+            .issues
+            .iter()
+            .map(Issue::to_string)
+            .collect()
+    }
+
+    #[test]
+    fn reports_gaps_after_the_last_bar() {
+        assert!(
+            check(
+                &["2026-02-09", "2026-02-10", "2026-02-11"],
+                &[],
+                "2026-02-11"
+            )
+            .is_empty()
+        );
+        assert_eq!(
+            check(&["2026-02-09"], &[], "2026-02-11"),
+            ["2026-02-10 ~ 2026-02-11 缺少 2 個交易日"]
+        );
+        // 春節封關不算缺漏
+        assert_eq!(
+            check(&["2026-02-10"], &[], "2026-02-23"),
+            ["2026-02-11 ~ 2026-02-23 缺少 2 個交易日"]
+        );
+    }
+
+    #[test]
+    fn skips_suspension_periods() {
+        let dates = ["2026-02-02", "2026-02-11"];
+        assert_eq!(
+            check(&dates, &[], "2026-02-11"),
+            ["2026-02-03 ~ 2026-02-10 缺少 6 個交易日"]
+        );
+        let suspended = [suspension("2026-02-03", Some("2026-02-10"))];
+        assert!(check(&dates, &suspended, "2026-02-11").is_empty());
+        let partly = [suspension("2026-02-03", Some("2026-02-06"))];
+        assert_eq!(
+            check(&dates, &partly, "2026-02-11"),
+            ["2026-02-09 ~ 2026-02-10 缺少 2 個交易日"]
+        );
+        // 還沒恢復交易
+        let ongoing = [suspension("2026-02-05", None)];
+        assert!(check(&["2026-02-04"], &ongoing, "2026-02-11").is_empty());
+    }
+}
@@ -0,0 +1,125 @@
+use std::collections::BTreeMap;
+use std::fs;
+use std::fs::File;
+use std::io::{BufReader, BufWriter, Write};
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicU64, Ordering};
+
+use chrono::Local;
+use flate2::Compression;
+use flate2::read::GzDecoder;
+use flate2::write::GzEncoder;
+use reqwest::header::HeaderMap;
+use serde::{Deserialize, Serialize};
+
+use crate::cfg::data::CfgData;
+use crate::error::Result;
+
+const MODULE_NAME: &str = "storage::archive";
+
+/// 同一毫秒內的多個回應靠這個序號區分檔名
+static SEQUENCE: AtomicU64 = AtomicU64::new(0);
+
+/// 一次 API 呼叫的原始回應
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct ArchivedResponse {
+    /// RFC 3339
+    pub fetched_at: String,
+    pub url: String,
+    pub status: u16,
+    pub headers: BTreeMap<String, String>,
+    pub body: String,
+}
+
+impl ArchivedResponse {
+    pub fn new(url: &str, status: u16, headers: &HeaderMap, body: &str) -> Self {
+        ArchivedResponse {
+            fetched_at: Local::now().to_rfc3339(),
+            url: url.to_string(),
+            status,
+            headers: headers
+                .iter()
+                .map(|(name, value)| {
+                    (
+                        name.to_string(),
+                        String::from_utf8_lossy(value.as_bytes()).to_string(),
+                    )
+                })
+                .collect(),
+            body: body.to_string(),
+        }
+    }
+}
+
+/// API 原始回應的壓縮封存：<archive_dir>/<source>/<YYYY-MM-DD>/<時間>-<序號>.json.gz
+#[derive(Clone)]
+pub struct Archive {
+    dir: PathBuf,
+}
+
+impl Archive {
+    /// 設定沒有開啟 archive_responses 時回傳 None
+    pub fn from_cfg(cfg: &CfgData) -> Option<Self> {
+        cfg.archive_responses
+            .then(|| Archive::new(&cfg.archive_dir))
+    }
+
+    pub fn new(dir: &str) -> Self {
+        Archive {
+            dir: PathBuf::from(dir),
+        }
+    }
+
+    pub fn save(&self, source: &str, response: &ArchivedResponse) -> Result<PathBuf> {
+        let now = Local::now();
+        let dir = self
+            .dir
+            .join(source)
+            .join(now.format("%Y-%m-%d").to_string());
+        fs::create_dir_all(&dir)?;
+
+        let sequence = SEQUENCE.fetch_add(1, Ordering::Relaxed);
+        let path = dir.join(format!(
+            "{}-{sequence:06}.json.gz",
+            now.format("%H%M%S%.3f")
+        ));
+        let mut encoder =
+            GzEncoder::new(BufWriter::new(File::create(&path)?), Compression::default());
+        serde_json::to_writer(&mut encoder, response)?;
+        encoder.finish()?.flush()?;
+        Ok(path)
+    }
+
+    /// 封存失敗不影響抓資料，只印出原因
+    pub fn record(&self, source: &str, response: &ArchivedResponse) {
+        if let Err(e) = self.save(source, response) {
+            println!("[{MODULE_NAME}] 無法封存 {}: {e}", response.url);
+        }
+    }
+
+    /// 某個來源的所有封存檔，依抓取時間排序
+    pub fn list(&self, source: &str) -> Vec<PathBuf> {
+        let mut paths = Vec::new();
+        let Ok(days) = fs::read_dir(self.dir.join(source)) else {
+            return paths;
+        };
+        for day in days.filter_map(|entry| entry.ok()) {
+            let Ok(files) = fs::read_dir(day.path()) else {
+                continue;
+            };
+            paths.extend(
+                files
+                    .filter_map(|entry| entry.ok())
+                    .map(|entry| entry.path())
+                    .filter(|path| path.to_string_lossy().ends_with(".json.gz")),
+            );
+        }
+        paths.sort();
+        paths
+    }
+
+    pub fn load(path: &Path) -> Result<ArchivedResponse> {
+        let decoder = GzDecoder::new(BufReader::new(File::open(path)?));
+        Ok(serde_json::from_reader(decoder)?)
+    }
+}
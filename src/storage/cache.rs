@@ -0,0 +1,231 @@
+use std::fs;
+use std::fs::File;
+use std::hash::{DefaultHasher, Hash, Hasher};
+use std::io;
+use std::io::{BufReader, BufWriter, Read, Write};
+use std::path::{Path, PathBuf};
+use std::time::UNIX_EPOCH;
+
+use chrono::{Datelike, NaiveDate};
+
+use crate::stocks::data_company::StockData;
+
+const MODULE_NAME: &str = "storage::cache";
+const MAGIC: &[u8; 4] = b"S2CC";
+const VERSION: u32 = 3;
+const HEADER_LEN: usize = 20;
+
+// 快取檔格式 (little-endian):
+//   magic[4] version:u32 fingerprint:u64 rows:u32
+//   date:[u32; rows] (YYYYMMDD)
+//   open/high/low/close:[f64; rows]
+//   volume/turnover:[u64; rows]
+//   change:[f64; rows] (沒有漲跌存 NaN)
+
+fn cache_file(cache_dir: &str, stock_no: &str) -> PathBuf {
+    Path::new(cache_dir).join(format!("{stock_no}.bin"))
+}
+
+/// JSON 年度檔的檔名、大小與修改時間合成的指紋，任何一個年度檔新增、刪除或改寫都會不同
+///
+/// DefaultHasher 在不同 Rust 版本可能算出不同的值，頂多就是重建一次快取
+pub fn fingerprint(sources: &[PathBuf]) -> u64 {
+    let mut hasher = DefaultHasher::new();
+    for source in sources {
+        source.hash(&mut hasher);
+        if let Ok(metadata) = fs::metadata(source) {
+            metadata.len().hash(&mut hasher);
+            metadata
+                .modified()
+                .ok()
+                .and_then(|mtime| mtime.duration_since(UNIX_EPOCH).ok())
+                .hash(&mut hasher);
+        }
+    }
+    hasher.finish()
+}
+
+/// 讀取快取，若快取不存在或 JSON 來源的指紋不同就回傳 None
+pub fn load(cache_dir: &str, stock_no: &str, fingerprint: u64) -> Option<Vec<StockData>> {
+    let cache_file = cache_file(cache_dir, stock_no);
+    let mut buf = Vec::new();
+    BufReader::new(File::open(&cache_file).ok()?)
+        .read_to_end(&mut buf)
+        .ok()?;
+    let stock_data = decode(&buf, fingerprint);
+    if stock_data.is_none() {
+        println!(
+            "[{MODULE_NAME}] 快取檔 {} 已過期或格式不符，重新建立",
+            cache_file.display()
+        );
+    }
+    stock_data
+}
+
+/// 依 JSON 來源重建快取，寫入失敗只印訊息，不影響讀取
+pub fn save(cache_dir: &str, stock_no: &str, fingerprint: u64, stock_data: &[StockData]) {
+    if let Err(e) = try_save(cache_dir, stock_no, fingerprint, stock_data) {
+        println!("[{MODULE_NAME}] 無法寫入 {stock_no} 的快取: {e}");
+    }
+}
+
+fn try_save(
+    cache_dir: &str,
+    stock_no: &str,
+    fingerprint: u64,
+    stock_data: &[StockData],
+) -> io::Result<()> {
+    let buf = encode(fingerprint, stock_data)?;
+    fs::create_dir_all(cache_dir)?;
+
+    // 先寫暫存檔再改名，避免中斷時留下不完整的快取
+    let cache_file = cache_file(cache_dir, stock_no);
+    let tmp_file = cache_file.with_extension("bin.tmp");
+    {
+        let mut writer = BufWriter::new(File::create(&tmp_file)?);
+        writer.write_all(&buf)?;
+        writer.flush()?;
+    }
+    fs::rename(&tmp_file, &cache_file)
+}
+
+fn encode(fingerprint: u64, stock_data: &[StockData]) -> io::Result<Vec<u8>> {
+    let rows = stock_data.len();
+    let mut buf = Vec::with_capacity(HEADER_LEN + rows * (4 + 8 * 7));
+    buf.extend_from_slice(MAGIC);
+    buf.extend_from_slice(&VERSION.to_le_bytes());
+    buf.extend_from_slice(&fingerprint.to_le_bytes());
+    buf.extend_from_slice(&(rows as u32).to_le_bytes());
+
+    for d in stock_data {
+        buf.extend_from_slice(&encode_date(&d.date)?.to_le_bytes());
+    }
+    for column in [
+        |d: &StockData| d.open,
+        |d: &StockData| d.high,
+        |d: &StockData| d.low,
+        |d: &StockData| d.close,
+    ] {
+        for d in stock_data {
+            buf.extend_from_slice(&column(d).to_le_bytes());
+        }
+    }
+    for d in stock_data {
+        buf.extend_from_slice(&d.volume.to_le_bytes());
+    }
+    for d in stock_data {
+        buf.extend_from_slice(&d.turnover.to_le_bytes());
+    }
+    for d in stock_data {
+        buf.extend_from_slice(&d.change.unwrap_or(f64::NAN).to_le_bytes());
+    }
+
+    Ok(buf)
+}
+
+fn decode(buf: &[u8], fingerprint: u64) -> Option<Vec<StockData>> {
+    if buf.len() < HEADER_LEN || &buf[0..4] != MAGIC {
+        return None;
+    }
+    let version = u32::from_le_bytes(buf[4..8].try_into().unwrap());
+    let cached = u64::from_le_bytes(buf[8..16].try_into().unwrap());
+    if version != VERSION || cached != fingerprint {
+        return None;
+    }
+    let rows = u32::from_le_bytes(buf[16..20].try_into().unwrap()) as usize;
+    if buf.len() != HEADER_LEN + rows * (4 + 8 * 7) {
+        return None;
+    }
+
+    let dates = &buf[HEADER_LEN..HEADER_LEN + rows * 4];
+    let columns = HEADER_LEN + rows * 4;
+    let column = |n: usize| &buf[columns + n * rows * 8..columns + (n + 1) * rows * 8];
+    let f64_at =
+        |n: usize, i: usize| f64::from_le_bytes(column(n)[i * 8..i * 8 + 8].try_into().unwrap());
+    let u64_at =
+        |n: usize, i: usize| u64::from_le_bytes(column(n)[i * 8..i * 8 + 8].try_into().unwrap());
+
+    let stock_data = (0..rows)
+        .map(|i| StockData {
+            date: decode_date(u32::from_le_bytes(
+                dates[i * 4..i * 4 + 4].try_into().unwrap(),
+            )),
+            open: f64_at(0, i),
+            high: f64_at(1, i),
+            low: f64_at(2, i),
+            close: f64_at(3, i),
+            volume: u64_at(4, i),
+            turnover: u64_at(5, i),
+            change: Some(f64_at(6, i)).filter(|change| !change.is_nan()),
+        })
+        .collect();
+
+    Some(stock_data)
+}
+
+/// "YYYY-MM-DD" → YYYYMMDD，不是合法日期就不寫快取，免得讀回來變成別的日期
+fn encode_date(date: &str) -> io::Result<u32> {
+    NaiveDate::parse_from_str(date, "%Y-%m-%d")
+        .map(|d| d.year() as u32 * 10000 + d.month() * 100 + d.day())
+        .map_err(|_| io::Error::new(io::ErrorKind::InvalidData, format!("無效的日期: {date}")))
+}
+
+fn decode_date(date: u32) -> String {
+    format!(
+        "{:04}-{:02}-{:02}",
+        date / 10000,
+        date / 100 % 100,
+        date % 100
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn bar(date: &str, close: f64) -> StockData {
+        StockData {
+            date: date.to_string(),
+            open: close - 1.0,
+            high: close + 1.0,
+            low: close - 2.0,
+            close,
+            volume: 1_234,
+            turnover: 5_678,
+            change: Some(-0.5),
+        }
+    }
+
+    #[test]
+    fn round_trips_every_column() {
+        let stock_data = vec![
+            bar("2025-12-31", 100.5),
+            StockData {
+                change: None,
+                ..bar("2026-01-02", 101.0)
+            },
+        ];
+
+        let buf = encode(42, &stock_data).unwrap();
+        let decoded = decode(&buf, 42).unwrap();
+
+        assert_eq!(decoded.len(), 2);
+        for (a, b) in decoded.iter().zip(&stock_data) {
+            assert_eq!(a.date, b.date);
+            assert_eq!(
+                (a.open, a.high, a.low, a.close, a.change),
+                (b.open, b.high, b.low, b.close, b.change)
+            );
+            assert_eq!((a.volume, a.turnover), (b.volume, b.turnover));
+        }
+        // 來源指紋不同就視為過期
+        assert!(decode(&buf, 43).is_none());
+    }
+
+    #[test]
+    fn rejects_invalid_dates() {
+        assert_eq!(encode_date("2026-02-11").unwrap(), 20260211);
+        assert!(encode_date("2026-13-01").is_err());
+        assert!(encode(0, &[bar("20260211", 1.0)]).is_err());
+    }
+}
@@ -0,0 +1,59 @@
+use crate::cfg::data::CfgData;
+use crate::error::Result;
+use crate::stocks::timeframe;
+use crate::storage::sqlite::SqliteStore;
+use crate::storage::stocks::JsonStore;
+use crate::storage::store::StockStore;
+
+const MODULE_NAME: &str = "storage::migrate";
+
+/// 把既有的 data/ JSON 檔全部匯入 SQLite，不需要重新抓資料
+pub fn json_to_sqlite(cfg: &CfgData) -> Result<()> {
+    let json_store = JsonStore::new(cfg);
+    let sqlite_store = SqliteStore::open(cfg)?;
+
+    // 舊版的還原價照樣標成舊版，匯入後一樣要重抓
+    if let Some(format) = json_store.price_format()? {
+        sqlite_store.save_price_format(&format)?;
+    }
+
+    let stock_nos = json_store.list_stock_nos();
+    let total = stock_nos.len();
+    let mut rows = 0;
+    for (index, stock_no) in (1..).zip(&stock_nos) {
+        for year in json_store.list_years(stock_no) {
+            // 壞掉的年度檔跳過，不中斷整個匯入
+            match json_store.read_year(stock_no, &year) {
+                Ok(Some(yearly_data)) => {
+                    rows += yearly_data.len();
+                    sqlite_store.save(stock_no, &year, &yearly_data)?;
+                }
+                Ok(None) => {}
+                Err(e) => println!("[{MODULE_NAME}] 略過 {stock_no} {year}: {e}"),
+            }
+        }
+        for timeframe in timeframe::INTRADAY {
+            for year in json_store.list_intraday_years(stock_no, timeframe) {
+                match json_store.read_intraday_year(stock_no, timeframe, &year) {
+                    Ok(Some(candles)) => {
+                        rows += candles.len();
+                        sqlite_store.save_intraday(stock_no, timeframe, &year, &candles)?;
+                    }
+                    Ok(None) => {}
+                    Err(e) => println!("[{MODULE_NAME}] 略過 {stock_no} {timeframe} {year}: {e}"),
+                }
+            }
+        }
+        match json_store.read_adjustments(stock_no) {
+            Ok(adjustments) => sqlite_store.save_adjustments(stock_no, &adjustments)?,
+            Err(e) => println!("[{MODULE_NAME}] 略過 {stock_no} 還原因子: {e}"),
+        }
+        println!("[{MODULE_NAME}] [{index}/{total}] 已匯入 {stock_no}");
+    }
+
+    println!(
+        "[{MODULE_NAME}] 匯入完成: {total} 支股票，共 {rows} 筆資料 → {}",
+        cfg.sqlite_path
+    );
+    Ok(())
+}
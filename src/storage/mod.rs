@@ -1 +1,6 @@
+pub mod archive;
+pub mod cache;
+pub mod migrate;
+pub mod sqlite;
 pub mod stocks;
+pub mod store;
@@ -0,0 +1,302 @@
+use std::ops::RangeInclusive;
+use std::sync::Mutex;
+
+use rusqlite::{Connection, OptionalExtension, params};
+
+use crate::cfg::data::CfgData;
+use crate::error::Result;
+use crate::stocks::adjustment::Adjustment;
+use crate::stocks::data_company::StockData;
+use crate::stocks::timeframe::Timeframe;
+use crate::storage::store::StockStore;
+
+const MODULE_NAME: &str = "storage::sqlite";
+
+/// 內嵌 SQLite 資料庫後端，所有股票存在同一個檔案的 stock_data 表，還原因子在 adjustments 表，
+/// 分K在 intraday 表以 timeframe 欄位區分週期，價格格式等設定在 meta 表
+pub struct SqliteStore {
+    conn: Mutex<Connection>,
+    years: RangeInclusive<i32>,
+}
+
+impl SqliteStore {
+    pub fn open(cfg: &CfgData) -> Result<Self> {
+        let path = &cfg.sqlite_path;
+        let conn = Connection::open(path)?;
+        conn.execute_batch(
+            "PRAGMA journal_mode = WAL;
+             PRAGMA synchronous = NORMAL;
+             CREATE TABLE IF NOT EXISTS stock_data (
+                 stock_no TEXT NOT NULL,
+                 date     TEXT NOT NULL,
+                 open     REAL NOT NULL,
+                 high     REAL NOT NULL,
+                 low      REAL NOT NULL,
+                 close    REAL NOT NULL,
+                 volume   INTEGER NOT NULL,
+                 turnover INTEGER NOT NULL,
+                 change   REAL,
+                 PRIMARY KEY (stock_no, date)
+             ) WITHOUT ROWID;
+             CREATE TABLE IF NOT EXISTS adjustments (
+                 stock_no TEXT NOT NULL,
+                 date     TEXT NOT NULL,
+                 factor   REAL NOT NULL,
+                 PRIMARY KEY (stock_no, date)
+             ) WITHOUT ROWID;
+             CREATE TABLE IF NOT EXISTS intraday (
+                 stock_no  TEXT NOT NULL,
+                 timeframe TEXT NOT NULL,
+                 time      TEXT NOT NULL,
+                 open      REAL NOT NULL,
+                 high      REAL NOT NULL,
+                 low       REAL NOT NULL,
+                 close     REAL NOT NULL,
+                 volume    INTEGER NOT NULL,
+                 PRIMARY KEY (stock_no, timeframe, time)
+             ) WITHOUT ROWID;
+             CREATE TABLE IF NOT EXISTS meta (
+                 key   TEXT PRIMARY KEY,
+                 value TEXT NOT NULL
+             );",
+        )?;
+        migrate_nullable_change(&conn)?;
+        println!("[{MODULE_NAME}] Opened SQLite database: {path}");
+
+        Ok(SqliteStore {
+            conn: Mutex::new(conn),
+            years: cfg.years(),
+        })
+    }
+}
+
+/// 舊版的 stock_data.change 是 NOT NULL，不比價的日子要存 NULL，重建成可以是 NULL 的欄位
+fn migrate_nullable_change(conn: &Connection) -> Result<()> {
+    let not_null: bool = conn.query_row(
+        "SELECT \"notnull\" FROM pragma_table_info('stock_data') WHERE name = 'change'",
+        [],
+        |row| row.get(0),
+    )?;
+    if !not_null {
+        return Ok(());
+    }
+    println!("[{MODULE_NAME}] 將 stock_data.change 改成可以是 NULL...");
+    conn.execute_batch(
+        "BEGIN;
+         ALTER TABLE stock_data RENAME TO stock_data_old;
+         CREATE TABLE stock_data (
+             stock_no TEXT NOT NULL,
+             date     TEXT NOT NULL,
+             open     REAL NOT NULL,
+             high     REAL NOT NULL,
+             low      REAL NOT NULL,
+             close    REAL NOT NULL,
+             volume   INTEGER NOT NULL,
+             turnover INTEGER NOT NULL,
+             change   REAL,
+             PRIMARY KEY (stock_no, date)
+         ) WITHOUT ROWID;
+         INSERT INTO stock_data SELECT * FROM stock_data_old;
+         DROP TABLE stock_data_old;
+         COMMIT;",
+    )?;
+    Ok(())
+}
+
+impl StockStore for SqliteStore {
+    fn name(&self) -> &'static str {
+        "sqlite"
+    }
+
+    fn years(&self) -> RangeInclusive<i32> {
+        self.years.clone()
+    }
+
+    fn save(&self, stock_no: &str, year: &str, data: &[StockData]) -> Result<()> {
+        let mut conn = self.conn.lock().unwrap();
+        let tx = conn.transaction()?;
+        tx.execute(
+            "DELETE FROM stock_data WHERE stock_no = ?1 AND substr(date, 1, 4) = ?2",
+            params![stock_no, year],
+        )?;
+        {
+            let mut stmt = tx.prepare_cached(
+                "INSERT OR REPLACE INTO stock_data
+                 (stock_no, date, open, high, low, close, volume, turnover, change)
+                 VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9)",
+            )?;
+            for d in data {
+                stmt.execute(params![
+                    stock_no,
+                    d.date,
+                    d.open,
+                    d.high,
+                    d.low,
+                    d.close,
+                    d.volume as i64,
+                    d.turnover as i64,
+                    d.change
+                ])?;
+            }
+        }
+        tx.commit()?;
+        Ok(())
+    }
+
+    fn read_year(&self, stock_no: &str, year: &str) -> Result<Option<Vec<StockData>>> {
+        let conn = self.conn.lock().unwrap();
+        let mut stmt = conn.prepare_cached(
+            "SELECT date, open, high, low, close, volume, turnover, change
+             FROM stock_data WHERE stock_no = ?1 AND substr(date, 1, 4) = ?2
+             ORDER BY date",
+        )?;
+        let yearly_data: Vec<StockData> = stmt
+            .query_map(params![stock_no, year], |row| {
+                Ok(StockData {
+                    date: row.get(0)?,
+                    open: row.get(1)?,
+                    high: row.get(2)?,
+                    low: row.get(3)?,
+                    close: row.get(4)?,
+                    volume: row.get::<_, i64>(5)? as u64,
+                    turnover: row.get::<_, i64>(6)? as u64,
+                    change: row.get(7)?,
+                })
+            })?
+            .collect::<rusqlite::Result<_>>()?;
+
+        if yearly_data.is_empty() {
+            Ok(None)
+        } else {
+            Ok(Some(yearly_data))
+        }
+    }
+
+    fn save_intraday(
+        &self,
+        stock_no: &str,
+        timeframe: Timeframe,
+        year: &str,
+        candles: &[StockData],
+    ) -> Result<()> {
+        let mut conn = self.conn.lock().unwrap();
+        let tx = conn.transaction()?;
+        tx.execute(
+            "DELETE FROM intraday
+             WHERE stock_no = ?1 AND timeframe = ?2 AND substr(time, 1, 4) = ?3",
+            params![stock_no, timeframe.key(), year],
+        )?;
+        {
+            let mut stmt = tx.prepare_cached(
+                "INSERT OR REPLACE INTO intraday
+                 (stock_no, timeframe, time, open, high, low, close, volume)
+                 VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8)",
+            )?;
+            for c in candles {
+                stmt.execute(params![
+                    stock_no,
+                    timeframe.key(),
+                    c.date,
+                    c.open,
+                    c.high,
+                    c.low,
+                    c.close,
+                    c.volume as i64
+                ])?;
+            }
+        }
+        tx.commit()?;
+        Ok(())
+    }
+
+    fn read_intraday_year(
+        &self,
+        stock_no: &str,
+        timeframe: Timeframe,
+        year: &str,
+    ) -> Result<Option<Vec<StockData>>> {
+        let conn = self.conn.lock().unwrap();
+        let mut stmt = conn.prepare_cached(
+            "SELECT time, open, high, low, close, volume
+             FROM intraday
+             WHERE stock_no = ?1 AND timeframe = ?2 AND substr(time, 1, 4) = ?3
+             ORDER BY time",
+        )?;
+        let candles: Vec<StockData> = stmt
+            .query_map(params![stock_no, timeframe.key(), year], |row| {
+                Ok(StockData {
+                    date: row.get(0)?,
+                    open: row.get(1)?,
+                    high: row.get(2)?,
+                    low: row.get(3)?,
+                    close: row.get(4)?,
+                    volume: row.get::<_, i64>(5)? as u64,
+                    turnover: 0,
+                    change: None,
+                })
+            })?
+            .collect::<rusqlite::Result<_>>()?;
+
+        if candles.is_empty() {
+            Ok(None)
+        } else {
+            Ok(Some(candles))
+        }
+    }
+
+    fn save_adjustments(&self, stock_no: &str, adjustments: &[Adjustment]) -> Result<()> {
+        let mut conn = self.conn.lock().unwrap();
+        let tx = conn.transaction()?;
+        tx.execute(
+            "DELETE FROM adjustments WHERE stock_no = ?1",
+            params![stock_no],
+        )?;
+        {
+            let mut stmt = tx.prepare_cached(
+                "INSERT OR REPLACE INTO adjustments (stock_no, date, factor) VALUES (?1, ?2, ?3)",
+            )?;
+            for a in adjustments {
+                stmt.execute(params![stock_no, a.date, a.factor])?;
+            }
+        }
+        tx.commit()?;
+        Ok(())
+    }
+
+    fn read_adjustments(&self, stock_no: &str) -> Result<Vec<Adjustment>> {
+        let conn = self.conn.lock().unwrap();
+        let mut stmt = conn.prepare_cached(
+            "SELECT date, factor FROM adjustments WHERE stock_no = ?1 ORDER BY date",
+        )?;
+        let adjustments = stmt
+            .query_map(params![stock_no], |row| {
+                Ok(Adjustment {
+                    date: row.get(0)?,
+                    factor: row.get(1)?,
+                })
+            })?
+            .collect::<rusqlite::Result<_>>()?;
+        Ok(adjustments)
+    }
+
+    fn price_format(&self) -> Result<Option<String>> {
+        let conn = self.conn.lock().unwrap();
+        let format = conn
+            .query_row(
+                "SELECT value FROM meta WHERE key = 'price_format'",
+                [],
+                |row| row.get(0),
+            )
+            .optional()?;
+        Ok(format)
+    }
+
+    fn save_price_format(&self, format: &str) -> Result<()> {
+        let conn = self.conn.lock().unwrap();
+        conn.execute(
+            "INSERT OR REPLACE INTO meta (key, value) VALUES ('price_format', ?1)",
+            params![format],
+        )?;
+        Ok(())
+    }
+}
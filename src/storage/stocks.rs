@@ -1,45 +1,210 @@
 use std::fs;
 use std::fs::File;
 use std::io::BufWriter;
+use std::ops::RangeInclusive;
+use std::path::PathBuf;
 
-use crate::consts;
+use crate::cfg::data::CfgData;
+use crate::error::Result;
+use crate::stocks::adjustment::Adjustment;
 use crate::stocks::data_company::StockData;
+use crate::stocks::timeframe::Timeframe;
+use crate::storage::cache;
+use crate::storage::store::StockStore;
 
 const MODULE_NAME: &str = "storage::stocks";
-const DATA_DIR: &str = "data";
 
-pub fn save(stock_no: &str, year: &str, data: &Vec<StockData>) {
-    let data_company_dir = format!("{DATA_DIR}/{stock_no}");
-    if fs::metadata(&data_company_dir).is_err() {
-        fs::create_dir_all(&data_company_dir).unwrap();
+const ADJUSTMENTS_FILE: &str = "adjustments.json";
+/// 記錄日K價格格式的檔案，放在 data_dir 底下
+const PRICE_FORMAT_FILE: &str = "price_format";
+
+/// <data_dir>/<stock_no>/<year>.json 的 JSON 檔案後端，還原因子存在同目錄的 adjustments.json
+///
+/// 分K存在 <data_dir>/<stock_no>/<timeframe>/<year>.json，例如 60m/2026.json
+pub struct JsonStore {
+    data_dir: String,
+    cache_dir: String,
+    years: RangeInclusive<i32>,
+}
+
+impl JsonStore {
+    pub fn new(cfg: &CfgData) -> Self {
+        JsonStore {
+            data_dir: cfg.data_dir.clone(),
+            cache_dir: cfg.cache_dir.clone(),
+            years: cfg.years(),
+        }
+    }
+
+    /// 列出 data/ 底下所有的股票代號
+    pub fn list_stock_nos(&self) -> Vec<String> {
+        let mut stock_nos: Vec<String> = match fs::read_dir(&self.data_dir) {
+            Ok(entries) => entries
+                .filter_map(|entry| entry.ok())
+                .filter(|entry| entry.path().is_dir())
+                .map(|entry| entry.file_name().to_string_lossy().to_string())
+                .collect(),
+            Err(_) => Vec::new(),
+        };
+        stock_nos.sort();
+        stock_nos
     }
 
-    let data_company_file = format!("{data_company_dir}/{year}.json");
+    /// 列出某支股票有哪些年度檔
+    pub fn list_years(&self, stock_no: &str) -> Vec<String> {
+        list_year_files(&format!("{}/{stock_no}", self.data_dir))
+    }
+
+    /// 列出某支股票某個分K週期有哪些年度檔
+    pub fn list_intraday_years(&self, stock_no: &str, timeframe: Timeframe) -> Vec<String> {
+        list_year_files(&self.intraday_dir(stock_no, timeframe))
+    }
 
-    // Implementation for writing data to storage
-    let file = File::create(&data_company_file).unwrap();
-    let writer = BufWriter::new(file);
+    fn intraday_dir(&self, stock_no: &str, timeframe: Timeframe) -> String {
+        format!("{}/{stock_no}/{}", self.data_dir, timeframe.key())
+    }
+}
 
-    serde_json::to_writer_pretty(writer, &data).unwrap();
+/// 目錄底下以年份命名的 .json 檔
+fn list_year_files(dir: &str) -> Vec<String> {
+    let mut years: Vec<String> = match fs::read_dir(dir) {
+        Ok(entries) => entries
+            .filter_map(|entry| entry.ok())
+            .filter_map(|entry| {
+                let file_name = entry.file_name().to_string_lossy().to_string();
+                let year = file_name.strip_suffix(".json")?;
+                year.parse::<i32>().is_ok().then(|| year.to_string())
+            })
+            .collect(),
+        Err(_) => Vec::new(),
+    };
+    years.sort();
+    years
 }
 
-pub fn read(stock_no: &str) -> Vec<StockData> {
-    let mut stock_data = Vec::new();
-    for year in consts::YEAR_FROM..=consts::YEAR_TO {
-        let data_company_file = format!("{DATA_DIR}/{stock_no}/{year}.json");
+impl StockStore for JsonStore {
+    fn name(&self) -> &'static str {
+        "json"
+    }
+
+    fn years(&self) -> RangeInclusive<i32> {
+        self.years.clone()
+    }
+
+    fn save(&self, stock_no: &str, year: &str, data: &[StockData]) -> Result<()> {
+        let data_company_dir = format!("{}/{stock_no}", self.data_dir);
+        if fs::metadata(&data_company_dir).is_err() {
+            fs::create_dir_all(&data_company_dir)?;
+        }
+
+        let data_company_file = format!("{data_company_dir}/{year}.json");
+
+        // Implementation for writing data to storage
+        let file = File::create(&data_company_file)?;
+        let writer = BufWriter::new(file);
+
+        serde_json::to_writer_pretty(writer, &data)?;
+        Ok(())
+    }
+
+    fn save_intraday(
+        &self,
+        stock_no: &str,
+        timeframe: Timeframe,
+        year: &str,
+        candles: &[StockData],
+    ) -> Result<()> {
+        let intraday_dir = self.intraday_dir(stock_no, timeframe);
+        fs::create_dir_all(&intraday_dir)?;
+
+        let file = File::create(format!("{intraday_dir}/{year}.json"))?;
+        serde_json::to_writer_pretty(BufWriter::new(file), candles)?;
+        Ok(())
+    }
+
+    fn read_intraday_year(
+        &self,
+        stock_no: &str,
+        timeframe: Timeframe,
+        year: &str,
+    ) -> Result<Option<Vec<StockData>>> {
+        let intraday_file = format!("{}/{year}.json", self.intraday_dir(stock_no, timeframe));
+        if fs::metadata(&intraday_file).is_err() {
+            return Ok(None);
+        }
+        let reader = std::io::BufReader::new(File::open(&intraday_file)?);
+        Ok(Some(serde_json::from_reader(reader)?))
+    }
+
+    fn save_adjustments(&self, stock_no: &str, adjustments: &[Adjustment]) -> Result<()> {
+        let data_company_dir = format!("{}/{stock_no}", self.data_dir);
+        fs::create_dir_all(&data_company_dir)?;
+
+        let file = File::create(format!("{data_company_dir}/{ADJUSTMENTS_FILE}"))?;
+        serde_json::to_writer_pretty(BufWriter::new(file), adjustments)?;
+        Ok(())
+    }
+
+    fn read_adjustments(&self, stock_no: &str) -> Result<Vec<Adjustment>> {
+        let adjustments_file = format!("{}/{stock_no}/{ADJUSTMENTS_FILE}", self.data_dir);
+        if fs::metadata(&adjustments_file).is_err() {
+            return Ok(Vec::new());
+        }
+        let reader = std::io::BufReader::new(File::open(&adjustments_file)?);
+        Ok(serde_json::from_reader(reader)?)
+    }
+
+    fn price_format(&self) -> Result<Option<String>> {
+        let path = format!("{}/{PRICE_FORMAT_FILE}", self.data_dir);
+        if fs::metadata(&path).is_err() {
+            return Ok(None);
+        }
+        Ok(Some(fs::read_to_string(path)?.trim().to_string()))
+    }
+
+    fn save_price_format(&self, format: &str) -> Result<()> {
+        fs::create_dir_all(&self.data_dir)?;
+        fs::write(format!("{}/{PRICE_FORMAT_FILE}", self.data_dir), format)?;
+        Ok(())
+    }
+
+    /// 優先讀二進位快取，JSON 年度檔有變動時才重新解析並重建快取
+    fn read(&self, stock_no: &str) -> Result<Vec<StockData>> {
+        let sources: Vec<PathBuf> = self
+            .years()
+            .map(|year| PathBuf::from(format!("{}/{stock_no}/{year}.json", self.data_dir)))
+            .filter(|source| source.exists())
+            .collect();
+
+        let fingerprint = cache::fingerprint(&sources);
+        if let Some(stock_data) = cache::load(&self.cache_dir, stock_no, fingerprint) {
+            return Ok(stock_data);
+        }
+
+        let mut stock_data = Vec::new();
+        for year in self.years() {
+            if let Some(yearly_data) = self.read_year(stock_no, &year.to_string())? {
+                stock_data.extend(yearly_data);
+            }
+        }
+        stock_data.sort_by(|a, b| a.date.cmp(&b.date));
+
+        cache::save(&self.cache_dir, stock_no, fingerprint, &stock_data);
+        Ok(stock_data)
+    }
+
+    fn read_year(&self, stock_no: &str, year: &str) -> Result<Option<Vec<StockData>>> {
+        let data_company_file = format!("{}/{stock_no}/{year}.json", self.data_dir);
         // println!("[{MODULE_NAME}] Reading data from {data_company_file}");
         if fs::metadata(&data_company_file).is_ok() {
-            let file = File::open(&data_company_file).unwrap();
+            let file = File::open(&data_company_file)?;
             let reader = std::io::BufReader::new(file);
-            let yearly_data: Vec<StockData> = serde_json::from_reader(reader).unwrap();
-            stock_data.extend(yearly_data);
+            Ok(Some(serde_json::from_reader(reader)?))
         } else {
             println!(
                 "[{MODULE_NAME}] Data file not found for stock: {stock_no}, year: {year}. Please run the fetch function first."
             );
+            Ok(None)
         }
     }
-
-    stock_data.sort_by(|a, b| a.date.cmp(&b.date));
-    stock_data
 }
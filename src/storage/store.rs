@@ -0,0 +1,194 @@
+use std::collections::BTreeMap;
+use std::ops::RangeInclusive;
+
+use crate::cfg::data::{CfgData, StorageBackend};
+use crate::error::Result;
+use crate::stocks::adjustment::Adjustment;
+use crate::stocks::data_company::StockData;
+use crate::stocks::timeframe::Timeframe;
+use crate::storage::sqlite::SqliteStore;
+use crate::storage::stocks::JsonStore;
+
+/// price_format 的值，代表日K存的是實際成交的原始價格；舊版存的是 Fugle 的還原價，沒有這個標記
+pub const RAW_PRICES: &str = "raw";
+
+/// 股票日K的儲存後端，以 (股票代號, 年份) 為單位存取
+pub trait StockStore {
+    fn name(&self) -> &'static str;
+
+    /// read() 會讀取的年份範圍
+    fn years(&self) -> RangeInclusive<i32>;
+
+    /// 以 data 覆蓋整個年度的資料
+    fn save(&self, stock_no: &str, year: &str, data: &[StockData]) -> Result<()>;
+
+    /// 該年度沒有資料時回傳 Ok(None)
+    fn read_year(&self, stock_no: &str, year: &str) -> Result<Option<Vec<StockData>>>;
+
+    /// 以 adjustments 覆蓋整支股票的還原因子表
+    fn save_adjustments(&self, stock_no: &str, adjustments: &[Adjustment]) -> Result<()>;
+
+    /// 沒有還原因子表時回傳空的
+    fn read_adjustments(&self, stock_no: &str) -> Result<Vec<Adjustment>>;
+
+    /// 日K的價格格式，還沒記錄過時回傳 Ok(None)
+    fn price_format(&self) -> Result<Option<String>>;
+
+    fn save_price_format(&self, format: &str) -> Result<()>;
+
+    /// 以 candles 覆蓋某個分K週期整個年度的資料，每個週期是獨立的序列
+    fn save_intraday(
+        &self,
+        stock_no: &str,
+        timeframe: Timeframe,
+        year: &str,
+        candles: &[StockData],
+    ) -> Result<()>;
+
+    /// 該年度沒有分K時回傳 Ok(None)
+    fn read_intraday_year(
+        &self,
+        stock_no: &str,
+        timeframe: Timeframe,
+        year: &str,
+    ) -> Result<Option<Vec<StockData>>>;
+
+    fn read_intraday(&self, stock_no: &str, timeframe: Timeframe) -> Result<Vec<StockData>> {
+        let mut candles = Vec::new();
+        for year in self.years() {
+            if let Some(yearly_candles) =
+                self.read_intraday_year(stock_no, timeframe, &year.to_string())?
+            {
+                candles.extend(yearly_candles);
+            }
+        }
+
+        candles.sort_by(|a, b| a.date.cmp(&b.date));
+        Ok(candles)
+    }
+
+    /// 把新抓到的分K依年度合併進既有資料，同一根 K 棒以新資料為準，回傳合併後的總筆數
+    fn merge_intraday(
+        &self,
+        stock_no: &str,
+        timeframe: Timeframe,
+        candles: &[StockData],
+    ) -> Result<usize> {
+        let mut years: BTreeMap<&str, Vec<StockData>> = BTreeMap::new();
+        for candle in candles {
+            if let Some(year) = candle.date.get(..4) {
+                years.entry(year).or_default().push(candle.clone());
+            }
+        }
+
+        let mut total = 0;
+        for (year, new) in years {
+            let old = self
+                .read_intraday_year(stock_no, timeframe, year)?
+                .unwrap_or_default();
+            let merged = merge_by_date(old, new);
+            self.save_intraday(stock_no, timeframe, year, &merged)?;
+            total += merged.len();
+        }
+        Ok(total)
+    }
+
+    fn read(&self, stock_no: &str) -> Result<Vec<StockData>> {
+        let mut stock_data = Vec::new();
+        for year in self.years() {
+            if let Some(yearly_data) = self.read_year(stock_no, &year.to_string())? {
+                stock_data.extend(yearly_data);
+            }
+        }
+
+        stock_data.sort_by(|a, b| a.date.cmp(&b.date));
+        Ok(stock_data)
+    }
+
+    /// 把新抓到的資料合併進既有的年度資料，同一天以新資料為準，回傳合併後的筆數
+    fn merge(&self, stock_no: &str, year: &str, data: &[StockData]) -> Result<usize> {
+        let old = self.read_year(stock_no, year)?.unwrap_or_default();
+        let merged = merge_by_date(old, data.to_vec());
+
+        self.save(stock_no, year, &merged)?;
+        Ok(merged.len())
+    }
+}
+
+/// 依日期合併兩份 K 棒，同一個日期以 new 為準，結果依日期排序
+fn merge_by_date(old: Vec<StockData>, new: Vec<StockData>) -> Vec<StockData> {
+    let mut merged: BTreeMap<String, StockData> =
+        old.into_iter().map(|d| (d.date.clone(), d)).collect();
+    merged.extend(new.into_iter().map(|d| (d.date.clone(), d)));
+    merged.into_values().collect()
+}
+
+pub fn open(cfg: &CfgData) -> Result<Box<dyn StockStore>> {
+    Ok(match cfg.storage_backend {
+        StorageBackend::Json => Box::new(JsonStore::new(cfg)),
+        StorageBackend::Sqlite => Box::new(SqliteStore::open(cfg)?),
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn candle(time: &str, close: f64) -> StockData {
+        StockData {
+            date: time.to_string(),
+            open: close,
+            high: close,
+            low: close,
+            close,
+            volume: 1000,
+            turnover: 0,
+            change: None,
+        }
+    }
+
+    #[test]
+    fn merge_intraday_replaces_overlapping_candles_per_year() {
+        let mut cfg = CfgData::default();
+        cfg.sqlite_path = ":memory:".to_string();
+        let store = SqliteStore::open(&cfg).unwrap();
+        let old = [
+            candle("2025-12-31T13:00:00", 10.0),
+            candle("2026-01-02T09:00:00", 11.0),
+            candle("2026-01-02T10:00:00", 12.0),
+        ];
+        store
+            .merge_intraday("2330", Timeframe::Min60, &old)
+            .unwrap();
+
+        let new = [
+            candle("2026-01-02T10:00:00", 12.5),
+            candle("2026-01-02T11:00:00", 13.0),
+        ];
+        assert_eq!(
+            store
+                .merge_intraday("2330", Timeframe::Min60, &new)
+                .unwrap(),
+            3
+        );
+
+        let merged = store.read_intraday("2330", Timeframe::Min60).unwrap();
+        let closes: Vec<(&str, f64)> = merged.iter().map(|c| (c.date.as_str(), c.close)).collect();
+        assert_eq!(
+            closes,
+            [
+                ("2025-12-31T13:00:00", 10.0),
+                ("2026-01-02T09:00:00", 11.0),
+                ("2026-01-02T10:00:00", 12.5),
+                ("2026-01-02T11:00:00", 13.0),
+            ]
+        );
+        // 其他週期是獨立的序列
+        assert!(
+            store
+                .read_intraday("2330", Timeframe::Min5)
+                .unwrap()
+                .is_empty()
+        );
+    }
+}
@@ -0,0 +1,92 @@
+use serde::Deserialize;
+
+use crate::error::{Error, Result};
+use crate::stocks::company_map::{CompanyInfo, Market};
+
+#[derive(Debug, Deserialize)]
+struct TpexCompanyInfo {
+    #[serde(rename = "SecuritiesCompanyCode")]
+    stock_no: String,
+    #[serde(rename = "CompanyAbbreviation")]
+    name: String,
+    #[serde(rename = "SecuritiesIndustryCode")]
+    industry: String,
+    #[serde(rename = "CompanyName", default)]
+    full_name: String,
+    #[serde(rename = "DateOfListing", default)]
+    listed_on: String,
+    #[serde(rename = "Paidin.Capital.NTDollars", default)]
+    paid_in_capital: String,
+    #[serde(rename = "IssueShares", default)]
+    shares_issued: String,
+}
+
+/// 從 TPEx API 抓取上櫃公司代號 → 中文名稱，base_url 是 cfg 的 tpex_openapi_url
+pub async fn fetch(base_url: &str) -> Result<Vec<CompanyInfo>> {
+    let url = format!("{base_url}/mopsfe_t187ap03_O");
+    let resp = reqwest::get(url)
+        .await?
+        .error_for_status()?
+        .json::<Vec<TpexCompanyInfo>>()
+        .await?;
+    // 空的清單多半是 API 出問題，不能拿來把上櫃公司從清單裡刪掉
+    if resp.is_empty() {
+        return Err(Error::Api("TPEx 上櫃公司清單是空的".to_string()));
+    }
+
+    Ok(resp
+        .into_iter()
+        .map(|company| CompanyInfo {
+            stock_no: company.stock_no,
+            name: company.name,
+            industry: company.industry,
+            market: Market::Tpex,
+            full_name: company.full_name,
+            listed_on: company.listed_on,
+            paid_in_capital: company.paid_in_capital,
+            shares_issued: company.shares_issued,
+        })
+        .collect())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::mock_server::{MockResponse, MockServer};
+
+    const LISTED: &str = include_str!("../../fixtures/tpex/mopsfe_t187ap03_O.json");
+
+    #[tokio::test]
+    async fn parses_company_profiles() {
+        let server = MockServer::start(vec![MockResponse::ok(LISTED)]).await;
+
+        let companies = fetch(&server.base_url).await.unwrap();
+
+        assert_eq!(server.requests(), ["/mopsfe_t187ap03_O"]);
+        assert_eq!(companies.len(), 2);
+        let win = &companies[0];
+        assert_eq!(win.stock_no, "3105");
+        assert_eq!(win.name, "穩懋");
+        assert_eq!(win.full_name, "穩懋半導體股份有限公司");
+        assert_eq!(win.industry, "24");
+        assert_eq!(win.market, Market::Tpex);
+        assert_eq!(win.listing_date().as_deref(), Some("2010-12-15"));
+        assert_eq!(win.shares(), Some(423_942_525.0));
+    }
+
+    #[tokio::test]
+    async fn http_error_is_an_error() {
+        let server = MockServer::start(vec![MockResponse::new(500, "")]).await;
+        assert!(matches!(fetch(&server.base_url).await, Err(Error::Http(_))));
+    }
+
+    #[tokio::test]
+    async fn malformed_or_empty_listing_is_an_error() {
+        let malformed = include_str!("../../fixtures/tpex/mopsfe_t187ap03_O_malformed.json");
+        let server = MockServer::start(vec![MockResponse::ok(malformed)]).await;
+        assert!(fetch(&server.base_url).await.is_err());
+
+        let server = MockServer::start(vec![MockResponse::ok("[]")]).await;
+        assert!(matches!(fetch(&server.base_url).await, Err(Error::Api(_))));
+    }
+}
@@ -1,11 +1,56 @@
-use std::error::Error;
-
+use crate::error::Result;
 use crate::stocks::company_map::CompanyInfo;
 
-/// 從 TWSE API 抓取上市公司代號 → 中文名稱
-pub async fn fetch() -> Result<Vec<CompanyInfo>, Box<dyn Error>> {
-    let url = "https://openapi.twse.com.tw/v1/opendata/t187ap03_L";
-    let resp = reqwest::get(url).await?.json::<Vec<CompanyInfo>>().await?;
+/// 從 TWSE API 抓取上市公司基本資料，市場別預設為上市；base_url 是 cfg 的 twse_openapi_url
+pub async fn fetch(base_url: &str) -> Result<Vec<CompanyInfo>> {
+    let url = format!("{base_url}/opendata/t187ap03_L");
+    let resp = reqwest::get(url)
+        .await?
+        .error_for_status()?
+        .json::<Vec<CompanyInfo>>()
+        .await?;
 
     Ok(resp)
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::error::Error;
+    use crate::mock_server::{MockResponse, MockServer};
+    use crate::stocks::company_map::Market;
+
+    const LISTED: &str = include_str!("../../fixtures/twse/t187ap03_L.json");
+
+    #[tokio::test]
+    async fn parses_company_profiles() {
+        let server = MockServer::start(vec![MockResponse::ok(LISTED)]).await;
+
+        let companies = fetch(&server.base_url).await.unwrap();
+
+        assert_eq!(server.requests(), ["/opendata/t187ap03_L"]);
+        assert_eq!(companies.len(), 2);
+        let tsmc = &companies[1];
+        assert_eq!(tsmc.stock_no, "2330");
+        assert_eq!(tsmc.name, "台積電");
+        assert_eq!(tsmc.full_name, "台灣積體電路製造股份有限公司");
+        assert_eq!(tsmc.industry, "24");
+        assert_eq!(tsmc.market, Market::Twse);
+        assert_eq!(tsmc.listing_date().as_deref(), Some("1994-09-05"));
+        assert_eq!(tsmc.shares(), Some(25_932_733_242.0));
+        assert!((tsmc.capital_100m().unwrap() - 2_593.27).abs() < 0.01);
+    }
+
+    #[tokio::test]
+    async fn http_error_is_an_error() {
+        let server = MockServer::start(vec![MockResponse::new(500, "")]).await;
+        assert!(matches!(fetch(&server.base_url).await, Err(Error::Http(_))));
+    }
+
+    #[tokio::test]
+    async fn malformed_json_is_an_error() {
+        let malformed = include_str!("../../fixtures/twse/t187ap03_L_malformed.json");
+        let server = MockServer::start(vec![MockResponse::ok(malformed)]).await;
+        assert!(fetch(&server.base_url).await.is_err());
+    }
+}
@@ -0,0 +1,250 @@
+use chrono::NaiveDate;
+use reqwest::Url;
+use serde::Deserialize;
+use serde_json::Value;
+use tokio::time::Duration;
+
+use crate::error::{Error, Result};
+use crate::stocks::data_company::{StockData, StockDataWithNo};
+use crate::storage::archive::{Archive, ArchivedResponse};
+
+#[derive(Deserialize)]
+struct MiIndexResponse {
+    stat: String,
+    #[serde(default)]
+    tables: Vec<MiIndexTable>,
+}
+
+#[derive(Deserialize)]
+struct MiIndexTable {
+    #[serde(default)]
+    fields: Vec<String>,
+    #[serde(default)]
+    data: Vec<Vec<Value>>,
+}
+
+pub const ARCHIVE_SOURCE: &str = "twse";
+
+/// 送 TWSE 請求用的 client，整個 provider 共用一個
+pub fn client() -> Result<reqwest::Client> {
+    Ok(reqwest::Client::builder()
+        .timeout(Duration::from_secs(30))
+        .build()?)
+}
+
+/// 從 TWSE 每日收盤行情 (MI_INDEX) 抓取某個交易日全部上市股票的日K
+pub async fn fetch(
+    client: &reqwest::Client,
+    base_url: &str,
+    date: NaiveDate,
+    archive: Option<&Archive>,
+) -> Result<Vec<StockDataWithNo>> {
+    let url = format!(
+        "{base_url}/rwd/zh/afterTrading/MI_INDEX?date={}&type=ALLBUT0999&response=json",
+        date.format("%Y%m%d")
+    );
+    let response = client
+        .get(&url)
+        .header("User-Agent", "Mozilla/5.0 (Windows NT 10.0; Win64; x64) AppleWebKit/537.36 (KHTML, like Gecko) Chrome/144.0.0.0 Safari/537.36")
+        .header("Accept", "application/json")
+        .send()
+        .await?;
+    let status = response.status();
+    let headers = response.headers().clone();
+    let body_text = response.text().await?;
+    if let Some(archive) = archive {
+        archive.record(
+            ARCHIVE_SOURCE,
+            &ArchivedResponse::new(&url, status.as_u16(), &headers, &body_text),
+        );
+    }
+    if !status.is_success() {
+        return Err(Error::Api(format!(
+            "TWSE {} HTTP 狀態碼 {status}",
+            date.format("%Y%m%d")
+        )));
+    }
+
+    parse(&body_text, date)
+}
+
+/// 從 fetch 的網址取回交易日，重播封存時用
+pub fn parse_url(url: &str) -> Option<NaiveDate> {
+    let url = Url::parse(url).ok()?;
+    let (_, date) = url.query_pairs().find(|(name, _)| name == "date")?;
+    NaiveDate::parse_from_str(&date, "%Y%m%d").ok()
+}
+
+/// 解析 MI_INDEX 回應，找出「每日收盤行情」那一張表；沒有成交的股票不會出現在結果裡
+pub fn parse(body_text: &str, date: NaiveDate) -> Result<Vec<StockDataWithNo>> {
+    let response: MiIndexResponse = serde_json::from_str(body_text)?;
+    if response.stat != "OK" {
+        return Err(Error::Api(format!(
+            "TWSE {} 沒有收盤行情: {}",
+            date.format("%Y%m%d"),
+            response.stat
+        )));
+    }
+
+    let table = response
+        .tables
+        .iter()
+        .find(|table| table.fields.iter().any(|field| field == "證券代號"))
+        .ok_or_else(|| {
+            Error::Api(format!(
+                "TWSE {} 的回應裡找不到每日收盤行情",
+                date.format("%Y%m%d")
+            ))
+        })?;
+    let column = |name: &str| {
+        table
+            .fields
+            .iter()
+            .position(|field| field == name)
+            .ok_or_else(|| Error::Api(format!("TWSE 每日收盤行情缺少欄位: {name}")))
+    };
+    let stock_no_col = column("證券代號")?;
+    let volume_col = column("成交股數")?;
+    let turnover_col = column("成交金額")?;
+    let open_col = column("開盤價")?;
+    let high_col = column("最高價")?;
+    let low_col = column("最低價")?;
+    let close_col = column("收盤價")?;
+    let sign_col = column("漲跌(+/-)")?;
+    let change_col = column("漲跌價差")?;
+
+    let date = date.format("%Y-%m-%d").to_string();
+    let mut results = Vec::new();
+    for row in &table.data {
+        let cell = |col: usize| row.get(col).map(cell_text).unwrap_or_default();
+        // 沒有成交的股票價格是 "--"
+        let (Some(open), Some(high), Some(low), Some(close)) = (
+            parse_number(&cell(open_col)),
+            parse_number(&cell(high_col)),
+            parse_number(&cell(low_col)),
+            parse_number(&cell(close_col)),
+        ) else {
+            continue;
+        };
+        // 漲跌符號「X」表示不比價 (除權息、減資、新上市等)，漲跌價差欄的 0.00 不是真的漲跌
+        let change = match cell(sign_col).as_str() {
+            "X" => None,
+            "-" => parse_number(&cell(change_col)).map(|change| -change),
+            _ => parse_number(&cell(change_col)),
+        };
+
+        results.push(StockDataWithNo {
+            stock_no: cell(stock_no_col),
+            stock_data: StockData {
+                date: date.clone(),
+                open,
+                high,
+                low,
+                close,
+                volume: parse_number(&cell(volume_col)).unwrap_or_default() as u64,
+                turnover: parse_number(&cell(turnover_col)).unwrap_or_default() as u64,
+                change,
+            },
+        });
+    }
+
+    Ok(results)
+}
+
+/// 欄位值可能是字串或 HTML (漲跌符號)，只取出文字部分
+fn cell_text(value: &Value) -> String {
+    let text = match value {
+        Value::String(text) => text.clone(),
+        other => other.to_string(),
+    };
+    let mut plain = String::new();
+    let mut in_tag = false;
+    for c in text.chars() {
+        match c {
+            '<' => in_tag = true,
+            '>' => in_tag = false,
+            c if !in_tag => plain.push(c),
+            _ => {}
+        }
+    }
+    plain.trim().to_string()
+}
+
+fn parse_number(text: &str) -> Option<f64> {
+    text.replace(',', "").parse().ok()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::mock_server::{MockResponse, MockServer};
+
+    const TRADING_DAY: &str = include_str!("../../fixtures/twse/mi_index_20260211.json");
+    const HOLIDAY: &str = include_str!("../../fixtures/twse/mi_index_20260214.json");
+
+    fn date(ymd: &str) -> NaiveDate {
+        NaiveDate::parse_from_str(ymd, "%Y%m%d").unwrap()
+    }
+
+    fn find<'a>(results: &'a [StockDataWithNo], stock_no: &str) -> &'a StockData {
+        &results
+            .iter()
+            .find(|r| r.stock_no == stock_no)
+            .unwrap()
+            .stock_data
+    }
+
+    #[test]
+    fn parses_whole_market_quotes() {
+        let results = parse(TRADING_DAY, date("20260211")).unwrap();
+
+        let tsmc = find(&results, "2330");
+        assert_eq!(tsmc.date, "2026-02-11");
+        assert_eq!(tsmc.open, 1880.0);
+        assert_eq!(tsmc.high, 1925.0);
+        assert_eq!(tsmc.low, 1875.0);
+        assert_eq!(tsmc.close, 1915.0);
+        assert_eq!(tsmc.volume, 44_684_131);
+        assert_eq!(tsmc.turnover, 85_272_506_875);
+        assert_eq!(tsmc.change, Some(35.0));
+    }
+
+    #[test]
+    fn negative_change_uses_sign_column() {
+        let results = parse(TRADING_DAY, date("20260211")).unwrap();
+        assert_eq!(find(&results, "3665").change, Some(-15.0));
+    }
+
+    #[test]
+    fn not_comparable_has_no_change() {
+        let results = parse(TRADING_DAY, date("20260211")).unwrap();
+        assert_eq!(find(&results, "6924").change, None);
+    }
+
+    #[test]
+    fn skips_stocks_without_trades() {
+        let results = parse(TRADING_DAY, date("20260211")).unwrap();
+        assert!(results.iter().all(|r| r.stock_no != "1213"));
+        assert_eq!(results.len(), 5);
+    }
+
+    #[test]
+    fn holiday_is_an_error() {
+        assert!(parse(HOLIDAY, date("20260214")).is_err());
+    }
+
+    #[tokio::test]
+    async fn fetches_from_the_configured_base_url() {
+        let server = MockServer::start(vec![MockResponse::ok(TRADING_DAY)]).await;
+
+        let results = fetch(&client().unwrap(), &server.base_url, date("20260211"), None)
+            .await
+            .unwrap();
+
+        assert_eq!(
+            server.requests(),
+            ["/rwd/zh/afterTrading/MI_INDEX?date=20260211&type=ALLBUT0999&response=json"]
+        );
+        assert_eq!(results.len(), 5);
+    }
+}
@@ -1 +1,2 @@
 pub mod company_map;
+pub mod daily_quotes;